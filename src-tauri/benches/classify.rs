@@ -0,0 +1,53 @@
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use std::hint::black_box;
+use textgo_lib::classify::{classify, is_match};
+
+/// 整段文本为单一类型的短文本
+const SHORT_SAMPLES: &[(&str, &str)] = &[
+    ("uuid", "123e4567-e89b-42d3-a456-426614174000"),
+    ("url", "https://www.example.com/path?q=1"),
+    ("ipv6", "2001:db8::8a2e:370:7334"),
+    ("iso8601", "2024-02-29T12:30:00.123+08:00"),
+    ("camel_case", "textGoApp"),
+];
+
+/// 包含链接、邮箱和地址的一段文字
+const PARAGRAPH: &str = "Deploy notes: the build from 2024-02-29T12:30:00Z is live at \
+https://www.example.com/releases/42, mirrored on 10.0.0.12 and 2001:db8::8a2e:370:7334. \
+Ping ops@example.com with the request id 123e4567-e89b-42d3-a456-426614174000 or check \
+/var/log/textgo/app.log if anything looks off. ";
+
+fn short_text(c: &mut Criterion) {
+    let mut group = c.benchmark_group("classify_short");
+    for (case, text) in SHORT_SAMPLES {
+        group.bench_with_input(BenchmarkId::from_parameter(case), text, |b, text| {
+            b.iter(|| classify(black_box(text)))
+        });
+    }
+    group.finish();
+}
+
+fn long_text(c: &mut Criterion) {
+    let mut group = c.benchmark_group("classify_long");
+    for repeat in [1, 16, 64] {
+        let text = PARAGRAPH.repeat(repeat);
+        group.throughput(Throughput::Bytes(text.len() as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(text.len()), &text, |b, text| {
+            b.iter(|| classify(black_box(text)))
+        });
+    }
+    group.finish();
+}
+
+fn single_case(c: &mut Criterion) {
+    let mut group = c.benchmark_group("is_match");
+    for (case, text) in SHORT_SAMPLES {
+        group.bench_with_input(BenchmarkId::from_parameter(case), text, |b, text| {
+            b.iter(|| is_match(black_box(case), black_box(text)))
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, short_text, long_text, single_case);
+criterion_main!(benches);
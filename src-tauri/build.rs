@@ -1,3 +1,49 @@
+/// 应用程序命令，在能力配置中按窗口授权
+const COMMANDS: &[&str] = &[
+    "show_main_window",
+    "hide_main_window",
+    "toggle_main_window",
+    "goto_shortcuts",
+    "register_shortcut",
+    "unregister_shortcut",
+    "is_shortcut_registered",
+    "is_editable",
+    "get_selection",
+    "execute_script",
+    "detect_runtimes",
+    "install_dependencies",
+    "run_script_tests",
+    "query_audit_log",
+    "clear_audit_log",
+    "get_script_library",
+    "set_scripts_dir",
+    "set_secret",
+    "delete_secret",
+    "has_secret",
+    "execute_command",
+    "deliver_result",
+    "rerun_action",
+    "clear_cache",
+    "stream_chat",
+    "chat_session",
+    "get_session",
+    "delete_session",
+    "classify_text",
+    "render_template",
+    "validate_template",
+    "cancel_execution",
+    "send_copy_key",
+    "send_paste_key",
+    "show_popup",
+    "update_popup",
+    "show_about",
+    "setup_tray",
+];
+
 fn main() {
-    tauri_build::build()
+    tauri_build::try_build(
+        tauri_build::Attributes::new()
+            .app_manifest(tauri_build::AppManifest::new().commands(COMMANDS)),
+    )
+    .expect("failed to run tauri-build");
 }
@@ -0,0 +1,188 @@
+use crate::error::AppError;
+use crate::RESPONSE_CACHE;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::path::PathBuf;
+use std::time::SystemTime;
+use tauri::Manager;
+
+/// 保存缓存的目录，每条缓存一个 JSON 文件
+const CACHE_DIR: &str = "cache";
+
+/// 默认的缓存有效期 (小时)
+pub const DEFAULT_CACHE_TTL_HOURS: u64 = 24 * 7;
+
+/// 默认的缓存大小上限 (MiB)
+pub const DEFAULT_CACHE_SIZE_MB: u64 = 50;
+
+/// 响应缓存选项
+#[derive(Debug, Clone, Default)]
+pub struct CacheOptions {
+    /// 是否启用缓存
+    pub enabled: bool,
+    /// 缓存有效期 (小时)，为空时使用默认有效期
+    pub ttl_hours: Option<u64>,
+    /// 缓存大小上限 (MiB)，为空时使用默认上限
+    pub max_size_mb: Option<u64>,
+}
+
+/// 缓存的执行结果
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CachedOutput {
+    /// 动作标识
+    action: String,
+    /// 缓存时间
+    created_at: String,
+    /// 执行结果
+    output: String,
+}
+
+/// 计算缓存键
+///
+/// 由动作标识、动作定义 (即动作版本) 和输入文本的哈希组成，
+/// 动作被修改后原有的缓存不再命中
+pub fn cache_key(action: &str, version: &impl Serialize, input: &str) -> String {
+    let version = serde_json::to_vec(version).unwrap_or_default();
+    let mut hasher = Sha256::new();
+    for part in [
+        action.as_bytes(),
+        &Sha256::digest(version),
+        &Sha256::digest(input),
+    ] {
+        hasher.update((part.len() as u64).to_le_bytes());
+        hasher.update(part);
+    }
+    hasher
+        .finalize()
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+/// 查找缓存的执行结果
+///
+/// 过期的缓存会被删除，命中时更新文件的修改时间，使其最后被淘汰
+pub fn lookup(app: &tauri::AppHandle, options: &CacheOptions, key: &str) -> Option<String> {
+    let path = cache_file(app, key)
+        .map_err(|e| eprintln!("[WARNING] Response cache is not available: {}", e))
+        .ok()?;
+    let _guard = RESPONSE_CACHE.lock().ok()?;
+    let cached: CachedOutput = match std::fs::read(&path) {
+        Ok(bytes) => serde_json::from_slice(&bytes)
+            .map_err(|e| eprintln!("[WARNING] Failed to parse cached output: {}", e))
+            .ok()?,
+        Err(_) => return None,
+    };
+    let ttl = chrono::Duration::hours(options.ttl_hours.unwrap_or(DEFAULT_CACHE_TTL_HOURS) as i64);
+    let expired = chrono::DateTime::parse_from_rfc3339(&cached.created_at)
+        .map_or(true, |created_at| {
+            chrono::Utc::now() - created_at.to_utc() > ttl
+        });
+    if expired {
+        let _ = std::fs::remove_file(&path);
+        return None;
+    }
+    if let Err(e) = std::fs::File::options()
+        .append(true)
+        .open(&path)
+        .and_then(|file| file.set_modified(SystemTime::now()))
+    {
+        eprintln!("[WARNING] Failed to touch cached output: {}", e);
+    }
+    eprintln!("[INFO] Using cached output of {}", cached.action);
+    Some(cached.output)
+}
+
+/// 保存执行结果，并按最近使用的顺序淘汰超出大小上限的缓存
+///
+/// 保存失败只记录日志，不影响动作的执行结果
+pub fn store(
+    app: &tauri::AppHandle,
+    options: &CacheOptions,
+    key: &str,
+    action: &str,
+    output: &str,
+) {
+    if let Err(e) = try_store(app, options, key, action, output) {
+        eprintln!("[WARNING] Failed to cache output of {}: {}", action, e);
+    }
+}
+
+/// 保存执行结果
+fn try_store(
+    app: &tauri::AppHandle,
+    options: &CacheOptions,
+    key: &str,
+    action: &str,
+    output: &str,
+) -> Result<(), AppError> {
+    let path = cache_file(app, key)?;
+    let cached = CachedOutput {
+        action: action.to_string(),
+        created_at: chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true),
+        output: output.to_string(),
+    };
+    let _guard = RESPONSE_CACHE.lock()?;
+    let dir = cache_dir(app)?;
+    std::fs::create_dir_all(&dir)?;
+    std::fs::write(&path, serde_json::to_vec(&cached)?)?;
+
+    let mut files: Vec<_> = std::fs::read_dir(&dir)?
+        .filter_map(Result::ok)
+        .filter_map(|entry| {
+            let metadata = entry.metadata().ok()?;
+            Some((metadata.modified().ok()?, metadata.len(), entry.path()))
+        })
+        .collect();
+    let limit = options.max_size_mb.unwrap_or(DEFAULT_CACHE_SIZE_MB) * 1024 * 1024;
+    let mut size: u64 = files.iter().map(|(_, len, _)| len).sum();
+    files.sort();
+    for (_, len, path) in &files {
+        if size <= limit {
+            break;
+        }
+        if let Err(e) = std::fs::remove_file(path) {
+            eprintln!("[WARNING] Failed to remove cached output: {}", e);
+        }
+        size = size.saturating_sub(*len);
+    }
+    Ok(())
+}
+
+/// 删除一条缓存，不存在时忽略
+pub fn remove(app: &tauri::AppHandle, key: &str) -> Result<(), AppError> {
+    let path = cache_file(app, key)?;
+    let _guard = RESPONSE_CACHE.lock()?;
+    match std::fs::remove_file(path) {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+        _ => Ok(()),
+    }
+}
+
+/// 清空全部缓存
+pub fn clear(app: &tauri::AppHandle) -> Result<(), AppError> {
+    let dir = cache_dir(app)?;
+    let _guard = RESPONSE_CACHE.lock()?;
+    match std::fs::remove_dir_all(dir) {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+        _ => Ok(()),
+    }
+}
+
+/// 保存缓存的目录
+fn cache_dir(app: &tauri::AppHandle) -> Result<PathBuf, AppError> {
+    let dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("App data directory is not available: {}", e))?;
+    Ok(dir.join(CACHE_DIR))
+}
+
+/// 缓存文件路径，缓存键只能是十六进制的哈希值
+fn cache_file(app: &tauri::AppHandle, key: &str) -> Result<PathBuf, AppError> {
+    if key.is_empty() || !key.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(format!("Invalid cache key '{}'", key).into());
+    }
+    Ok(cache_dir(app)?.join(format!("{}.json", key)))
+}
@@ -0,0 +1,189 @@
+use fancy_regex::Regex;
+use std::sync::LazyLock;
+
+/// 内置文本类型
+#[derive(Debug)]
+pub struct BuiltinCase {
+    /// 类型标识，与前端 `matcher.ts` 中的选项值一致
+    pub id: &'static str,
+    /// 不含首尾锚点的正则模式
+    pattern: &'static str,
+    /// 是否忽略大小写
+    ignore_case: bool,
+    /// 是否在文本中查找嵌入的片段，命名格式等只描述整段文本的类型不查找
+    embedded: bool,
+    /// 整段文本匹配时的置信度，格式较宽泛、容易误判的类型较低
+    pub weight: f64,
+}
+
+/// 编译后的内置文本类型
+pub(super) struct CompiledCase {
+    /// 类型定义
+    pub case: &'static BuiltinCase,
+    /// 匹配整段文本的正则
+    pub whole: Regex,
+    /// 查找嵌入片段的正则
+    pub embedded: Option<Regex>,
+}
+
+/// 内置文本类型，按识别选项的顺序排列，模式与前端 `matcher.ts` 保持一致
+pub static BUILTIN_CASES: &[BuiltinCase] = &[
+    BuiltinCase {
+        id: "numbers",
+        pattern: r"[0-9]+",
+        ignore_case: false,
+        embedded: false,
+        weight: 1.0,
+    },
+    // 等价于 JS 中的 [a-z0-9_\W]
+    BuiltinCase {
+        id: "small_letters",
+        pattern: r"(?=.*[a-z])[^A-Z]+",
+        ignore_case: false,
+        embedded: false,
+        weight: 1.0,
+    },
+    BuiltinCase {
+        id: "capital_letters",
+        pattern: r"(?=.*[A-Z])[^a-z]+",
+        ignore_case: false,
+        embedded: false,
+        weight: 1.0,
+    },
+    BuiltinCase {
+        id: "uuid",
+        pattern: r"[0-9a-f]{8}-[0-9a-f]{4}-[0-5][0-9a-f]{3}-[089ab][0-9a-f]{3}-[0-9a-f]{12}",
+        ignore_case: true,
+        embedded: true,
+        weight: 1.0,
+    },
+    BuiltinCase {
+        id: "guid",
+        pattern: r"\{?[0-9a-f]{8}-[0-9a-f]{4}-[0-9a-f]{4}-[0-9a-f]{4}-[0-9a-f]{12}\}?",
+        ignore_case: true,
+        embedded: true,
+        weight: 0.9,
+    },
+    BuiltinCase {
+        id: "url",
+        pattern: r"https?://(?:www\.)?[-a-zA-Z0-9@:%._+~#=]{1,256}\.[a-zA-Z0-9()]{1,6}\b[-a-zA-Z0-9()@:%_+.~#?&/=]*",
+        ignore_case: false,
+        embedded: true,
+        weight: 1.0,
+    },
+    BuiltinCase {
+        id: "email",
+        pattern: r"[a-z0-9!#$%&'*+/=?^_`{|}~-]+(?:\.[a-z0-9!#$%&'*+/=?^_`{|}~-]+)*@(?:[a-z0-9](?:[a-z0-9-]*[a-z0-9])?\.)+[a-z0-9](?:[a-z0-9-]*[a-z0-9])?",
+        ignore_case: true,
+        embedded: true,
+        weight: 1.0,
+    },
+    BuiltinCase {
+        id: "path",
+        pattern: r#"[a-zA-Z]:\\[^<>:"|?*\n\r/]+(?:\\[^<>:"|?*\n\r/]+)*|~?/[^<>:"|?*\n\r\\]+(?:/[^<>:"|?*\n\r\\]+)*"#,
+        ignore_case: false,
+        embedded: true,
+        weight: 0.8,
+    },
+    BuiltinCase {
+        id: "ipv4",
+        pattern: r"(?:25[0-5]|2[0-4][0-9]|[01]?[0-9][0-9]?)\.(?:25[0-5]|2[0-4][0-9]|[01]?[0-9][0-9]?)\.(?:25[0-5]|2[0-4][0-9]|[01]?[0-9][0-9]?)\.(?:25[0-5]|2[0-4][0-9]|[01]?[0-9][0-9]?)",
+        ignore_case: false,
+        embedded: true,
+        weight: 1.0,
+    },
+    BuiltinCase {
+        id: "ipv6",
+        pattern: r"(?:[0-9a-f]{1,4}:){7}[0-9a-f]{1,4}|(?:[0-9a-f]{1,4}:){1,7}:|(?:[0-9a-f]{1,4}:){1,6}:[0-9a-f]{1,4}|(?:[0-9a-f]{1,4}:){1,5}(?::[0-9a-f]{1,4}){1,2}|(?:[0-9a-f]{1,4}:){1,4}(?::[0-9a-f]{1,4}){1,3}|(?:[0-9a-f]{1,4}:){1,3}(?::[0-9a-f]{1,4}){1,4}|(?:[0-9a-f]{1,4}:){1,2}(?::[0-9a-f]{1,4}){1,5}|[0-9a-f]{1,4}:(?::[0-9a-f]{1,4}){1,6}|:(?:(?::[0-9a-f]{1,4}){1,7}|:)|fe80:(?::[0-9a-f]{0,4}){0,4}%[0-9a-z]+|::(?:ffff(?::0{1,4})?:)?(?:(?:25[0-5]|(?:2[0-4]|1?[0-9])?[0-9])\.){3}(?:25[0-5]|(?:2[0-4]|1?[0-9])?[0-9])|(?:[0-9a-f]{1,4}:){1,4}:(?:(?:25[0-5]|(?:2[0-4]|1?[0-9])?[0-9])\.){3}(?:25[0-5]|(?:2[0-4]|1?[0-9])?[0-9])",
+        ignore_case: true,
+        embedded: true,
+        weight: 1.0,
+    },
+    BuiltinCase {
+        id: "info_hash",
+        pattern: r"[0-9a-zA-Z]{32}|[0-9a-fA-F]{40}|1220[0-9a-fA-F]{64}",
+        ignore_case: false,
+        embedded: true,
+        weight: 0.6,
+    },
+    BuiltinCase {
+        id: "iso8601",
+        pattern: r"(?:(?:[0-9][0-9][2468][048]|[0-9][0-9][13579][26]|[0-9][0-9]0[48]|[02468][048]00|[13579][26]00)-02-29|[0-9]{4}-(?:(?:0[13578]|1[02])-(?:0[1-9]|[12][0-9]|3[01])|(?:0[469]|11)-(?:0[1-9]|[12][0-9]|30)|02-(?:0[1-9]|1[0-9]|2[0-8])))T(?:[01][0-9]|2[0-3]):[0-5][0-9]:[0-5][0-9](?:\.[0-9]+)?(?:[+-](?:[01][0-9]|2[0-3]):[0-5][0-9]|Z)?",
+        ignore_case: false,
+        embedded: true,
+        weight: 1.0,
+    },
+    // 2001 ~ 2286 年间10位秒级或13位毫秒级的 Unix 时间戳
+    BuiltinCase {
+        id: "timestamp",
+        pattern: r"[1-9][0-9]{9}|[1-9][0-9]{12}",
+        ignore_case: false,
+        embedded: true,
+        weight: 0.8,
+    },
+    BuiltinCase {
+        id: "camel_case",
+        pattern: r"[a-z][a-z0-9]*(?:[A-Z][a-z0-9]*)+",
+        ignore_case: false,
+        embedded: false,
+        weight: 1.0,
+    },
+    BuiltinCase {
+        id: "pascal_case",
+        pattern: r"[A-Z]+[a-z0-9]+(?:[A-Z][a-z0-9]*)+|[A-Z]{2,}[a-z0-9]+",
+        ignore_case: false,
+        embedded: false,
+        weight: 0.9,
+    },
+    BuiltinCase {
+        id: "snake_case",
+        pattern: r"[a-z0-9]+(?:_[a-z0-9]+)+",
+        ignore_case: false,
+        embedded: false,
+        weight: 1.0,
+    },
+    BuiltinCase {
+        id: "kebab_case",
+        pattern: r"[a-z0-9]+(?:-[a-z0-9]+)+",
+        ignore_case: false,
+        embedded: false,
+        weight: 1.0,
+    },
+    BuiltinCase {
+        id: "lower_case",
+        pattern: r"(?=.*[a-z])[a-z0-9]+(?: [a-z0-9]+)*",
+        ignore_case: false,
+        embedded: false,
+        weight: 1.0,
+    },
+    BuiltinCase {
+        id: "upper_case",
+        pattern: r"(?=.*[A-Z])[A-Z0-9]+(?: [A-Z0-9]+)*",
+        ignore_case: false,
+        embedded: false,
+        weight: 1.0,
+    },
+];
+
+/// 编译后的内置文本类型，与 `BUILTIN_CASES` 的顺序相同
+pub(super) static COMPILED_CASES: LazyLock<Vec<CompiledCase>> = LazyLock::new(|| {
+    BUILTIN_CASES
+        .iter()
+        .map(|case| {
+            let flags = if case.ignore_case { "(?i)" } else { "" };
+            let compile = |source: String| Regex::new(&source).expect("invalid builtin pattern");
+            CompiledCase {
+                case,
+                whole: compile(format!("{}^(?:{})$", flags, case.pattern)),
+                embedded: case
+                    .embedded
+                    .then(|| compile(format!("{}(?:{})", flags, case.pattern))),
+            }
+        })
+        .collect()
+});
+
+/// 根据标识查找内置文本类型
+pub fn builtin_case(id: &str) -> Option<&'static BuiltinCase> {
+    BUILTIN_CASES.iter().find(|case| case.id == id)
+}
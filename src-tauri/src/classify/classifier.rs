@@ -0,0 +1,263 @@
+use super::cases::COMPILED_CASES;
+use fancy_regex::Regex;
+use serde::Serialize;
+
+/// 文本中匹配的片段，偏移按字符计算
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct Span {
+    /// 起始位置
+    pub start: usize,
+    /// 结束位置 (不含)
+    pub end: usize,
+}
+
+/// 一种文本类型的识别结果
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Classification {
+    /// 文本类型标识
+    pub case: &'static str,
+    /// 是否整段文本都是该类型
+    pub exact: bool,
+    /// 置信度 (0 ~ 1)
+    pub confidence: f64,
+    /// 匹配的片段
+    pub spans: Vec<Span>,
+}
+
+/// 识别文本包含的全部内置文本类型
+///
+/// 整段文本匹配时以类型的权重作为置信度；否则查找文本中嵌入的片段，
+/// 置信度按片段覆盖的非空白字符比例折算。结果按置信度从高到低排列
+pub fn classify(text: &str) -> Vec<Classification> {
+    if text.is_empty() {
+        return Vec::new();
+    }
+    let length = text.chars().count();
+    let content = text.chars().filter(|c| !c.is_whitespace()).count().max(1);
+
+    let mut results = Vec::new();
+    for compiled in COMPILED_CASES.iter() {
+        let case = compiled.case;
+        if compiled.whole.is_match(text).unwrap_or(false) {
+            results.push(Classification {
+                case: case.id,
+                exact: true,
+                confidence: case.weight,
+                spans: vec![Span {
+                    start: 0,
+                    end: length,
+                }],
+            });
+            continue;
+        }
+        let Some(pattern) = &compiled.embedded else {
+            continue;
+        };
+        let spans = find_spans(pattern, text);
+        if spans.is_empty() {
+            continue;
+        }
+        let covered: usize = spans.iter().map(|span| span.end - span.start).sum();
+        results.push(Classification {
+            case: case.id,
+            exact: false,
+            confidence: case.weight * (covered as f64 / content as f64).min(1.0),
+            spans,
+        });
+    }
+    results.sort_by(|a, b| b.confidence.total_cmp(&a.confidence));
+    results
+}
+
+/// 判断整段文本是否为指定的内置文本类型，不是内置类型时返回 `None`
+pub fn is_match(case: &str, text: &str) -> Option<bool> {
+    COMPILED_CASES
+        .iter()
+        .find(|compiled| compiled.case.id == case)
+        .map(|compiled| compiled.whole.is_match(text).unwrap_or(false))
+}
+
+/// 查找前后不与字母、数字或下划线相连的片段
+fn find_spans(pattern: &Regex, text: &str) -> Vec<Span> {
+    let mut spans = Vec::new();
+    let mut cursor = CharCursor::new(text);
+    let mut pos = 0;
+    while pos < text.len() {
+        let Ok(Some(found)) = pattern.find_from_pos(text, pos) else {
+            break;
+        };
+        let (start, end) = (found.start(), found.end());
+        let before = text[..start].chars().next_back();
+        let after = text[end..].chars().next();
+        if end > start && !before.is_some_and(is_word) && !after.is_some_and(is_word) {
+            spans.push(Span {
+                start: cursor.advance(start),
+                end: cursor.advance(end),
+            });
+            pos = end;
+        } else {
+            // 从下一个字符开始重新查找
+            pos = start + text[start..].chars().next().map_or(1, char::len_utf8);
+        }
+    }
+    spans
+}
+
+/// 是否为组成单词的字符
+fn is_word(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+/// 按顺序将字节偏移转换为字符偏移
+struct CharCursor<'a> {
+    /// 文本
+    text: &'a str,
+    /// 当前的字节偏移
+    byte: usize,
+    /// 当前的字符偏移
+    char: usize,
+}
+
+impl<'a> CharCursor<'a> {
+    fn new(text: &'a str) -> Self {
+        Self {
+            text,
+            byte: 0,
+            char: 0,
+        }
+    }
+
+    /// 前进到指定的字节偏移，返回对应的字符偏移
+    fn advance(&mut self, byte: usize) -> usize {
+        self.char += self.text[self.byte..byte].chars().count();
+        self.byte = byte;
+        self.char
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 查找指定类型的识别结果
+    fn find<'a>(results: &'a [Classification], case: &str) -> Option<&'a Classification> {
+        results.iter().find(|result| result.case == case)
+    }
+
+    #[test]
+    fn matches_whole_text_of_each_builtin_case() {
+        let samples = [
+            ("numbers", "20240101"),
+            ("small_letters", "hello, world!"),
+            ("capital_letters", "HELLO, WORLD!"),
+            ("uuid", "123e4567-e89b-42d3-a456-426614174000"),
+            ("guid", "{123E4567-E89B-72D3-A456-426614174000}"),
+            ("url", "https://www.example.com/path?q=1"),
+            ("email", "someone@example.co.uk"),
+            ("path", "/usr/local/bin"),
+            ("path", r"C:\Users\textgo\notes.txt"),
+            ("ipv4", "192.168.0.1"),
+            ("ipv6", "2001:db8::8a2e:370:7334"),
+            ("info_hash", "c12fe1c06bba254a9dc9f519b335aa7c1367a88a"),
+            ("iso8601", "2024-02-29T12:30:00.123+08:00"),
+            ("timestamp", "1700000000000"),
+            ("camel_case", "textGoApp"),
+            ("pascal_case", "TextGoApp"),
+            ("snake_case", "text_go_app"),
+            ("kebab_case", "text-go-app"),
+            ("lower_case", "text go app"),
+            ("upper_case", "TEXT GO APP"),
+        ];
+        for (case, text) in samples {
+            assert_eq!(
+                is_match(case, text),
+                Some(true),
+                "{} should match {}",
+                text,
+                case
+            );
+            let results = classify(text);
+            let result = find(&results, case).unwrap_or_else(|| panic!("{} not found", case));
+            assert!(result.exact);
+            assert_eq!(
+                result.spans,
+                vec![Span {
+                    start: 0,
+                    end: text.chars().count()
+                }]
+            );
+        }
+    }
+
+    #[test]
+    fn rejects_invalid_values() {
+        assert_eq!(is_match("ipv4", "256.1.1.1"), Some(false));
+        assert_eq!(is_match("iso8601", "2023-02-29T12:00:00Z"), Some(false));
+        assert_eq!(
+            is_match("uuid", "123e4567-e89b-62d3-a456-426614174000"),
+            Some(false)
+        );
+        assert_eq!(is_match("timestamp", "0700000000"), Some(false));
+        assert_eq!(is_match("camel_case", "TextGo"), Some(false));
+        assert_eq!(is_match("snake_case", "text_Go"), Some(false));
+    }
+
+    #[test]
+    fn returns_none_for_non_builtin_cases() {
+        assert_eq!(is_match("eng", "hello"), None);
+        assert_eq!(is_match("regexp-custom", "hello"), None);
+    }
+
+    #[test]
+    fn finds_embedded_spans_in_character_offsets() {
+        let text = "访问 https://example.com 或发邮件到 a@b.io";
+        let results = classify(text);
+        let url = find(&results, "url").expect("url not found");
+        assert!(!url.exact);
+        assert_eq!(url.spans, vec![Span { start: 3, end: 22 }]);
+        let email = find(&results, "email").expect("email not found");
+        assert_eq!(email.spans, vec![Span { start: 29, end: 35 }]);
+        assert!(url.confidence > email.confidence);
+        assert!(url.confidence < 1.0);
+    }
+
+    #[test]
+    fn finds_every_occurrence() {
+        let results = classify("ping 10.0.0.1 and 10.0.0.2");
+        let ipv4 = find(&results, "ipv4").expect("ipv4 not found");
+        assert_eq!(
+            ipv4.spans,
+            vec![Span { start: 5, end: 13 }, Span { start: 18, end: 26 }]
+        );
+    }
+
+    #[test]
+    fn ignores_fragments_inside_words() {
+        let results = classify("version1.2.3.4 build");
+        assert!(find(&results, "ipv4").is_none());
+        let results = classify("id 17000000001");
+        assert!(find(&results, "timestamp").is_none());
+    }
+
+    #[test]
+    fn does_not_search_naming_cases_inside_text() {
+        let results = classify("rename text_go_app to text-go-app");
+        assert!(find(&results, "snake_case").is_none());
+        assert!(find(&results, "kebab_case").is_none());
+        assert!(find(&results, "small_letters").is_some_and(|r| r.exact));
+    }
+
+    #[test]
+    fn orders_results_by_confidence() {
+        let results = classify("1700000000");
+        let cases: Vec<_> = results.iter().map(|result| result.case).collect();
+        assert_eq!(cases, vec!["numbers", "timestamp"]);
+        assert_eq!(results[1].confidence, 0.8);
+    }
+
+    #[test]
+    fn returns_nothing_for_empty_text() {
+        assert!(classify("").is_empty());
+    }
+}
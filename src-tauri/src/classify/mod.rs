@@ -0,0 +1,5 @@
+mod cases;
+mod classifier;
+
+pub use cases::*;
+pub use classifier::*;
@@ -0,0 +1,19 @@
+use crate::error::AppError;
+use crate::script::{self, AuditQuery, AuditRecord};
+
+/// 查询脚本和命令行动作的执行记录，按时间从新到旧返回
+#[tauri::command]
+pub fn query_audit_log(
+    app: tauri::AppHandle,
+    query: AuditQuery,
+) -> Result<Vec<AuditRecord>, AppError> {
+    script::query_audit(&app, &query)
+}
+
+/// 清除执行记录，指定动作时只清除该动作的记录
+///
+/// 返回清除的记录数量
+#[tauri::command]
+pub fn clear_audit_log(app: tauri::AppHandle, action: Option<String>) -> Result<usize, AppError> {
+    script::clear_audit(&app, action.as_deref())
+}
@@ -0,0 +1,8 @@
+use crate::cache;
+use crate::error::AppError;
+
+/// 清空动作执行结果的缓存
+#[tauri::command]
+pub async fn clear_cache(app: tauri::AppHandle) -> Result<(), AppError> {
+    cache::clear(&app)
+}
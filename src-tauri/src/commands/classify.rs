@@ -0,0 +1,7 @@
+use crate::classify::{self, Classification};
+
+/// 识别文本包含的全部内置文本类型，返回各类型匹配的片段和置信度
+#[tauri::command]
+pub fn classify_text(text: String) -> Vec<Classification> {
+    classify::classify(&text)
+}
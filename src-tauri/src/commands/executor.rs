@@ -1,313 +1,130 @@
+use crate::engine::{self, ChainState, Config, Entry, Rule};
 use crate::error::AppError;
-use std::process::Stdio;
-use tokio::{io::AsyncWriteExt, process::Command};
+use crate::script::{
+    self, CommandAction, Data, Execution, RuntimeStatus, ScriptAction, ScriptOptions, TestResult,
+};
+use std::sync::Arc;
+use tauri::ipc::Channel;
+use tauri::Manager;
+
+/// 取消正在运行的脚本
+///
+/// 返回是否找到对应的执行
+#[tauri::command]
+pub fn cancel_execution(id: String) -> Result<bool, AppError> {
+    script::cancel(&id)
+}
 
+/// 执行命令行动作
+///
+/// 启动时通过 `on_start` 通道返回执行标识，可用于取消执行
+/// 运行期间的输出通过 `execution-output` 事件逐行发送
 #[tauri::command]
-pub async fn execute_javascript(
-    code: String,
-    data: String,
-    node_path: Option<String>,
+pub async fn execute_command(
+    app: tauri::AppHandle,
+    command: CommandAction,
+    data: Data,
+    on_start: Channel<String>,
 ) -> Result<String, AppError> {
-    // 创建 JavaScript 代码包装
-    let wrapped_code = format!(
-        r#"
-const data = {};
-{}
-const result = process(data);
-console.log(typeof result === 'string' ? result : JSON.stringify(result));
-        "#,
-        data, code
-    );
-
-    // 如果提供了自定义路径，直接使用
-    if let Some(program) = node_path {
-        if !program.is_empty() {
-            // 在 Windows 上，如果是 .bat 文件，需要特殊处理
-            #[cfg(target_os = "windows")]
-            let use_stdin = program.to_lowercase().ends_with(".bat");
-            #[cfg(not(target_os = "windows"))]
-            let use_stdin = false;
-
-            let mut command = if use_stdin {
-                // 对于 .bat 文件，通过 stdin 传递代码，避免参数转义问题
-                let mut cmd = Command::new(&program);
-                cmd.stdin(Stdio::piped());
-                cmd
-            } else {
-                // 对于普通可执行文件，使用 -e 参数
-                let mut cmd = Command::new(&program);
-                cmd.arg("-e").arg(&wrapped_code);
-                cmd
-            };
-
-            command.stdout(Stdio::piped()).stderr(Stdio::piped());
-
-            match command.spawn() {
-                Ok(mut child) => {
-                    // 如果使用 stdin，写入代码
-                    if use_stdin {
-                        if let Some(mut stdin) = child.stdin.take() {
-                            stdin.write_all(wrapped_code.as_bytes()).await?;
-                            drop(stdin); // 关闭 stdin
-                        }
-                    }
-
-                    let output = child.wait_with_output().await?;
-
-                    if output.status.success() {
-                        let stdout = String::from_utf8_lossy(&output.stdout);
-                        return Ok(stdout.trim().to_string());
-                    } else {
-                        let stderr = String::from_utf8_lossy(&output.stderr);
-                        return Err(format!("JavaScript execution failed: {}", stderr).into());
-                    }
-                }
-                Err(e) => {
-                    return Err(
-                        format!("Failed to execute custom path '{}': {}", program, e).into(),
-                    );
-                }
-            }
-        }
-    }
-
-    // 获取用户主目录
-    #[cfg(target_os = "windows")]
-    let home = std::env::var("USERPROFILE").unwrap_or_default();
-    #[cfg(not(target_os = "windows"))]
-    let home = std::env::var("HOME").unwrap_or_default();
-
-    // 常见的 JavaScript 运行环境路径
-    #[cfg(target_os = "windows")]
-    let paths: Vec<String> = {
-        vec![
-            "C:\\Program Files\\nodejs".to_string(),
-            "C:\\Program Files (x86)\\nodejs".to_string(),
-            format!("{}\\AppData\\Local\\Programs\\nodejs", home),
-            format!("{}\\AppData\\Roaming\\npm", home),
-            format!("{}\\.deno\\bin", home),
-        ]
-    };
-    #[cfg(not(target_os = "windows"))]
-    let paths: Vec<String> = {
-        vec![
-            "/usr/local/bin".to_string(),
-            "/opt/homebrew/bin".to_string(),
-            "/opt/local/bin".to_string(),
-            "/usr/bin".to_string(),
-            "/bin".to_string(),
-            format!("{}/.local/bin", home),
-            format!("{}/.deno/bin", home),
-        ]
-    };
-
-    // 构建 PATH 环境变量
-    #[cfg(target_os = "windows")]
-    let separator = ";";
-    #[cfg(not(target_os = "windows"))]
-    let separator = ":";
-
-    let path = match std::env::var("PATH") {
-        Ok(path) if !path.is_empty() => format!("{}{}{}", path, separator, paths.join(separator)),
-        _ => paths.join(separator),
-    };
-
-    // 尝试使用 node，如果失败则尝试 deno
-    let commands = [("node", vec!["-e"]), ("deno", vec!["eval"])];
-    for (cmd, args) in &commands {
-        let mut command = Command::new(cmd);
-        for arg in args {
-            command.arg(arg);
-        }
-        command
-            .arg(&wrapped_code)
-            .env("PATH", &path)
-            .stdout(Stdio::piped())
-            .stderr(Stdio::piped());
-
-        match command.spawn() {
-            Ok(child) => {
-                let output = child.wait_with_output().await?;
-
-                if output.status.success() {
-                    let stdout = String::from_utf8_lossy(&output.stdout);
-                    return Ok(stdout.trim().to_string());
-                } else {
-                    let stderr = String::from_utf8_lossy(&output.stderr);
-                    // 如果是找不到命令的错误，继续尝试下一个命令
-                    if stderr.contains("No such file or directory")
-                        || stderr.contains("command not found")
-                    {
-                        continue;
-                    }
-                    return Err(format!("JavaScript execution failed: {}", stderr).into());
-                }
-            }
-            Err(_) => continue, // 尝试下一个命令
-        }
-    }
-
-    Err("JavaScript runtime not found. Please install Node.js or Deno.".into())
+    let execution = Execution::new(command.timeout)?
+        .sandboxed(command.sandbox.unwrap_or_default())
+        .stream_to(&app);
+    on_start.send(execution.id.clone())?;
+    let task = script::run_command(&command, &data, &execution);
+    script::audited(&app, "command", &command.id, &data, &execution, task).await
 }
 
+/// 执行脚本动作
+///
+/// 启动时通过 `on_start` 通道返回执行标识，可用于取消执行
+/// 运行期间的输出通过 `execution-output` 事件逐行发送
 #[tauri::command]
-pub async fn execute_python(
-    code: String,
-    data: String,
-    python_path: Option<String>,
+pub async fn execute_script(
+    app: tauri::AppHandle,
+    script: ScriptAction,
+    data: Data,
+    mut options: ScriptOptions,
+    on_start: Channel<String>,
 ) -> Result<String, AppError> {
-    // 创建 Python 代码包装
-    let wrapped_code = format!(
-        r#"
-import json
-data = {}
-{}
-result = process(data)
-print(result if isinstance(result, str) else json.dumps(result, ensure_ascii=False))
-        "#,
-        data, code
-    );
-
-    // 如果提供了自定义路径，直接使用
-    if let Some(program) = python_path {
-        if !program.is_empty() {
-            // 在 Windows 上，如果是 .bat 文件，需要特殊处理
-            #[cfg(target_os = "windows")]
-            let use_stdin = program.to_lowercase().ends_with(".bat");
-            #[cfg(not(target_os = "windows"))]
-            let use_stdin = false;
-
-            let mut command = if use_stdin {
-                // 对于 .bat 文件，通过 stdin 传递代码，避免参数转义问题
-                let mut cmd = Command::new(&program);
-                cmd.stdin(Stdio::piped());
-                cmd
-            } else {
-                // 对于普通可执行文件，使用 -c 参数
-                let mut cmd = Command::new(&program);
-                cmd.arg("-c").arg(&wrapped_code);
-                cmd
-            };
-
-            command.stdout(Stdio::piped()).stderr(Stdio::piped());
+    let execution = Execution::new(script.timeout)?
+        .sandboxed(script.sandbox.unwrap_or_default())
+        .stream_to(&app);
+    on_start.send(execution.id.clone())?;
+    options.data_dir = app.path().app_data_dir().ok();
+    let task = script::run_script(&script, &data, &options, &execution);
+    script::audited(&app, "script", &script.id, &data, &execution, task).await
+}
 
-            match command.spawn() {
-                Ok(mut child) => {
-                    // 如果使用 stdin，写入代码
-                    if use_stdin {
-                        if let Some(mut stdin) = child.stdin.take() {
-                            stdin.write_all(wrapped_code.as_bytes()).await?;
-                            drop(stdin); // 关闭 stdin
-                        }
-                    }
+/// 跳过缓存重新执行记录对应的脚本或提示词动作
+///
+/// 命中缓存的记录在弹窗中提供重新执行，执行结果会替换原有的缓存
+#[tauri::command]
+pub async fn rerun_action(app: tauri::AppHandle, entry: Entry) -> Result<(), AppError> {
+    engine::rerun(app, entry).await
+}
 
-                    let output = child.wait_with_output().await?;
+/// 安装脚本声明的依赖
+///
+/// 启动时通过 `on_start` 通道返回执行标识，可用于取消安装
+/// 安装过程的输出通过 `execution-output` 事件逐行发送
+#[tauri::command]
+pub async fn install_dependencies(
+    app: tauri::AppHandle,
+    script: ScriptAction,
+    mut options: ScriptOptions,
+    on_start: Channel<String>,
+) -> Result<(), AppError> {
+    let execution = Execution::new(Some(script::INSTALL_TIMEOUT.as_secs()))?.stream_to(&app);
+    on_start.send(execution.id.clone())?;
+    options.data_dir = app.path().app_data_dir().ok();
+    script::install_dependencies(&script, &options, &execution).await
+}
 
-                    if output.status.success() {
-                        let stdout = String::from_utf8_lossy(&output.stdout);
-                        return Ok(stdout.trim().to_string());
-                    } else {
-                        let stderr = String::from_utf8_lossy(&output.stderr);
-                        return Err(format!("Python execution failed: {}", stderr).into());
-                    }
-                }
-                Err(e) => {
-                    return Err(
-                        format!("Failed to execute custom path '{}': {}", program, e).into(),
-                    );
-                }
-            }
-        }
-    }
+/// 运行脚本的测试用例
+///
+/// 使用表单中尚未保存的脚本内容，启动时通过 `on_start` 通道返回执行标识，可用于取消
+#[tauri::command]
+pub async fn run_script_tests(
+    app: tauri::AppHandle,
+    script: ScriptAction,
+    mut options: ScriptOptions,
+    on_start: Channel<String>,
+) -> Result<Vec<TestResult>, AppError> {
+    let execution = Execution::new(script.timeout)?.sandboxed(script.sandbox.unwrap_or_default());
+    on_start.send(execution.id.clone())?;
+    options.data_dir = app.path().app_data_dir().ok();
+    script::run_tests(&script, &options, &execution).await
+}
 
-    // 获取用户主目录
-    #[cfg(target_os = "windows")]
-    let home = std::env::var("USERPROFILE").unwrap_or_default();
-    #[cfg(not(target_os = "windows"))]
-    let home = std::env::var("HOME").unwrap_or_default();
+/// 检测可用的脚本运行环境
+///
+/// 结果会被缓存并用于跳过执行时的路径查找，`refresh` 为 true 时重新检测
+#[tauri::command]
+pub async fn detect_runtimes(
+    options: ScriptOptions,
+    refresh: Option<bool>,
+) -> Result<Vec<RuntimeStatus>, AppError> {
+    Ok(script::scan_runtimes(&options, refresh.unwrap_or(false)).await)
+}
 
-    // 常见的 Python 运行环境路径
-    #[cfg(target_os = "windows")]
-    let paths: Vec<String> = {
-        // 添加所有常见 Python 版本及其 Scripts 目录
-        let mut paths = vec![format!("{}\\AppData\\Local\\Microsoft\\WindowsApps", home)];
-        let versions = [
-            "Python313",
-            "Python312",
-            "Python311",
-            "Python310",
-            "Python39",
-        ];
-        for version in versions {
-            paths.push(format!(
-                "{}\\AppData\\Local\\Programs\\Python\\{}",
-                home, version
-            ));
-            paths.push(format!(
-                "{}\\AppData\\Local\\Programs\\Python\\{}\\Scripts",
-                home, version
-            ));
-            paths.push(format!("C:\\{}", version));
-            paths.push(format!("C:\\{}\\Scripts", version));
-        }
-        paths
-    };
-    #[cfg(not(target_os = "windows"))]
-    let paths: Vec<String> = {
-        vec![
-            format!("{}/.pyenv/shims", home),
-            "/usr/local/bin".to_string(),
-            "/opt/homebrew/bin".to_string(),
-            "/opt/local/bin".to_string(),
-            "/usr/bin".to_string(),
-            "/bin".to_string(),
-            format!("{}/.local/bin", home),
-        ]
+/// 分发前端执行得到的结构化结果
+///
+/// 返回值不是结构化结果时返回 false，由前端按文本处理
+#[tauri::command]
+pub async fn deliver_result(
+    app: tauri::AppHandle,
+    rule: Rule,
+    entry: Entry,
+) -> Result<bool, AppError> {
+    let Some(output) = script::parse_output(entry.result.as_deref().unwrap_or_default())? else {
+        return Ok(false);
     };
-
-    // 构建 PATH 环境变量
-    #[cfg(target_os = "windows")]
-    let separator = ";";
-    #[cfg(not(target_os = "windows"))]
-    let separator = ":";
-
-    let path = match std::env::var("PATH") {
-        Ok(path) if !path.is_empty() => format!("{}{}{}", path, separator, paths.join(separator)),
-        _ => paths.join(separator),
+    let config = Config::load(&app)?;
+    let state = ChainState {
+        depth: 0,
+        sandbox: config.sandbox(&rule.action),
     };
-
-    // 尝试使用 python3，如果失败则尝试 python
-    let commands = ["python3", "python"];
-    for cmd in &commands {
-        let mut command = Command::new(cmd);
-        command
-            .arg("-c")
-            .arg(&wrapped_code)
-            .env("PATH", &path)
-            .stdout(Stdio::piped())
-            .stderr(Stdio::piped());
-
-        match command.spawn() {
-            Ok(child) => {
-                let output = child.wait_with_output().await?;
-
-                if output.status.success() {
-                    let stdout = String::from_utf8_lossy(&output.stdout);
-                    return Ok(stdout.trim().to_string());
-                } else {
-                    let stderr = String::from_utf8_lossy(&output.stderr);
-                    // 如果是找不到命令的错误，继续尝试下一个命令
-                    if stderr.contains("No such file or directory")
-                        || stderr.contains("command not found")
-                    {
-                        continue;
-                    }
-                    return Err(format!("Python execution failed: {}", stderr).into());
-                }
-            }
-            Err(_) => continue, // 尝试下一个命令
-        }
-    }
-
-    Err("Python interpreter not found. Please install Python.".into())
+    // 前端执行的动作不经过调度器，链式执行的后续动作使用独立的取消信号
+    engine::deliver(&app, &config, &rule, entry, output, &Arc::default(), state).await?;
+    Ok(true)
 }
@@ -0,0 +1,47 @@
+use crate::engine::Config;
+use crate::error::AppError;
+use crate::script::{self, Library, Runtime};
+use std::path::PathBuf;
+use tauri::Manager;
+
+/// 获取脚本目录中加载的脚本和解析问题
+#[tauri::command]
+pub fn get_script_library() -> Library {
+    script::script_library()
+}
+
+/// 切换脚本目录并重新加载
+///
+/// `dir` 为空时使用应用数据目录下的 scripts 目录
+#[tauri::command]
+pub fn set_scripts_dir(
+    app: tauri::AppHandle,
+    dir: String,
+    runtimes: Vec<Runtime>,
+) -> Result<Library, AppError> {
+    let dir = resolve_scripts_dir(&app, &dir)?;
+    script::watch_library(&app, dir, runtimes)
+}
+
+/// 应用启动时加载并监听存储中设置的脚本目录
+pub fn start_script_library(app: &tauri::AppHandle) {
+    let config = Config::load(app).unwrap_or_default();
+    let watched = resolve_scripts_dir(app, &config.scripts_dir)
+        .and_then(|dir| script::watch_library(app, dir, config.script_options.runtimes));
+    if let Err(e) = watched {
+        eprintln!("[ERROR] Failed to load scripts directory: {}", e);
+    }
+}
+
+/// 确定脚本目录的路径
+fn resolve_scripts_dir(app: &tauri::AppHandle, dir: &str) -> Result<PathBuf, AppError> {
+    let dir = dir.trim();
+    if !dir.is_empty() {
+        return Ok(PathBuf::from(dir));
+    }
+    let data_dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to resolve app data directory: {}", e))?;
+    Ok(data_dir.join("scripts"))
+}
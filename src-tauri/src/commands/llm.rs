@@ -0,0 +1,79 @@
+use crate::engine::Config;
+use crate::error::AppError;
+use crate::llm::{self, ChatMessage, ChatRequest, Session, SessionReply};
+use crate::script::Execution;
+use tauri::ipc::Channel;
+
+/// 流式调用大模型
+///
+/// 启动时通过 `on_start` 通道返回执行标识，可用于取消
+/// 生成的文本通过 `on_token` 通道逐段发送，返回完整的回复
+#[tauri::command]
+pub async fn stream_chat(
+    app: tauri::AppHandle,
+    request: ChatRequest,
+    on_start: Channel<String>,
+    on_token: Channel<String>,
+) -> Result<String, AppError> {
+    let config = Config::load(&app)?;
+    let execution = Execution::new(Some(llm::LLM_TIMEOUT_SECS))?;
+    on_start.send(execution.id.clone())?;
+    llm::chat(&request, &config.llm, &execution, |token| {
+        if let Err(e) = on_token.send(token.to_string()) {
+            eprintln!("[ERROR] Failed to send token: {}", e);
+        }
+    })
+    .await
+}
+
+/// 在多轮对话中发送一条消息
+///
+/// 对话不存在时以 `session` 创建，其中可包含系统提示词和工具。
+/// 启动时通过 `on_start` 通道返回执行标识，生成的文本通过 `on_token` 通道逐段发送，
+/// 工具调用和结果消息通过 `on_step` 通道发送
+#[tauri::command]
+pub async fn chat_session(
+    app: tauri::AppHandle,
+    session: Session,
+    message: String,
+    on_start: Channel<String>,
+    on_token: Channel<String>,
+    on_step: Channel<ChatMessage>,
+) -> Result<SessionReply, AppError> {
+    let config = Config::load(&app)?;
+    if let Some(reply) = llm::cached_reply(&app, &config, &session, &message)? {
+        return Ok(reply);
+    }
+    let execution = Execution::new(Some(llm::LLM_TIMEOUT_SECS))?;
+    on_start.send(execution.id.clone())?;
+    llm::send_message(
+        &app,
+        &config,
+        session,
+        message,
+        &execution,
+        |token| {
+            if let Err(e) = on_token.send(token.to_string()) {
+                eprintln!("[ERROR] Failed to send token: {}", e);
+            }
+        },
+        |step| {
+            if let Err(e) = on_step.send(step.clone()) {
+                eprintln!("[ERROR] Failed to send tool step: {}", e);
+            }
+        },
+    )
+    .await
+}
+
+/// 获取保存的对话
+#[tauri::command]
+pub async fn get_session(app: tauri::AppHandle, id: String) -> Result<Option<Session>, AppError> {
+    llm::load_session(&app, &id)
+}
+
+/// 删除保存的对话
+#[tauri::command]
+pub async fn delete_session(app: tauri::AppHandle, id: String) -> Result<(), AppError> {
+    llm::delete_session(&app, &id)
+}
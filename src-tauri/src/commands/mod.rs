@@ -1,18 +1,32 @@
+mod audit;
+mod cache;
+mod classify;
 mod executor;
 mod keyboard;
+mod library;
+mod llm;
 mod popup;
+mod secret;
 mod selection;
 mod shortcut;
+mod template;
 mod tray;
 mod ui;
 mod window;
 
 // 重新导出所有命令函数
+pub use audit::*;
+pub use cache::*;
+pub use classify::*;
 pub use executor::*;
 pub use keyboard::*;
+pub use library::*;
+pub use llm::*;
 pub use popup::*;
+pub use secret::*;
 pub use selection::*;
 pub use shortcut::*;
+pub use template::*;
 pub use tray::*;
 pub use ui::*;
 pub use window::*;
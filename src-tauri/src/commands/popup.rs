@@ -67,3 +67,18 @@ pub fn show_popup(app: tauri::AppHandle, payload: String) -> Result<(), AppError
 
     Ok(())
 }
+
+/// 更新弹窗内容
+///
+/// 弹窗可见时仅发送数据，不改变窗口位置；弹窗隐藏时重新显示
+#[tauri::command]
+pub fn update_popup(app: tauri::AppHandle, payload: String) -> Result<(), AppError> {
+    match app.get_webview_window("popup") {
+        Some(window) if window.is_visible()? => {
+            window.emit("popup", payload)?;
+            Ok(())
+        }
+        Some(_) => show_popup(app, payload),
+        None => Err("Popup window not found".into()),
+    }
+}
@@ -0,0 +1,22 @@
+use crate::error::AppError;
+use crate::script;
+
+/// 保存密钥到系统钥匙串
+///
+/// 密钥值只保存在钥匙串中，前端只保存名称
+#[tauri::command]
+pub async fn set_secret(name: String, value: String) -> Result<(), AppError> {
+    script::set_secret(name, value).await
+}
+
+/// 从系统钥匙串中删除密钥
+#[tauri::command]
+pub async fn delete_secret(name: String) -> Result<(), AppError> {
+    script::delete_secret(name).await
+}
+
+/// 判断系统钥匙串中是否存在密钥
+#[tauri::command]
+pub async fn has_secret(name: String) -> Result<bool, AppError> {
+    script::has_secret(name).await
+}
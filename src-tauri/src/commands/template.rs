@@ -0,0 +1,15 @@
+use crate::error::AppError;
+use crate::script::Data;
+use crate::template::{self, TemplateIssue};
+
+/// 使用触发数据渲染模板
+#[tauri::command]
+pub fn render_template(template: String, data: Data) -> Result<String, AppError> {
+    template::render(&template, &data, &[])
+}
+
+/// 检查模板的语法，返回第一个问题及其位置
+#[tauri::command]
+pub fn validate_template(template: String) -> Option<TemplateIssue> {
+    template::validate(&template)
+}
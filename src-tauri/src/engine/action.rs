@@ -0,0 +1,446 @@
+use super::config::{Config, Entry, Prompt, Rule};
+use crate::cache;
+use crate::commands::{hide_window, send_paste_key, show_popup, show_window, update_popup};
+use crate::error::AppError;
+use crate::platform;
+use crate::script::{
+    self, run_command, CommandAction, Context, Data, Execution, Notification, Sandbox,
+    ScriptAction, ScriptOutput,
+};
+use crate::template;
+use std::future::Future;
+use std::sync::Arc;
+use tauri::Emitter;
+use tauri_plugin_clipboard_manager::ClipboardExt;
+use tauri_plugin_notification::NotificationExt;
+use tauri_plugin_opener::OpenerExt;
+use tokio::sync::Notify;
+
+/// 结构化结果中链式执行动作的最大深度
+const MAX_CHAIN_DEPTH: usize = 4;
+
+/// 链式执行中传递给后续动作的状态
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ChainState {
+    /// 链式深度，直接触发的动作为 0
+    pub depth: usize,
+    /// 沙箱级别，后续动作不能使用比之前的动作更弱的沙箱
+    pub sandbox: Sandbox,
+}
+
+/// 动作执行结果
+#[derive(Debug)]
+pub enum Outcome {
+    /// 已在后端执行完毕
+    Done,
+    /// 内置动作需要交由前端执行
+    Deferred,
+}
+
+/// 执行规则绑定的动作
+///
+/// `cancel` 触发时取消正在运行的脚本或命令，包括链式执行的后续动作
+pub async fn execute(
+    app: &tauri::AppHandle,
+    config: &Config,
+    rule: &Rule,
+    case_label: Option<String>,
+    selection: &str,
+    cancel: &Arc<Notify>,
+) -> Result<Outcome, AppError> {
+    let state = ChainState::default();
+    execute_at(app, config, rule, case_label, selection, cancel, state).await
+}
+
+/// 在指定的链式状态下执行动作
+async fn execute_at(
+    app: &tauri::AppHandle,
+    config: &Config,
+    rule: &Rule,
+    case_label: Option<String>,
+    selection: &str,
+    cancel: &Arc<Notify>,
+    state: ChainState,
+) -> Result<Outcome, AppError> {
+    // 执行默认动作
+    if rule.action.is_empty() {
+        let app_clone = app.clone();
+        app.run_on_main_thread(move || {
+            show_window(&app_clone, "main");
+        })?;
+        return Ok(Outcome::Done);
+    }
+
+    let data = collect(app, rule, selection);
+    let entry = Entry {
+        id: uuid::Uuid::new_v4().to_string(),
+        key: rule.key.clone(),
+        datetime: data.datetime.clone(),
+        clipboard: data.clipboard.clone(),
+        selection: data.selection.clone(),
+        case_label,
+        ..Default::default()
+    };
+
+    // 链式执行的动作至少使用之前的动作的沙箱级别
+    let state = ChainState {
+        sandbox: config.sandbox(&rule.action).max(state.sandbox),
+        ..state
+    };
+    if let Some(script) = config.script(&rule.action) {
+        let entry = run_script(app, config, script, &data, entry, cancel, state.sandbox).await?;
+        finish(app, config, rule, entry, cancel, state).await?;
+        Ok(Outcome::Done)
+    } else if let Some(command) = config.command(&rule.action) {
+        let entry = run_command_action(app, command, &data, entry, cancel, state.sandbox).await?;
+        finish(app, config, rule, entry, cancel, state).await?;
+        Ok(Outcome::Done)
+    } else if let Some(prompt) = config.prompt(&rule.action) {
+        // 提示词的工具以各自的沙箱级别运行，不能由沙箱中的动作触发
+        if state.sandbox.is_enabled() && !prompt.tools.is_empty() {
+            return Err(format!(
+                "Prompt '{}' uses tools and cannot be chained from a sandboxed action",
+                prompt.id
+            )
+            .into());
+        }
+        let entry = run_prompt(prompt, &data, entry)?;
+        notify(app, rule, &entry);
+        popup(app, &entry, show_popup)?;
+        Ok(Outcome::Done)
+    } else {
+        Ok(Outcome::Deferred)
+    }
+}
+
+/// 组装当前触发的数据
+fn collect(app: &tauri::AppHandle, rule: &Rule, selection: &str) -> Data {
+    Data {
+        selection: selection.to_string(),
+        clipboard: app.clipboard().read_text().unwrap_or_default(),
+        datetime: chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true),
+        context: Context {
+            key: rule.key.clone(),
+            case: rule.case.clone(),
+            action: rule.action.clone(),
+            app: platform::get_focused_app_name().unwrap_or_default(),
+        },
+    }
+}
+
+/// 执行脚本并填充记录
+async fn run_script(
+    app: &tauri::AppHandle,
+    config: &Config,
+    script: &ScriptAction,
+    data: &Data,
+    mut entry: Entry,
+    cancel: &Arc<Notify>,
+    sandbox: Sandbox,
+) -> Result<Entry, AppError> {
+    entry.action_type = Some("script".to_string());
+    entry.action_label = Some(script.id.clone());
+    entry.script_lang = Some(script.lang.clone());
+    entry.quiet_mode = script.quiet_mode;
+
+    // 纯脚本的结果只取决于选中的文本，命中缓存时不再执行
+    let action = &data.context.action;
+    let key = (config.cache.enabled && script.pure == Some(true))
+        .then(|| cache::cache_key(action, script, &data.selection));
+    if let Some(result) = key
+        .as_ref()
+        .and_then(|key| cache::lookup(app, &config.cache, key))
+        .filter(|result| is_plain(result))
+    {
+        entry.result = Some(result);
+        entry.cached = Some(true);
+        if entry.quiet_mode != Some(true) {
+            popup(app, &entry, show_popup)?;
+        }
+        return Ok(entry);
+    }
+
+    let execution = Execution::new(script.timeout)?
+        .cancelled_by(cancel)
+        .sandboxed(sandbox)
+        .stream_to(app);
+    let task = script::run_script(script, data, &config.script_options, &execution);
+    let task = script::audited(app, "script", &script.id, data, &execution, task);
+    let entry = stream(app, &execution, entry, task).await?;
+    if let (Some(key), Some(result)) = (&key, &entry.result) {
+        if is_plain(result) {
+            cache::store(app, &config.cache, key, action, result);
+        }
+    }
+    Ok(entry)
+}
+
+/// 判断脚本结果是否为纯文本
+///
+/// 结构化结果会打开链接、发送通知或执行后续动作，命中缓存时不能重放，因此只缓存纯文本结果
+fn is_plain(result: &str) -> bool {
+    matches!(script::parse_output(result), Ok(None))
+}
+
+/// 执行命令行动作并填充记录
+async fn run_command_action(
+    app: &tauri::AppHandle,
+    command: &CommandAction,
+    data: &Data,
+    mut entry: Entry,
+    cancel: &Arc<Notify>,
+    sandbox: Sandbox,
+) -> Result<Entry, AppError> {
+    let execution = Execution::new(command.timeout)?
+        .cancelled_by(cancel)
+        .sandboxed(sandbox)
+        .stream_to(app);
+
+    entry.action_type = Some("command".to_string());
+    entry.action_label = Some(command.id.clone());
+    entry.quiet_mode = command.quiet_mode;
+
+    let task = run_command(command, data, &execution);
+    let task = script::audited(app, "command", &command.id, data, &execution, task);
+    stream(app, &execution, entry, task).await
+}
+
+/// 等待执行结束并填充记录
+///
+/// 非静默模式下，执行开始时即显示弹窗以展示实时输出
+async fn stream(
+    app: &tauri::AppHandle,
+    execution: &Execution,
+    mut entry: Entry,
+    task: impl Future<Output = Result<String, AppError>>,
+) -> Result<Entry, AppError> {
+    let streaming = entry.quiet_mode != Some(true);
+    if streaming {
+        entry.execution_id = Some(execution.id.clone());
+        popup(app, &entry, show_popup)?;
+        entry.execution_id = None;
+    }
+
+    match task.await {
+        Ok(result) => {
+            entry.result = Some(result);
+            Ok(entry)
+        }
+        Err(e) => {
+            // 弹窗已显示时，展示错误信息
+            if streaming {
+                entry.error = Some(e.to_string());
+                popup(app, &entry, update_popup)?;
+            }
+            Err(e)
+        }
+    }
+}
+
+/// 通知前端保存记录，并粘贴结果或更新弹窗
+///
+/// 返回值为结构化结果时，按各字段分别分发
+async fn finish(
+    app: &tauri::AppHandle,
+    config: &Config,
+    rule: &Rule,
+    mut entry: Entry,
+    cancel: &Arc<Notify>,
+    state: ChainState,
+) -> Result<(), AppError> {
+    let output =
+        script::parse_output(entry.result.as_deref().unwrap_or_default()).and_then(|output| {
+            match output {
+                Some(output) => output.check_sandbox(state.sandbox).map(|_| Some(output)),
+                None => Ok(None),
+            }
+        });
+    match output {
+        Ok(Some(output)) => return deliver(app, config, rule, entry, output, cancel, state).await,
+        Ok(None) => {}
+        Err(e) => {
+            // 弹窗已显示时，展示错误信息
+            if entry.quiet_mode != Some(true) {
+                entry.error = Some(e.to_string());
+                popup(app, &entry, update_popup)?;
+            }
+            return Err(e);
+        }
+    }
+
+    notify(app, rule, &entry);
+    if entry.quiet_mode == Some(true) {
+        // 静默模式下不显示窗口
+        paste(app, entry.result.unwrap_or_default())
+    } else {
+        // 弹窗已在执行开始时显示，此处只更新执行结果
+        popup(app, &entry, update_popup)
+    }
+}
+
+/// 分发结构化结果
+///
+/// 依次写入剪贴板、发送通知、打开链接、粘贴文本和显示弹窗，最后执行后续动作。
+/// `state` 为产生结果的动作的链式状态，沙箱中的动作不能打开本地文件
+pub async fn deliver(
+    app: &tauri::AppHandle,
+    config: &Config,
+    rule: &Rule,
+    mut entry: Entry,
+    output: ScriptOutput,
+    cancel: &Arc<Notify>,
+    state: ChainState,
+) -> Result<(), AppError> {
+    output.check_sandbox(state.sandbox)?;
+    let quiet = entry.quiet_mode == Some(true);
+    entry.result = Some(output.summary());
+    entry.result_format = output.popup.as_ref().map(|popup| popup.format);
+    notify(app, rule, &entry);
+
+    if let Some(notification) = &output.notify {
+        send_notification(app, &entry, notification);
+    }
+    for target in &output.open {
+        open(app, target);
+    }
+
+    // 粘贴前隐藏未请求显示的弹窗，以便将焦点还给原窗口
+    let dismiss = !quiet && output.popup.is_none();
+    let paste_text = output.paste.clone();
+    let clipboard_text = output.clipboard.clone();
+    if dismiss || paste_text.is_some() || clipboard_text.is_some() {
+        let app_clone = app.clone();
+        app.run_on_main_thread(move || {
+            if dismiss {
+                hide_window(&app_clone, "popup");
+            }
+            if let Some(text) = paste_text {
+                let _ = send_paste_key(app_clone.clone(), text);
+            }
+            // 粘贴会占用剪贴板，因此最后写入
+            if let Some(text) = clipboard_text {
+                if let Err(e) = app_clone.clipboard().write_text(text) {
+                    eprintln!("[ERROR] Failed to write clipboard: {}", e);
+                }
+            }
+        })?;
+    }
+    if output.popup.is_some() {
+        popup(app, &entry, if quiet { show_popup } else { update_popup })?;
+    }
+
+    let Some(chain) = output.chain else {
+        return Ok(());
+    };
+    if state.depth >= MAX_CHAIN_DEPTH {
+        return Err(format!(
+            "Chained action '{}' exceeds the maximum depth of {}",
+            chain.action, MAX_CHAIN_DEPTH
+        )
+        .into());
+    }
+    let action = config
+        .resolve_action(&chain.action)
+        .ok_or_else(|| format!("Chained action '{}' not found", chain.action))?;
+    let selection = chain
+        .selection
+        .or(output.paste)
+        .unwrap_or_else(|| entry.selection.clone());
+    let rule = Rule {
+        action,
+        ..rule.clone()
+    };
+    Box::pin(execute_at(
+        app,
+        config,
+        &rule,
+        entry.case_label.clone(),
+        &selection,
+        cancel,
+        ChainState {
+            depth: state.depth + 1,
+            ..state
+        },
+    ))
+    .await?;
+    Ok(())
+}
+
+/// 渲染提示词并填充记录
+fn run_prompt(prompt: &Prompt, data: &Data, mut entry: Entry) -> Result<Entry, AppError> {
+    let result = template::render(&prompt.prompt, data, &[])?;
+
+    entry.action_type = Some("prompt".to_string());
+    entry.action_label = Some(prompt.id.clone());
+    entry.result = Some(result);
+    entry.system_prompt = prompt.system_prompt.clone();
+    entry.tools = (!prompt.tools.is_empty()).then(|| prompt.tools.clone());
+    entry.temperature = prompt.temperature;
+    entry.provider = Some(prompt.provider.clone());
+    entry.model = Some(prompt.model.clone());
+    Ok(entry)
+}
+
+/// 通知前端保存触发记录
+fn notify(app: &tauri::AppHandle, rule: &Rule, entry: &Entry) {
+    let event_data = serde_json::json!({
+        "case": rule.case,
+        "entry": entry
+    });
+    if let Err(e) = app.emit("shortcut-executed", event_data) {
+        eprintln!("[ERROR] Failed to emit executed event: {}", e);
+    }
+}
+
+/// 发送系统通知，未指定标题时使用动作名称
+fn send_notification(app: &tauri::AppHandle, entry: &Entry, notification: &Notification) {
+    let title = notification
+        .title
+        .clone()
+        .or_else(|| entry.action_label.clone())
+        .unwrap_or_else(|| "TextGO".to_string());
+    if let Err(e) = app
+        .notification()
+        .builder()
+        .title(title)
+        .body(&notification.body)
+        .show()
+    {
+        eprintln!("[ERROR] Failed to show notification: {}", e);
+    }
+}
+
+/// 使用系统默认程序打开链接或文件
+fn open(app: &tauri::AppHandle, target: &str) {
+    let result = if script::is_url(target) {
+        app.opener().open_url(target, None::<&str>)
+    } else {
+        app.opener().open_path(target, None::<&str>)
+    };
+    if let Err(e) = result {
+        eprintln!("[ERROR] Failed to open {}: {}", target, e);
+    }
+}
+
+/// 在主线程中粘贴文本
+fn paste(app: &tauri::AppHandle, text: String) -> Result<(), AppError> {
+    let app_clone = app.clone();
+    app.run_on_main_thread(move || {
+        let _ = send_paste_key(app_clone, text);
+    })?;
+    Ok(())
+}
+
+/// 在主线程中显示或更新弹窗
+fn popup(
+    app: &tauri::AppHandle,
+    entry: &Entry,
+    show: fn(tauri::AppHandle, String) -> Result<(), AppError>,
+) -> Result<(), AppError> {
+    let payload = serde_json::to_string(entry)?;
+    let app_clone = app.clone();
+    app.run_on_main_thread(move || {
+        let _ = show(app_clone, payload);
+    })?;
+    Ok(())
+}
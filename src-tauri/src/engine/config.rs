@@ -0,0 +1,314 @@
+use super::scheduler::{Concurrency, DEFAULT_MAX_CONCURRENCY};
+use crate::cache::CacheOptions;
+use crate::error::AppError;
+use crate::llm::LlmOptions;
+use crate::script::{self, CommandAction, PopupFormat, Sandbox, ScriptAction, ScriptOptions};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use tauri::Manager;
+use tauri_plugin_store::StoreExt;
+
+/// 前端持久化数据所在的存储文件
+pub const STORE_FILE: &str = ".settings.dat";
+
+/// 分类模型前缀标识
+pub const MODEL_MARK: &str = "model-";
+
+/// 正则表达式前缀标识
+pub const REGEXP_MARK: &str = "regexp-";
+
+/// 脚本前缀标识
+pub const SCRIPT_MARK: &str = "script-";
+
+/// 提示词前缀标识
+pub const PROMPT_MARK: &str = "prompt-";
+
+/// 命令行动作前缀标识
+pub const COMMAND_MARK: &str = "command-";
+
+/// 规则
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Rule {
+    /// 规则标识
+    pub id: String,
+    /// 绑定的快捷键
+    pub key: String,
+    /// 绑定的文本类型
+    pub case: String,
+    /// 要执行动作的标识
+    pub action: String,
+}
+
+/// 提示词
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Prompt {
+    /// 提示词标识
+    pub id: String,
+    /// 模型供应
+    pub provider: String,
+    /// 模型名称
+    pub model: String,
+    /// 提示词内容
+    pub prompt: String,
+    /// 系统提示词
+    #[serde(default)]
+    pub system_prompt: Option<String>,
+    /// 作为工具提供给模型的脚本标识
+    #[serde(default)]
+    pub tools: Vec<String>,
+    /// 采样温度，为空时使用模型的默认值
+    #[serde(default)]
+    pub temperature: Option<f64>,
+}
+
+/// 正则表达式
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Regexp {
+    /// 正则标识
+    pub id: String,
+    /// 正则模式
+    pub pattern: String,
+    /// 正则修饰符
+    #[serde(default)]
+    pub flags: Option<String>,
+}
+
+/// 分类模型
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Model {
+    /// 分类模型标识
+    pub id: String,
+    /// 模型是否已训练
+    #[serde(default)]
+    pub model_trained: Option<bool>,
+}
+
+/// 规则引擎所需的全部配置
+#[derive(Debug, Clone, Default)]
+pub struct Config {
+    /// 快捷键组
+    pub shortcuts: HashMap<String, Vec<Rule>>,
+    /// 分类模型
+    pub models: Vec<Model>,
+    /// 正则表达式
+    pub regexps: Vec<Regexp>,
+    /// 脚本
+    pub scripts: Vec<ScriptAction>,
+    /// 提示词
+    pub prompts: Vec<Prompt>,
+    /// 命令行动作
+    pub commands: Vec<CommandAction>,
+    /// 脚本运行选项
+    pub script_options: ScriptOptions,
+    /// 脚本目录，为空时使用应用数据目录下的 scripts 目录
+    pub scripts_dir: String,
+    /// 同时运行的动作数量上限
+    pub max_concurrency: usize,
+    /// 大模型服务的连接选项
+    pub llm: LlmOptions,
+    /// 响应缓存选项
+    pub cache: CacheOptions,
+}
+
+impl Config {
+    /// 从前端使用的存储文件中加载配置
+    pub fn load(app: &tauri::AppHandle) -> Result<Self, AppError> {
+        let store = app.store(STORE_FILE)?;
+        let get = |key: &str| store.get(key);
+
+        Ok(Self {
+            shortcuts: parse(get("shortcuts")),
+            models: parse(get("models")),
+            regexps: parse(get("regexps")),
+            scripts: with_library(parse(get("scripts"))),
+            prompts: parse(get("prompts")),
+            commands: parse(get("commands")),
+            script_options: ScriptOptions {
+                node_path: parse(get("nodePath")),
+                python_path: parse(get("pythonPath")),
+                warm_pool: parse(get("warmPool")),
+                runtimes: parse(get("runtimes")),
+                pip_index_url: parse(get("pipIndexUrl")),
+                npm_registry: parse(get("npmRegistry")),
+                data_dir: app.path().app_data_dir().ok(),
+            },
+            scripts_dir: parse(get("scriptsDir")),
+            max_concurrency: get("maxConcurrency")
+                .and_then(|value| value.as_u64())
+                .filter(|max| *max > 0)
+                .map_or(DEFAULT_MAX_CONCURRENCY, |max| max as usize),
+            llm: LlmOptions {
+                ollama_host: parse(get("ollamaHost")),
+                lm_studio_host: parse(get("lmStudioHost")),
+                openai_base_url: parse(get("openaiBaseUrl")),
+                openai_api_key: parse(get("openaiApiKey")),
+                context_tokens: get("contextTokens")
+                    .and_then(|value| value.as_u64())
+                    .filter(|tokens| *tokens > 0)
+                    .map(|tokens| tokens as usize),
+            },
+            cache: CacheOptions {
+                enabled: parse(get("responseCache")),
+                ttl_hours: get("cacheTtl")
+                    .and_then(|value| value.as_u64())
+                    .filter(|hours| *hours > 0),
+                max_size_mb: get("cacheSize")
+                    .and_then(|value| value.as_u64())
+                    .filter(|mb| *mb > 0),
+            },
+        })
+    }
+
+    /// 获取绑定到指定键位的规则列表
+    pub fn rules(&self, key: &str) -> &[Rule] {
+        self.shortcuts
+            .get(key)
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+
+    /// 根据动作标识查找脚本
+    pub fn script(&self, action: &str) -> Option<&ScriptAction> {
+        let id = action.strip_prefix(SCRIPT_MARK)?;
+        self.scripts.iter().find(|s| s.id == id)
+    }
+
+    /// 根据动作标识查找提示词
+    pub fn prompt(&self, action: &str) -> Option<&Prompt> {
+        let id = action.strip_prefix(PROMPT_MARK)?;
+        self.prompts.iter().find(|p| p.id == id)
+    }
+
+    /// 根据动作标识查找命令行动作
+    pub fn command(&self, action: &str) -> Option<&CommandAction> {
+        let id = action.strip_prefix(COMMAND_MARK)?;
+        self.commands.iter().find(|c| c.id == id)
+    }
+
+    /// 获取脚本或命令行动作声明的沙箱级别，其他动作不使用沙箱
+    pub fn sandbox(&self, action: &str) -> Sandbox {
+        self.script(action)
+            .and_then(|s| s.sandbox)
+            .or_else(|| self.command(action).and_then(|c| c.sandbox))
+            .unwrap_or_default()
+    }
+
+    /// 获取动作的并发策略，提示词等即时完成的动作使用默认策略
+    pub fn concurrency(&self, action: &str) -> Concurrency {
+        self.script(action)
+            .and_then(|s| s.concurrency)
+            .or_else(|| self.command(action).and_then(|c| c.concurrency))
+            .unwrap_or_default()
+    }
+
+    /// 将动作标识或名称解析为动作标识
+    ///
+    /// 名称按脚本、命令行动作、提示词的顺序查找
+    pub fn resolve_action(&self, name: &str) -> Option<String> {
+        if self.script(name).is_some()
+            || self.command(name).is_some()
+            || self.prompt(name).is_some()
+        {
+            return Some(name.to_string());
+        }
+        if self.scripts.iter().any(|s| s.id == name) {
+            Some(format!("{}{}", SCRIPT_MARK, name))
+        } else if self.commands.iter().any(|c| c.id == name) {
+            Some(format!("{}{}", COMMAND_MARK, name))
+        } else if self.prompts.iter().any(|p| p.id == name) {
+            Some(format!("{}{}", PROMPT_MARK, name))
+        } else {
+            None
+        }
+    }
+}
+
+/// 追加脚本目录中的脚本，名称相同时存储中的脚本优先
+fn with_library(mut scripts: Vec<ScriptAction>) -> Vec<ScriptAction> {
+    for script in script::script_library().scripts {
+        if !scripts.iter().any(|s| s.id == script.id) {
+            scripts.push(script);
+        }
+    }
+    scripts
+}
+
+/// 解析存储中的值，缺失或格式不符时使用默认值
+fn parse<T: DeserializeOwned + Default>(value: Option<serde_json::Value>) -> T {
+    value
+        .and_then(|value| {
+            serde_json::from_value(value)
+                .map_err(|e| eprintln!("[WARNING] Failed to parse stored value: {}", e))
+                .ok()
+        })
+        .unwrap_or_default()
+}
+
+/// 触发记录
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Entry {
+    /// 记录标识
+    pub id: String,
+    /// 触发键位
+    pub key: String,
+    /// 触发时间
+    pub datetime: String,
+    /// 剪贴板文本
+    pub clipboard: String,
+    /// 选中的文本
+    pub selection: String,
+    /// 文本类型
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub case_label: Option<String>,
+    /// 触发动作
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub action_label: Option<String>,
+    /// 动作类型
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub action_type: Option<String>,
+    /// 执行结果 (脚本返回值/提示词)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub result: Option<String>,
+    /// 执行结果的渲染格式，脚本返回结构化结果时设置
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub result_format: Option<PopupFormat>,
+    /// 脚本语言
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub script_lang: Option<String>,
+    /// 静默执行
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub quiet_mode: Option<bool>,
+    /// 模型供应
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub provider: Option<String>,
+    /// 模型名称
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    /// 系统提示词
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub system_prompt: Option<String>,
+    /// 作为工具提供给模型的脚本标识
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tools: Option<Vec<String>>,
+    /// 采样温度
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub temperature: Option<f64>,
+    /// 执行结果是否来自缓存
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cached: Option<bool>,
+    /// 响应内容
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub response: Option<String>,
+    /// 脚本执行标识，执行期间用于接收实时输出
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub execution_id: Option<String>,
+    /// 执行失败时的错误信息
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
@@ -0,0 +1,101 @@
+use super::config::{Config, Rule, MODEL_MARK, REGEXP_MARK};
+use crate::classify;
+use fancy_regex::Regex;
+
+/// 规则匹配结果
+#[derive(Debug)]
+pub enum Resolution<'a> {
+    /// 匹配到规则，附带识别出的文本类型标签
+    Matched(&'a Rule, Option<String>),
+    /// 存在无法在后端识别的文本类型，需要交由前端处理
+    Deferred,
+    /// 没有匹配的规则
+    Unmatched,
+}
+
+/// 根据文本类型匹配要执行的规则
+///
+/// 按顺序检查规则，遇到自然语言、编程语言、自定义模型等
+/// 只能在前端识别的文本类型时，返回 `Resolution::Deferred`
+pub fn resolve<'a>(config: &'a Config, rules: &'a [Rule], text: &str) -> Resolution<'a> {
+    for rule in rules {
+        let case = rule.case.as_str();
+        // 空字符串表示不进行任何识别
+        if case.is_empty() {
+            return Resolution::Matched(rule, None);
+        }
+        if text.is_empty() {
+            continue;
+        }
+
+        // 内置正则匹配
+        if let Some(matched) = classify::is_match(case, text) {
+            if matched {
+                return Resolution::Matched(rule, None);
+            }
+            continue;
+        }
+
+        // 自定义正则匹配
+        if let Some(id) = case.strip_prefix(REGEXP_MARK) {
+            let Some(regexp) = config.regexps.iter().find(|r| r.id == id) else {
+                continue;
+            };
+            if regexp.pattern.is_empty() {
+                continue;
+            }
+            match compile(&regexp.pattern, regexp.flags.as_deref().unwrap_or_default()) {
+                Some(pattern) => match pattern.is_match(text) {
+                    Ok(true) => return Resolution::Matched(rule, Some(regexp.id.clone())),
+                    Ok(false) => continue,
+                    Err(_) => return Resolution::Deferred,
+                },
+                // 语法不兼容时交由前端的 JS 引擎处理
+                None => return Resolution::Deferred,
+            }
+        }
+
+        // 自定义模型识别
+        if let Some(id) = case.strip_prefix(MODEL_MARK) {
+            let trained = config
+                .models
+                .iter()
+                .any(|m| m.id == id && m.model_trained == Some(true));
+            if trained {
+                return Resolution::Deferred;
+            }
+            continue;
+        }
+
+        // 自然语言和编程语言识别
+        return Resolution::Deferred;
+    }
+
+    Resolution::Unmatched
+}
+
+/// 判断文本类型是否为只能在前端识别的自然语言或编程语言
+pub fn is_language(case: &str) -> bool {
+    !case.is_empty()
+        && classify::builtin_case(case).is_none()
+        && !case.starts_with(REGEXP_MARK)
+        && !case.starts_with(MODEL_MARK)
+}
+
+/// 将 JS 风格的正则表达式及修饰符编译为正则对象
+fn compile(pattern: &str, flags: &str) -> Option<Regex> {
+    let inline: String = flags
+        .chars()
+        .filter(|f| matches!(f, 'i' | 'm' | 's'))
+        .collect();
+    let mut source = if flags.contains('y') {
+        // 粘连模式只从文本开头匹配
+        format!(r"\A(?:{})", pattern)
+    } else {
+        pattern.to_string()
+    };
+    if !inline.is_empty() {
+        source = format!("(?{}){}", inline, source);
+    }
+    Regex::new(&source).ok()
+}
@@ -0,0 +1,119 @@
+mod action;
+mod config;
+mod matcher;
+mod scheduler;
+
+pub use action::*;
+pub use config::*;
+pub use matcher::*;
+pub use scheduler::*;
+
+use crate::cache;
+use crate::error::AppError;
+use crate::llm::{self, Session};
+use crate::platform;
+use crate::SCHEDULER;
+use tauri::Emitter;
+
+/// 在后端匹配规则并执行动作
+///
+/// 动作按其并发策略调度，重复触发时可能被忽略、排队或取消之前的执行
+///
+/// 无法在后端完成识别或执行时，发送 `shortcut-triggered` 事件交由前端处理
+pub async fn dispatch(app: tauri::AppHandle, key: String, selection: String) {
+    let config = match Config::load(&app) {
+        Ok(config) => config,
+        Err(_) => {
+            defer(&app, &key, &selection, None);
+            return;
+        }
+    };
+
+    match resolve(&config, config.rules(&key), &selection) {
+        Resolution::Matched(rule, case_label) => {
+            let Some(ticket) = SCHEDULER
+                .admit(
+                    &rule.action,
+                    config.concurrency(&rule.action),
+                    config.max_concurrency,
+                )
+                .await
+            else {
+                return;
+            };
+            match execute(&app, &config, rule, case_label, &selection, ticket.cancel()).await {
+                Ok(Outcome::Done) => {}
+                Ok(Outcome::Deferred) => defer(&app, &key, &selection, Some(&rule.id)),
+                Err(e) => eprintln!("[ERROR] Failed to execute action: {}", e),
+            }
+        }
+        Resolution::Deferred => defer(&app, &key, &selection, None),
+        Resolution::Unmatched => eprintln!("[INFO] No rule matched for key {}", key),
+    }
+}
+
+/// 跳过缓存重新执行记录对应的脚本或提示词动作，执行前删除原有的缓存
+///
+/// 重新执行会生成新的触发记录
+pub async fn rerun(app: tauri::AppHandle, entry: Entry) -> Result<(), AppError> {
+    let config = Config::load(&app)?;
+    let label = entry.action_label.as_deref().unwrap_or_default();
+    let action = if entry.action_type.as_deref() == Some("prompt") {
+        let action = format!("{}{}", PROMPT_MARK, label);
+        if config.prompt(&action).is_none() {
+            return Err(format!("Prompt '{}' not found", action).into());
+        }
+        // 提示词的回复由弹窗在新对话中生成，删除缓存后不会再命中
+        let seed = Session::from_entry(&entry)?;
+        let message = entry.result.as_deref().unwrap_or_default();
+        llm::remove_cached_reply(&app, &config, &seed, message)?;
+        action
+    } else {
+        let action = format!("{}{}", SCRIPT_MARK, label);
+        let script = config
+            .script(&action)
+            .ok_or_else(|| format!("Script '{}' not found", action))?;
+        cache::remove(&app, &cache::cache_key(&action, script, &entry.selection))?;
+        action
+    };
+
+    let rule = Rule {
+        id: String::new(),
+        key: entry.key,
+        case: String::new(),
+        action,
+    };
+    let Some(ticket) = SCHEDULER
+        .admit(
+            &rule.action,
+            config.concurrency(&rule.action),
+            config.max_concurrency,
+        )
+        .await
+    else {
+        return Ok(());
+    };
+    execute(
+        &app,
+        &config,
+        &rule,
+        entry.case_label,
+        &entry.selection,
+        ticket.cancel(),
+    )
+    .await?;
+    Ok(())
+}
+
+/// 发送事件到前端，由前端匹配规则或执行指定规则
+fn defer(app: &tauri::AppHandle, key: &str, selection: &str, rule: Option<&str>) {
+    let event_data = serde_json::json!({
+        "key": key,
+        "selection": selection,
+        "rule": rule,
+        "app": platform::get_focused_app_name()
+    });
+    if let Err(e) = app.emit("shortcut-triggered", event_data) {
+        eprintln!("[ERROR] Failed to emit shortcut event: {}", e);
+    }
+}
@@ -0,0 +1,188 @@
+use crate::script;
+use crate::HELD_SHORTCUTS;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::{Notify, OwnedMutexGuard};
+
+/// 默认同时运行的动作数量上限
+pub const DEFAULT_MAX_CONCURRENCY: usize = 2;
+
+/// 按键重复事件的最大间隔，超过后即使未收到松开事件也视为重新按下
+const KEY_REPEAT_WINDOW: Duration = Duration::from_millis(500);
+
+/// 动作正在运行时再次触发的处理方式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Concurrency {
+    /// 忽略新的触发
+    #[default]
+    Drop,
+    /// 等待之前的执行结束后依次执行
+    Queue,
+    /// 取消之前的执行并重新开始
+    Restart,
+}
+
+/// 动作执行调度器
+///
+/// 同一动作按策略串行执行，所有动作同时运行的数量不超过上限
+#[derive(Default)]
+pub struct Scheduler {
+    /// 各动作的运行状态和正在运行的动作数量
+    state: Mutex<State>,
+    /// 有动作结束时通知等待中的触发
+    released: Notify,
+    /// 获取选中文本时独占剪贴板
+    grabbing: tokio::sync::Mutex<()>,
+}
+
+/// 调度器的内部状态
+#[derive(Default)]
+struct State {
+    /// 正在运行的动作数量
+    running: usize,
+    /// 各动作的运行状态
+    lanes: HashMap<String, Lane>,
+}
+
+/// 单个动作的运行状态
+#[derive(Default)]
+struct Lane {
+    /// 同一动作依次执行，等待者按先后顺序获得
+    lock: Arc<tokio::sync::Mutex<()>>,
+    /// 最近一次重新开始的序号，用于跳过已被更新的触发取代的等待
+    generation: u64,
+    /// 当前执行的取消信号
+    cancel: Option<Arc<Notify>>,
+}
+
+/// 执行许可，离开作用域时释放
+pub struct Ticket {
+    /// 签发许可的调度器
+    scheduler: &'static Scheduler,
+    /// 动作标识
+    action: String,
+    /// 本次执行的取消信号
+    cancel: Arc<Notify>,
+    /// 同一动作的执行锁
+    _lane: OwnedMutexGuard<()>,
+}
+
+impl Ticket {
+    /// 本次执行的取消信号，重新开始同一动作时触发
+    pub fn cancel(&self) -> &Arc<Notify> {
+        &self.cancel
+    }
+}
+
+impl Drop for Ticket {
+    fn drop(&mut self) {
+        if let Ok(mut state) = self.scheduler.state.lock() {
+            state.running = state.running.saturating_sub(1);
+            if let Some(lane) = state.lanes.get_mut(&self.action) {
+                if lane
+                    .cancel
+                    .as_ref()
+                    .is_some_and(|cancel| Arc::ptr_eq(cancel, &self.cancel))
+                {
+                    lane.cancel = None;
+                }
+            }
+        }
+        self.scheduler.released.notify_waiters();
+    }
+}
+
+impl Scheduler {
+    /// 按动作的策略申请执行许可，触发被忽略时返回 `None`
+    ///
+    /// 运行中的动作达到上限时等待其他动作结束
+    pub async fn admit(
+        &'static self,
+        action: &str,
+        policy: Concurrency,
+        max_concurrency: usize,
+    ) -> Option<Ticket> {
+        let (lock, generation) = {
+            let mut state = self.state.lock().ok()?;
+            let lane = state.lanes.entry(action.to_string()).or_default();
+            if policy == Concurrency::Restart {
+                lane.generation += 1;
+                if let Some(cancel) = &lane.cancel {
+                    script::signal_cancel(cancel);
+                }
+            }
+            (lane.lock.clone(), lane.generation)
+        };
+
+        let guard = match policy {
+            Concurrency::Drop => match lock.try_lock_owned() {
+                Ok(guard) => guard,
+                Err(_) => {
+                    eprintln!(
+                        "[INFO] Action '{}' is already running, trigger dropped",
+                        action
+                    );
+                    return None;
+                }
+            },
+            Concurrency::Queue | Concurrency::Restart => lock.lock_owned().await,
+        };
+
+        loop {
+            let released = self.released.notified();
+            tokio::pin!(released);
+            // 先登记等待再检查数量，避免错过检查之后的通知
+            released.as_mut().enable();
+            {
+                let mut state = self.state.lock().ok()?;
+                let lane = state.lanes.entry(action.to_string()).or_default();
+                // 等待期间又有新的触发要求重新开始，本次触发已被取代
+                if policy == Concurrency::Restart && lane.generation != generation {
+                    return None;
+                }
+                if state.running < max_concurrency.max(1) {
+                    let cancel = Arc::new(Notify::new());
+                    state.running += 1;
+                    if let Some(lane) = state.lanes.get_mut(action) {
+                        lane.cancel = Some(cancel.clone());
+                    }
+                    return Some(Ticket {
+                        scheduler: self,
+                        action: action.to_string(),
+                        cancel,
+                        _lane: guard,
+                    });
+                }
+            }
+            released.await;
+        }
+    }
+
+    /// 独占剪贴板获取选中文本，避免多次触发同时复制
+    pub async fn grab<T>(&self, task: impl std::future::Future<Output = T>) -> T {
+        let _guard = self.grabbing.lock().await;
+        task.await
+    }
+}
+
+/// 记录快捷键按下，按住时系统重复发送的按下事件返回 false
+pub fn key_pressed(id: u32) -> bool {
+    let Ok(mut held) = HELD_SHORTCUTS.lock() else {
+        return true;
+    };
+    let now = Instant::now();
+    match held.insert(id, now) {
+        Some(last) => now.duration_since(last) >= KEY_REPEAT_WINDOW,
+        None => true,
+    }
+}
+
+/// 记录快捷键松开
+pub fn key_released(id: u32) {
+    if let Ok(mut held) = HELD_SHORTCUTS.lock() {
+        held.remove(&id);
+    }
+}
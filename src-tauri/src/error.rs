@@ -1,22 +1,70 @@
+/// 需要前端区分处理的错误类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorKind {
+    /// 执行超时
+    Timeout,
+    /// 执行被取消
+    Cancelled,
+}
+
 #[derive(Debug, Clone)]
-pub struct AppError(String);
+pub struct AppError {
+    kind: Option<ErrorKind>,
+    message: String,
+}
+
+impl AppError {
+    fn new(message: String) -> Self {
+        AppError {
+            kind: None,
+            message,
+        }
+    }
+
+    /// 创建指定类型的错误
+    pub fn with_kind(kind: ErrorKind, message: impl Into<String>) -> Self {
+        let message = message.into();
+        eprintln!("[ERROR] {:?}: {}", kind, message);
+        AppError {
+            kind: Some(kind),
+            message,
+        }
+    }
+
+    /// 错误类型，普通错误为 `None`
+    pub fn kind(&self) -> Option<ErrorKind> {
+        self.kind
+    }
+}
 
 impl std::error::Error for AppError {}
 
 // 实现 Display，使错误信息可以直接打印
 impl std::fmt::Display for AppError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}", self.0)
+        write!(f, "{}", self.message)
     }
 }
 
 // 实现 Serialize，使其可以作为 Tauri 命令的返回类型
+// 普通错误序列化为字符串，指定类型的错误序列化为 { kind, message } 对象
 impl serde::Serialize for AppError {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
         S: serde::ser::Serializer,
     {
-        serializer.serialize_str(&self.0)
+        use serde::ser::SerializeStruct;
+
+        match self.kind {
+            Some(kind) => {
+                let mut state = serializer.serialize_struct("AppError", 2)?;
+                state.serialize_field("kind", &kind)?;
+                state.serialize_field("message", &self.message)?;
+                state.end()
+            }
+            None => serializer.serialize_str(&self.message),
+        }
     }
 }
 
@@ -29,49 +77,70 @@ impl From<String> for AppError {
 impl From<&str> for AppError {
     fn from(error: &str) -> Self {
         eprintln!("[ERROR] {}", error);
-        AppError(error.to_string())
+        AppError::new(error.to_string())
     }
 }
 
 impl From<std::io::Error> for AppError {
     fn from(error: std::io::Error) -> Self {
         eprintln!("[ERROR] IO error: {}", error);
-        AppError(error.to_string())
+        AppError::new(error.to_string())
     }
 }
 
 impl<T> From<std::sync::PoisonError<T>> for AppError {
     fn from(error: std::sync::PoisonError<T>) -> Self {
         eprintln!("[ERROR] Mutex lock poisoned: {}", error);
-        AppError(error.to_string())
+        AppError::new(error.to_string())
     }
 }
 
 impl From<tauri::Error> for AppError {
     fn from(error: tauri::Error) -> Self {
         eprintln!("[ERROR] Tauri error: {}", error);
-        AppError(error.to_string())
+        AppError::new(error.to_string())
+    }
+}
+
+impl From<serde_json::Error> for AppError {
+    fn from(error: serde_json::Error) -> Self {
+        eprintln!("[ERROR] JSON error: {}", error);
+        AppError::new(error.to_string())
+    }
+}
+
+impl From<tauri_plugin_store::Error> for AppError {
+    fn from(error: tauri_plugin_store::Error) -> Self {
+        eprintln!("[ERROR] Store error: {}", error);
+        AppError::new(error.to_string())
     }
 }
 
 impl From<tauri_plugin_clipboard_manager::Error> for AppError {
     fn from(error: tauri_plugin_clipboard_manager::Error) -> Self {
         eprintln!("[ERROR] Clipboard error: {}", error);
-        AppError(error.to_string())
+        AppError::new(error.to_string())
     }
 }
 
 impl From<tauri_plugin_global_shortcut::Error> for AppError {
     fn from(error: tauri_plugin_global_shortcut::Error) -> Self {
         eprintln!("[ERROR] Global shortcut error: {}", error);
-        AppError(error.to_string())
+        AppError::new(error.to_string())
+    }
+}
+
+impl From<wasmtime::Error> for AppError {
+    fn from(error: wasmtime::Error) -> Self {
+        eprintln!("[ERROR] WebAssembly error: {:#}", error);
+        AppError::new(format!("{:#}", error))
     }
 }
 
 impl From<enigo::InputError> for AppError {
     fn from(error: enigo::InputError) -> Self {
         eprintln!("[ERROR] Enigo input error: {}", error);
-        AppError(error.to_string())
+        AppError::new(error.to_string())
     }
 }
 
@@ -84,6 +153,6 @@ impl From<&mut enigo::NewConError> for AppError {
 impl From<&enigo::NewConError> for AppError {
     fn from(error: &enigo::NewConError) -> Self {
         eprintln!("[ERROR] Enigo initialization error: {}", error);
-        AppError(error.to_string())
+        AppError::new(error.to_string())
     }
 }
@@ -1,14 +1,20 @@
+mod cache;
+pub mod classify;
 mod commands;
+mod engine;
 mod error;
+mod llm;
 mod platform;
+mod script;
+mod template;
 
 use commands::*;
 use enigo::{Enigo, Settings};
 use std::{
     collections::HashMap,
-    sync::{LazyLock, Mutex},
+    sync::{Arc, LazyLock, Mutex},
 };
-use tauri::{Emitter, Manager, RunEvent, WindowEvent};
+use tauri::{Manager, RunEvent, WindowEvent};
 
 // Global, shared Enigo wrapped in a Mutex
 // The Enigo struct should be created once and then reused for efficiency
@@ -19,38 +25,88 @@ pub static ENIGO: LazyLock<Mutex<Result<Enigo, enigo::NewConError>>> =
 pub static REGISTERED_SHORTCUTS: LazyLock<Mutex<HashMap<String, String>>> =
     LazyLock::new(|| Mutex::new(HashMap::new()));
 
+// Global running script executions, mapping execution ID to its cancel signal
+pub static RUNNING_EXECUTIONS: LazyLock<Mutex<HashMap<String, Arc<tokio::sync::Notify>>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+// Global idle script workers, grouped by runtime
+pub static IDLE_WORKERS: LazyLock<Mutex<HashMap<String, Vec<script::Worker>>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+// Global lock serializing dependency installs, so concurrent runs never build the same environment twice
+pub static INSTALLING_DEPENDENCIES: LazyLock<tokio::sync::Mutex<()>> =
+    LazyLock::new(|| tokio::sync::Mutex::new(()));
+
+// Global watched scripts directory and the scripts last loaded from it
+pub static SCRIPT_LIBRARY: LazyLock<Mutex<Option<script::WatchedLibrary>>> =
+    LazyLock::new(|| Mutex::new(None));
+
+// Global cached runtime detection results, keyed by the options they were detected with
+pub static DETECTED_RUNTIMES: LazyLock<Mutex<script::DetectionCache>> =
+    LazyLock::new(|| Mutex::new(None));
+
+// Serializes reads and rewrites of the execution audit log file
+pub static AUDIT_LOG: LazyLock<Mutex<()>> = LazyLock::new(|| Mutex::new(()));
+
+// Serializes reads and writes of stored multi-turn chat sessions
+pub static CHAT_SESSIONS: LazyLock<Mutex<()>> = LazyLock::new(|| Mutex::new(()));
+
+// Serializes reads, writes and evictions of cached action outputs
+pub static RESPONSE_CACHE: LazyLock<Mutex<()>> = LazyLock::new(|| Mutex::new(()));
+
+// Global HTTP client shared by LLM providers, reusing connections between requests
+pub static HTTP_CLIENT: LazyLock<reqwest::Client> = LazyLock::new(|| {
+    reqwest::Client::builder()
+        .connect_timeout(llm::CONNECT_TIMEOUT)
+        .build()
+        .unwrap_or_default()
+});
+
+// Global compiled WebAssembly modules, keyed by the hash of their contents
+pub static WASM_MODULES: LazyLock<Mutex<script::ModuleCache>> =
+    LazyLock::new(|| Mutex::new(script::ModuleCache::default()));
+
+// Global action scheduler enforcing per-action concurrency policies and the global cap
+pub static SCHEDULER: LazyLock<engine::Scheduler> = LazyLock::new(engine::Scheduler::default);
+
+// Global held shortcuts, keyed by shortcut id, with the time of their last pressed event
+pub static HELD_SHORTCUTS: LazyLock<Mutex<HashMap<u32, std::time::Instant>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
 /// 全局快捷键处理函数
 fn handle_global_shortcut(
     app: &tauri::AppHandle,
     shortcut: &tauri_plugin_global_shortcut::Shortcut,
     event: tauri_plugin_global_shortcut::ShortcutEvent,
 ) {
-    if event.state() == tauri_plugin_global_shortcut::ShortcutState::Pressed {
-        // 从 shortcut.key 格式化字符串中提取最后一个字符
-        let key_str = format!("{}", shortcut.key);
-        let key_char = key_str.chars().last().unwrap_or('?').to_string();
-
-        let app_clone = app.clone();
-        let key_char_clone = key_char.clone();
-
-        // 异步获取选中文本并发送事件到前端
-        tauri::async_runtime::spawn(async move {
-            match get_selection(app_clone.clone()).await {
-                Ok(selection) => {
-                    let event_data = serde_json::json!({
-                        "key": key_char_clone,
-                        "selection": selection
-                    });
-                    if let Err(e) = app_clone.emit("shortcut-triggered", event_data) {
-                        eprintln!("[ERROR] Failed to emit shortcut event: {}", e);
-                    }
-                }
-                Err(e) => {
-                    eprintln!("[ERROR] Failed to get selection: {}", e);
-                }
-            }
-        });
+    if event.state() == tauri_plugin_global_shortcut::ShortcutState::Released {
+        engine::key_released(shortcut.id());
+        return;
+    }
+    // 按住快捷键时系统会重复发送按下事件，只处理第一次
+    if !engine::key_pressed(shortcut.id()) {
+        return;
     }
+
+    // 从 shortcut.key 格式化字符串中提取最后一个字符
+    let key_str = format!("{}", shortcut.key);
+    let key_char = key_str.chars().last().unwrap_or('?').to_string();
+
+    let app_clone = app.clone();
+    let key_char_clone = key_char.clone();
+
+    // 异步获取选中文本，并在后端匹配规则和执行动作
+    tauri::async_runtime::spawn(async move {
+        // 依次获取选中文本，避免多次触发同时使用剪贴板
+        match SCHEDULER.grab(get_selection(app_clone.clone())).await {
+            Ok(selection) => {
+                engine::dispatch(app_clone, key_char_clone, selection).await;
+            }
+            Err(e) => {
+                eprintln!("[ERROR] Failed to get selection: {}", e);
+            }
+        }
+    });
 }
 
 /// 应用设置函数
@@ -67,6 +123,9 @@ fn setup_app(app: &mut tauri::App) -> Result<(), Box<dyn std::error::Error>> {
     )
     .ok();
 
+    // 加载并监听脚本目录
+    start_script_library(&app_handle);
+
     // 获取主窗口
     if let Some(window) = app.get_webview_window("main") {
         let app_handle = window.app_handle().clone();
@@ -129,11 +188,17 @@ fn handle_run_event(app: &tauri::AppHandle, event: RunEvent) {
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
+    // Re-invoked as the embedded JavaScript engine
+    if let Some(code) = script::embedded_main() {
+        std::process::exit(code);
+    }
+
     tauri::Builder::default()
         .plugin(tauri_plugin_os::init())
         .plugin(tauri_plugin_dialog::init())
         .plugin(tauri_plugin_opener::init())
         .plugin(tauri_plugin_clipboard_manager::init())
+        .plugin(tauri_plugin_notification::init())
         .plugin(tauri_plugin_store::Builder::default().build())
         .plugin(
             tauri_plugin_global_shortcut::Builder::new()
@@ -151,11 +216,33 @@ pub fn run() {
             is_shortcut_registered,
             is_editable,
             get_selection,
-            execute_python,
-            execute_javascript,
+            execute_script,
+            detect_runtimes,
+            install_dependencies,
+            run_script_tests,
+            query_audit_log,
+            clear_audit_log,
+            get_script_library,
+            set_scripts_dir,
+            set_secret,
+            delete_secret,
+            has_secret,
+            execute_command,
+            deliver_result,
+            rerun_action,
+            clear_cache,
+            stream_chat,
+            chat_session,
+            get_session,
+            delete_session,
+            classify_text,
+            render_template,
+            validate_template,
+            cancel_execution,
             send_copy_key,
             send_paste_key,
             show_popup,
+            update_popup,
             show_about,
             setup_tray
         ])
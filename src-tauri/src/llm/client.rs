@@ -0,0 +1,473 @@
+use super::provider::{
+    error_message, ChatRequest, Chunk, Endpoint, LlmOptions, ToolCall, ToolCallDelta,
+};
+use crate::error::AppError;
+use crate::script::Execution;
+use crate::HTTP_CLIENT;
+use futures_util::StreamExt;
+use reqwest::StatusCode;
+use serde_json::Value;
+use std::time::Duration;
+
+/// 一次对话的默认超时时间（秒），包括等待和生成回复的全部时间
+pub const LLM_TIMEOUT_SECS: u64 = 300;
+
+/// 建立连接的超时时间
+pub const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// 等待响应开始的超时时间，首次调用时模型可能需要加载
+const RESPONSE_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// 两段输出之间的最长间隔
+const IDLE_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// 连接失败或服务暂时不可用时的最多尝试次数
+const MAX_ATTEMPTS: u32 = 3;
+
+/// 第一次重试前的等待时间，之后每次加倍
+const RETRY_DELAY: Duration = Duration::from_millis(500);
+
+/// 模型的一次回复
+#[derive(Debug, Default)]
+pub struct Reply {
+    /// 生成的文本
+    pub content: String,
+    /// 请求的工具调用
+    pub tool_calls: Vec<ToolCall>,
+}
+
+/// 流式调用大模型，每生成一段文本调用一次 `on_token`，返回完整的回复
+///
+/// 总时间受执行的超时时间限制，可以通过执行标识取消。连接失败或服务返回
+/// 429、502、503、504 时自动重试，开始输出后不再重试
+pub async fn chat(
+    request: &ChatRequest,
+    options: &LlmOptions,
+    execution: &Execution,
+    mut on_token: impl FnMut(&str),
+) -> Result<String, AppError> {
+    let endpoint = options.endpoint(request.provider).await?;
+    let reply = execution
+        .supervise(None, complete(&endpoint, request, &mut on_token))
+        .await?;
+    Ok(reply.content)
+}
+
+/// 流式调用一次大模型，返回生成的文本和请求的工具调用
+///
+/// 不限制时间，由调用方通过执行统一限制
+pub async fn complete(
+    endpoint: &Endpoint,
+    request: &ChatRequest,
+    on_token: &mut impl FnMut(&str),
+) -> Result<Reply, AppError> {
+    let response = send(endpoint, request).await?;
+    read_stream(endpoint, response, on_token).await
+}
+
+/// 发送请求，按需重试，返回状态正常的响应
+async fn send(endpoint: &Endpoint, request: &ChatRequest) -> Result<reqwest::Response, AppError> {
+    let name = endpoint.provider.name();
+    let body = endpoint.body(request);
+    let mut attempt = 1;
+    loop {
+        let mut builder = HTTP_CLIENT.post(&endpoint.url).json(&body);
+        if let Some(api_key) = &endpoint.api_key {
+            builder = builder.bearer_auth(api_key);
+        }
+        let error = match tokio::time::timeout(RESPONSE_TIMEOUT, builder.send()).await {
+            Ok(Ok(response)) if response.status().is_success() => return Ok(response),
+            Ok(Ok(response)) => {
+                let status = response.status();
+                let text = response.text().await.unwrap_or_default();
+                let detail = serde_json::from_str(&text)
+                    .ok()
+                    .and_then(|value| error_message(&value))
+                    .unwrap_or(text);
+                let error = format!("{} returned {}: {}", name, status, detail.trim());
+                if !is_transient(status) {
+                    return Err(error.into());
+                }
+                error
+            }
+            Ok(Err(e)) if e.is_connect() => {
+                format!("Failed to connect to {} at {}: {}", name, endpoint.url, e)
+            }
+            Ok(Err(e)) => return Err(format!("Request to {} failed: {}", name, e).into()),
+            Err(_) => {
+                return Err(format!(
+                    "{} did not respond within {}s",
+                    name,
+                    RESPONSE_TIMEOUT.as_secs()
+                )
+                .into())
+            }
+        };
+        if attempt >= MAX_ATTEMPTS {
+            return Err(error.into());
+        }
+        eprintln!(
+            "[INFO] {}, retrying ({}/{})",
+            error,
+            attempt,
+            MAX_ATTEMPTS - 1
+        );
+        tokio::time::sleep(RETRY_DELAY * 2u32.pow(attempt - 1)).await;
+        attempt += 1;
+    }
+}
+
+/// 服务暂时不可用，可以稍后重试
+fn is_transient(status: StatusCode) -> bool {
+    matches!(
+        status,
+        StatusCode::TOO_MANY_REQUESTS
+            | StatusCode::BAD_GATEWAY
+            | StatusCode::SERVICE_UNAVAILABLE
+            | StatusCode::GATEWAY_TIMEOUT
+    )
+}
+
+/// 按行读取流式响应
+async fn read_stream(
+    endpoint: &Endpoint,
+    response: reqwest::Response,
+    on_token: &mut impl FnMut(&str),
+) -> Result<Reply, AppError> {
+    let mut stream = response.bytes_stream();
+    let mut buffer = Vec::new();
+    let mut reply = String::new();
+    let mut calls = Vec::new();
+    let mut handle = |line: &[u8], reply: &mut String| match endpoint
+        .parse_line(&String::from_utf8_lossy(line))?
+    {
+        Chunk::Token(token) => {
+            on_token(&token);
+            reply.push_str(&token);
+            Ok(false)
+        }
+        Chunk::ToolCalls(deltas) => {
+            deltas
+                .into_iter()
+                .for_each(|delta| merge(&mut calls, delta));
+            Ok(false)
+        }
+        Chunk::Done => Ok(true),
+        Chunk::Empty => Ok::<_, AppError>(false),
+    };
+
+    let mut done = false;
+    while !done {
+        let chunk = match tokio::time::timeout(IDLE_TIMEOUT, stream.next()).await {
+            Ok(Some(chunk)) => chunk.map_err(|e| {
+                format!("Connection to {} was lost: {}", endpoint.provider.name(), e)
+            })?,
+            Ok(None) => break,
+            Err(_) => {
+                return Err(format!(
+                    "{} stopped responding for {}s",
+                    endpoint.provider.name(),
+                    IDLE_TIMEOUT.as_secs()
+                )
+                .into())
+            }
+        };
+        buffer.extend_from_slice(&chunk);
+        // 一段数据可能包含多行，也可能只有半行，只处理完整的行
+        while let Some(end) = buffer.iter().position(|byte| *byte == b'\n') {
+            let line: Vec<u8> = buffer.drain(..=end).collect();
+            if handle(&line, &mut reply)? {
+                done = true;
+                break;
+            }
+        }
+    }
+    if !done {
+        handle(&buffer, &mut reply)?;
+    }
+    Ok(Reply {
+        content: reply,
+        tool_calls: calls.into_iter().enumerate().map(finish).collect(),
+    })
+}
+
+/// 合并工具调用片段，没有序号的片段视为一次新的调用
+fn merge(calls: &mut Vec<ToolCallDelta>, delta: ToolCallDelta) {
+    let Some(index) = delta.index else {
+        calls.push(delta);
+        return;
+    };
+    match calls.iter_mut().find(|call| call.index == Some(index)) {
+        Some(call) => {
+            call.id = call.id.take().or(delta.id);
+            call.name = call.name.take().or(delta.name);
+            call.arguments.push_str(&delta.arguments);
+        }
+        None => calls.push(delta),
+    }
+}
+
+/// 将合并后的片段转换为完整的工具调用
+fn finish((index, call): (usize, ToolCallDelta)) -> ToolCall {
+    let arguments = if call.arguments.trim().is_empty() {
+        Value::Object(Default::default())
+    } else {
+        serde_json::from_str(&call.arguments).unwrap_or(Value::String(call.arguments))
+    };
+    ToolCall {
+        id: call.id.unwrap_or_else(|| format!("call_{}", index)),
+        name: call.name.unwrap_or_default(),
+        arguments,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ErrorKind;
+    use crate::llm::{ChatMessage, Provider};
+    use crate::script;
+    use std::future::Future;
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::TcpListener;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::time::Instant;
+    use tokio::sync::Notify;
+
+    /// 本地模拟服务的一次响应
+    struct MockResponse {
+        /// 状态行，例如 `200 OK`
+        status: &'static str,
+        /// 分段发送的响应内容
+        parts: Vec<&'static str>,
+        /// 发送完毕后保持连接的时间
+        hold: Duration,
+    }
+
+    impl MockResponse {
+        fn new(status: &'static str, parts: Vec<&'static str>) -> Self {
+            Self {
+                status,
+                parts,
+                hold: Duration::ZERO,
+            }
+        }
+    }
+
+    /// 启动依次返回预设响应的本地服务，返回服务地址和收到的请求数量
+    fn serve(responses: Vec<MockResponse>) -> (String, Arc<AtomicUsize>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap().to_string();
+        let requests = Arc::new(AtomicUsize::new(0));
+        let counter = requests.clone();
+        std::thread::spawn(move || {
+            for response in responses {
+                let Ok((mut stream, _)) = listener.accept() else {
+                    return;
+                };
+                // 读取请求头和请求体，避免客户端写入时连接被重置
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                let mut length = 0;
+                loop {
+                    let mut line = String::new();
+                    if reader.read_line(&mut line).unwrap_or(0) == 0 || line == "\r\n" {
+                        break;
+                    }
+                    if let Some((name, value)) = line.split_once(':') {
+                        if name.eq_ignore_ascii_case("content-length") {
+                            length = value.trim().parse().unwrap_or(0);
+                        }
+                    }
+                }
+                let mut body = vec![0; length];
+                let _ = reader.read_exact(&mut body);
+                counter.fetch_add(1, Ordering::SeqCst);
+
+                let head = format!(
+                    "HTTP/1.1 {}\r\nContent-Type: text/event-stream\r\nConnection: close\r\n\r\n",
+                    response.status
+                );
+                let _ = stream.write_all(head.as_bytes());
+                for part in response.parts {
+                    let _ = stream.write_all(part.as_bytes());
+                    let _ = stream.flush();
+                    std::thread::sleep(Duration::from_millis(20));
+                }
+                std::thread::sleep(response.hold);
+            }
+        });
+        (address, requests)
+    }
+
+    fn options(address: &str) -> LlmOptions {
+        LlmOptions {
+            lm_studio_host: Some(address.to_string()),
+            ..Default::default()
+        }
+    }
+
+    fn request() -> ChatRequest {
+        ChatRequest {
+            provider: Provider::LmStudio,
+            model: "test".to_string(),
+            messages: vec![ChatMessage::new("user", "hi")],
+            tools: Vec::new(),
+            temperature: None,
+        }
+    }
+
+    fn block_on<T>(task: impl Future<Output = T>) -> T {
+        tokio::runtime::Runtime::new().unwrap().block_on(task)
+    }
+
+    #[test]
+    fn streams_tokens_split_across_chunks() {
+        let (address, requests) = serve(vec![MockResponse::new(
+            "200 OK",
+            vec![
+                "data: {\"choices\":[{\"delta\":{\"content\":\"Hel\"}}]}\n\ndata: {\"choi",
+                "ces\":[{\"delta\":{\"content\":\"lo\"}}]}\n\n",
+                "data: [DONE]\n\n",
+            ],
+        )]);
+        let execution = Execution::new(Some(10)).unwrap();
+        let mut tokens = Vec::new();
+        let reply = block_on(chat(&request(), &options(&address), &execution, |token| {
+            tokens.push(token.to_string())
+        }))
+        .unwrap();
+        assert_eq!(reply, "Hello");
+        assert_eq!(tokens, ["Hel", "lo"]);
+        assert_eq!(requests.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn retries_transient_errors() {
+        let (address, requests) = serve(vec![
+            MockResponse::new("503 Service Unavailable", vec!["{\"error\":\"loading\"}"]),
+            MockResponse::new("429 Too Many Requests", vec!["slow down"]),
+            MockResponse::new(
+                "200 OK",
+                vec!["data: {\"choices\":[{\"delta\":{\"content\":\"ok\"}}]}\n\n"],
+            ),
+        ]);
+        let execution = Execution::new(Some(10)).unwrap();
+        let reply = block_on(chat(&request(), &options(&address), &execution, |_| {})).unwrap();
+        assert_eq!(reply, "ok");
+        assert_eq!(requests.load(Ordering::SeqCst), 3);
+    }
+
+    #[test]
+    fn gives_up_after_max_attempts() {
+        let responses = (0..MAX_ATTEMPTS)
+            .map(|_| MockResponse::new("502 Bad Gateway", vec!["{\"error\":\"upstream\"}"]))
+            .collect();
+        let (address, requests) = serve(responses);
+        let execution = Execution::new(Some(10)).unwrap();
+        let error = block_on(chat(&request(), &options(&address), &execution, |_| {}))
+            .unwrap_err()
+            .to_string();
+        assert!(error.contains("502"), "{}", error);
+        assert!(error.contains("upstream"), "{}", error);
+        assert_eq!(requests.load(Ordering::SeqCst), MAX_ATTEMPTS as usize);
+    }
+
+    #[test]
+    fn does_not_retry_client_errors() {
+        let (address, requests) = serve(vec![
+            MockResponse::new("400 Bad Request", vec!["{\"error\":{\"message\":\"bad\"}}"]),
+            MockResponse::new("200 OK", vec!["data: [DONE]\n\n"]),
+        ]);
+        let execution = Execution::new(Some(10)).unwrap();
+        let error = block_on(chat(&request(), &options(&address), &execution, |_| {}))
+            .unwrap_err()
+            .to_string();
+        assert!(error.contains("400"), "{}", error);
+        assert_eq!(requests.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn cancels_a_running_stream() {
+        let (address, _) = serve(vec![MockResponse {
+            hold: Duration::from_secs(10),
+            ..MockResponse::new(
+                "200 OK",
+                vec!["data: {\"choices\":[{\"delta\":{\"content\":\"Hel\"}}]}\n\n"],
+            )
+        }]);
+        let cancel = Arc::new(Notify::new());
+        let execution = Execution::new(Some(30)).unwrap().cancelled_by(&cancel);
+        let mut tokens = Vec::new();
+        let started = Instant::now();
+        let (request, options) = (request(), options(&address));
+        let error = block_on(async {
+            let signal = async {
+                tokio::time::sleep(Duration::from_millis(300)).await;
+                script::signal_cancel(&cancel);
+                std::future::pending::<()>().await
+            };
+            let chat = chat(&request, &options, &execution, |token| {
+                tokens.push(token.to_string())
+            });
+            tokio::select! {
+                result = chat => result,
+                _ = signal => unreachable!(),
+            }
+        })
+        .unwrap_err();
+        assert_eq!(error.kind(), Some(ErrorKind::Cancelled));
+        assert_eq!(tokens, ["Hel"]);
+        assert!(started.elapsed() < Duration::from_secs(5));
+    }
+
+    #[test]
+    fn times_out_a_stalled_stream() {
+        let (address, _) = serve(vec![MockResponse {
+            hold: Duration::from_secs(10),
+            ..MockResponse::new("200 OK", Vec::new())
+        }]);
+        let execution = Execution::new(Some(1)).unwrap();
+        let error = block_on(chat(&request(), &options(&address), &execution, |_| {})).unwrap_err();
+        assert_eq!(error.kind(), Some(ErrorKind::Timeout));
+    }
+
+    #[test]
+    fn merges_tool_call_fragments() {
+        let delta = |index, id: Option<&str>, name: Option<&str>, arguments: &str| ToolCallDelta {
+            index,
+            id: id.map(str::to_string),
+            name: name.map(str::to_string),
+            arguments: arguments.to_string(),
+        };
+        let mut calls = Vec::new();
+        merge(
+            &mut calls,
+            delta(Some(0), Some("a"), Some("upper"), "{\"te"),
+        );
+        merge(&mut calls, delta(Some(1), Some("b"), Some("lower"), ""));
+        merge(&mut calls, delta(Some(0), None, None, "xt\":\"hi\"}"));
+        merge(&mut calls, delta(None, None, Some("trim"), "{}"));
+        merge(&mut calls, delta(None, None, Some("trim"), "not json"));
+        let calls: Vec<ToolCall> = calls.into_iter().enumerate().map(finish).collect();
+
+        assert_eq!(calls.len(), 4);
+        assert_eq!(calls[0].id, "a");
+        assert_eq!(calls[0].name, "upper");
+        assert_eq!(calls[0].arguments, serde_json::json!({ "text": "hi" }));
+        assert_eq!(calls[1].arguments, serde_json::json!({}));
+        assert_eq!(calls[2].id, "call_2");
+        assert_eq!(calls[3].id, "call_3");
+        assert_eq!(calls[3].arguments, Value::String("not json".to_string()));
+    }
+
+    #[test]
+    fn retries_only_transient_statuses() {
+        for status in [429, 502, 503, 504] {
+            assert!(is_transient(StatusCode::from_u16(status).unwrap()));
+        }
+        for status in [400, 401, 404, 500] {
+            assert!(!is_transient(StatusCode::from_u16(status).unwrap()));
+        }
+    }
+}
@@ -0,0 +1,8 @@
+mod client;
+mod provider;
+mod session;
+mod tools;
+
+pub use client::*;
+pub use provider::*;
+pub use session::*;
@@ -0,0 +1,552 @@
+use crate::error::AppError;
+use crate::script;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+/// Ollama 默认服务地址
+const DEFAULT_OLLAMA_HOST: &str = "http://127.0.0.1:11434";
+
+/// LM Studio 默认服务地址
+const DEFAULT_LM_STUDIO_HOST: &str = "http://127.0.0.1:1234";
+
+/// 大模型服务的提供方
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Provider {
+    /// Ollama
+    #[default]
+    Ollama,
+    /// LM Studio
+    LmStudio,
+    /// 兼容 OpenAI 接口的服务，例如 llama.cpp、vLLM 和 LocalAI
+    OpenAi,
+}
+
+impl Provider {
+    /// 提供方名称
+    pub fn name(self) -> &'static str {
+        match self {
+            Provider::Ollama => "Ollama",
+            Provider::LmStudio => "LM Studio",
+            Provider::OpenAi => "OpenAI-compatible server",
+        }
+    }
+}
+
+/// 大模型服务的连接选项
+#[derive(Debug, Clone, Default)]
+pub struct LlmOptions {
+    /// Ollama 服务地址，为空时使用默认地址
+    pub ollama_host: Option<String>,
+    /// LM Studio 服务地址，为空时使用默认地址
+    pub lm_studio_host: Option<String>,
+    /// 兼容 OpenAI 接口的服务地址，包含 `/v1` 等路径前缀
+    pub openai_base_url: Option<String>,
+    /// 保存 API 密钥的密钥名称
+    pub openai_api_key: Option<String>,
+    /// 多轮对话的上下文长度 (token)，为空时使用默认长度
+    pub context_tokens: Option<usize>,
+}
+
+impl LlmOptions {
+    /// 获取提供方的对话接口
+    pub async fn endpoint(&self, provider: Provider) -> Result<Endpoint, AppError> {
+        let host = |host: &Option<String>, default: &str| {
+            let host = host
+                .as_deref()
+                .map(str::trim)
+                .filter(|host| !host.is_empty())
+                .unwrap_or(default);
+            let host = host.trim_end_matches('/');
+            if host.contains("://") {
+                host.to_string()
+            } else {
+                format!("http://{}", host)
+            }
+        };
+        let endpoint = match provider {
+            Provider::Ollama => Endpoint {
+                provider,
+                url: format!("{}/api/chat", host(&self.ollama_host, DEFAULT_OLLAMA_HOST)),
+                api_key: None,
+            },
+            Provider::LmStudio => Endpoint {
+                provider,
+                url: format!(
+                    "{}/v1/chat/completions",
+                    host(&self.lm_studio_host, DEFAULT_LM_STUDIO_HOST)
+                ),
+                api_key: None,
+            },
+            Provider::OpenAi => {
+                if self.openai_base_url.as_deref().is_none_or(str::is_empty) {
+                    return Err("The OpenAI-compatible server address is not set".into());
+                }
+                let api_key = match self.openai_api_key.as_deref().filter(|key| !key.is_empty()) {
+                    Some(name) => Some(script::get_secret(name.to_string()).await?),
+                    None => None,
+                };
+                Endpoint {
+                    provider,
+                    url: format!("{}/chat/completions", host(&self.openai_base_url, "")),
+                    api_key,
+                }
+            }
+        };
+        Ok(endpoint)
+    }
+}
+
+/// 对话消息
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ChatMessage {
+    /// 角色：system、user、assistant 或 tool
+    pub role: String,
+    /// 消息内容
+    #[serde(default)]
+    pub content: String,
+    /// 模型请求的工具调用
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tool_calls: Vec<ToolCall>,
+    /// 工具结果对应的调用标识
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tool_call_id: Option<String>,
+    /// 返回结果的工具名称
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+}
+
+impl ChatMessage {
+    /// 创建消息
+    pub fn new(role: &str, content: impl Into<String>) -> Self {
+        Self {
+            role: role.to_string(),
+            content: content.into(),
+            tool_calls: Vec::new(),
+            tool_call_id: None,
+            name: None,
+        }
+    }
+
+    /// 创建工具调用的结果消息
+    pub fn tool_result(call: &ToolCall, content: impl Into<String>) -> Self {
+        Self {
+            tool_call_id: Some(call.id.clone()),
+            name: Some(call.name.clone()),
+            ..Self::new("tool", content)
+        }
+    }
+}
+
+/// 模型请求的一次工具调用
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ToolCall {
+    /// 调用标识，提供方未返回时自动生成
+    pub id: String,
+    /// 工具名称
+    pub name: String,
+    /// 调用参数，无法解析为 JSON 时为原始文本
+    #[serde(default)]
+    pub arguments: Value,
+}
+
+/// 提供给模型的工具
+#[derive(Debug, Clone, Serialize)]
+pub struct ToolDefinition {
+    /// 工具名称
+    pub name: String,
+    /// 工具说明
+    pub description: String,
+    /// 参数的 JSON Schema
+    pub parameters: Value,
+}
+
+/// 对话请求
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChatRequest {
+    /// 提供方
+    #[serde(default)]
+    pub provider: Provider,
+    /// 模型名称
+    pub model: String,
+    /// 对话消息
+    pub messages: Vec<ChatMessage>,
+    /// 提供给模型的工具，只在后端设置
+    #[serde(skip)]
+    pub tools: Vec<ToolDefinition>,
+    /// 采样温度，为空时使用模型的默认值
+    #[serde(default)]
+    pub temperature: Option<f64>,
+}
+
+/// 提供方的对话接口
+pub struct Endpoint {
+    /// 提供方
+    pub provider: Provider,
+    /// 接口地址
+    pub url: String,
+    /// API 密钥
+    pub api_key: Option<String>,
+}
+
+/// 流式响应中的一行解析出的内容
+#[derive(Debug, PartialEq, Eq)]
+pub enum Chunk {
+    /// 新生成的文本
+    Token(String),
+    /// 生成结束
+    Done,
+    /// 模型请求的工具调用，可能只是调用的一部分
+    ToolCalls(Vec<ToolCallDelta>),
+    /// 不包含文本的行
+    Empty,
+}
+
+/// 流式响应中的工具调用片段
+///
+/// OpenAI 接口按序号分段发送同一调用的参数，Ollama 每次发送完整的调用
+#[derive(Debug, PartialEq, Eq)]
+pub struct ToolCallDelta {
+    /// 调用序号，为空时表示一次新的调用
+    pub index: Option<usize>,
+    /// 调用标识
+    pub id: Option<String>,
+    /// 工具名称
+    pub name: Option<String>,
+    /// 参数的 JSON 文本片段
+    pub arguments: String,
+}
+
+impl Endpoint {
+    /// 生成流式对话的请求体
+    pub fn body(&self, request: &ChatRequest) -> Value {
+        let messages: Vec<Value> = request
+            .messages
+            .iter()
+            .map(|message| self.message(message))
+            .collect();
+        let mut body = json!({
+            "model": request.model,
+            "messages": messages,
+            "stream": true,
+        });
+        if !request.tools.is_empty() {
+            body["tools"] = request
+                .tools
+                .iter()
+                .map(|tool| json!({ "type": "function", "function": tool }))
+                .collect();
+        }
+        if let Some(temperature) = request.temperature {
+            match self.provider {
+                Provider::Ollama => body["options"] = json!({ "temperature": temperature }),
+                Provider::LmStudio | Provider::OpenAi => body["temperature"] = json!(temperature),
+            }
+        }
+        body
+    }
+
+    /// 转换为提供方的消息格式
+    ///
+    /// Ollama 的调用参数为对象、工具结果以名称对应调用，
+    /// OpenAI 接口的调用参数为 JSON 文本、工具结果以调用标识对应调用
+    fn message(&self, message: &ChatMessage) -> Value {
+        let mut value = json!({ "role": message.role, "content": message.content });
+        if !message.tool_calls.is_empty() {
+            value["tool_calls"] = message
+                .tool_calls
+                .iter()
+                .map(|call| match self.provider {
+                    Provider::Ollama => json!({
+                        "function": { "name": call.name, "arguments": call.arguments }
+                    }),
+                    Provider::LmStudio | Provider::OpenAi => json!({
+                        "id": call.id,
+                        "type": "function",
+                        "function": {
+                            "name": call.name,
+                            "arguments": match &call.arguments {
+                                Value::String(raw) => raw.clone(),
+                                arguments => arguments.to_string(),
+                            },
+                        },
+                    }),
+                })
+                .collect();
+        }
+        match self.provider {
+            Provider::Ollama => {
+                if let Some(name) = &message.name {
+                    value["tool_name"] = json!(name);
+                }
+            }
+            Provider::LmStudio | Provider::OpenAi => {
+                if let Some(id) = &message.tool_call_id {
+                    value["tool_call_id"] = json!(id);
+                }
+            }
+        }
+        value
+    }
+
+    /// 解析流式响应中的一行
+    ///
+    /// Ollama 每行为一个 JSON 对象，其余提供方使用 Server-Sent Events
+    pub fn parse_line(&self, line: &str) -> Result<Chunk, AppError> {
+        let line = line.trim();
+        if line.is_empty() {
+            return Ok(Chunk::Empty);
+        }
+        let data = match self.provider {
+            Provider::Ollama => line,
+            Provider::LmStudio | Provider::OpenAi => match line.strip_prefix("data:") {
+                Some(data) if data.trim() == "[DONE]" => return Ok(Chunk::Done),
+                Some(data) => data.trim(),
+                // 注释、事件名称等其他字段
+                None => return Ok(Chunk::Empty),
+            },
+        };
+        let value: Value = serde_json::from_str(data)
+            .map_err(|e| format!("Invalid response from {}: {}", self.provider.name(), e))?;
+        if let Some(error) = error_message(&value) {
+            return Err(format!("{} error: {}", self.provider.name(), error).into());
+        }
+
+        let calls = match self.provider {
+            Provider::Ollama => &value["message"]["tool_calls"],
+            Provider::LmStudio | Provider::OpenAi => &value["choices"][0]["delta"]["tool_calls"],
+        };
+        if let Some(calls) = calls.as_array().filter(|calls| !calls.is_empty()) {
+            return Ok(Chunk::ToolCalls(
+                calls.iter().map(|call| self.tool_call(call)).collect(),
+            ));
+        }
+
+        let token = match self.provider {
+            Provider::Ollama => {
+                let token = value["message"]["content"].as_str().unwrap_or_default();
+                if value["done"].as_bool() == Some(true) && token.is_empty() {
+                    return Ok(Chunk::Done);
+                }
+                token
+            }
+            Provider::LmStudio | Provider::OpenAi => value["choices"][0]["delta"]["content"]
+                .as_str()
+                .unwrap_or_default(),
+        };
+        if token.is_empty() {
+            Ok(Chunk::Empty)
+        } else {
+            Ok(Chunk::Token(token.to_string()))
+        }
+    }
+
+    /// 解析响应中的一个工具调用
+    fn tool_call(&self, call: &Value) -> ToolCallDelta {
+        let function = &call["function"];
+        let arguments = match &function["arguments"] {
+            Value::String(arguments) => arguments.clone(),
+            Value::Null => String::new(),
+            arguments => arguments.to_string(),
+        };
+        ToolCallDelta {
+            index: match self.provider {
+                Provider::Ollama => None,
+                Provider::LmStudio | Provider::OpenAi => {
+                    call["index"].as_u64().map(|index| index as usize)
+                }
+            },
+            id: call["id"].as_str().map(str::to_string),
+            name: function["name"].as_str().map(str::to_string),
+            arguments,
+        }
+    }
+}
+
+/// 提取响应中的错误信息，兼容 `{"error": "..."}` 和 `{"error": {"message": "..."}}`
+pub fn error_message(value: &Value) -> Option<String> {
+    match &value["error"] {
+        Value::String(message) => Some(message.clone()),
+        Value::Object(error) => Some(
+            error
+                .get("message")
+                .and_then(Value::as_str)
+                .unwrap_or("unknown error")
+                .to_string(),
+        ),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn endpoint(provider: Provider) -> Endpoint {
+        Endpoint {
+            provider,
+            url: String::new(),
+            api_key: None,
+        }
+    }
+
+    fn request() -> ChatRequest {
+        let call = ToolCall {
+            id: "call_1".to_string(),
+            name: "upper".to_string(),
+            arguments: json!({ "text": "hi" }),
+        };
+        ChatRequest {
+            provider: Provider::Ollama,
+            model: "llama3".to_string(),
+            messages: vec![
+                ChatMessage::new("user", "hi"),
+                ChatMessage {
+                    tool_calls: vec![call.clone()],
+                    ..ChatMessage::new("assistant", "")
+                },
+                ChatMessage::tool_result(&call, "HI"),
+            ],
+            tools: vec![ToolDefinition {
+                name: "upper".to_string(),
+                description: "Uppercase".to_string(),
+                parameters: json!({ "type": "object" }),
+            }],
+            temperature: Some(0.0),
+        }
+    }
+
+    #[test]
+    fn builds_ollama_body() {
+        let body = endpoint(Provider::Ollama).body(&request());
+        assert_eq!(body["model"], "llama3");
+        assert_eq!(body["stream"], true);
+        assert_eq!(body["options"]["temperature"], 0.0);
+        assert!(body.get("temperature").is_none());
+        assert_eq!(body["tools"][0]["type"], "function");
+        assert_eq!(body["tools"][0]["function"]["name"], "upper");
+        let messages = body["messages"].as_array().unwrap();
+        assert_eq!(
+            messages[1]["tool_calls"][0]["function"]["arguments"],
+            json!({ "text": "hi" })
+        );
+        assert_eq!(messages[2]["tool_name"], "upper");
+        assert!(messages[2].get("tool_call_id").is_none());
+    }
+
+    #[test]
+    fn builds_openai_compatible_body() {
+        for provider in [Provider::LmStudio, Provider::OpenAi] {
+            let body = endpoint(provider).body(&request());
+            assert_eq!(body["temperature"], 0.0);
+            assert!(body.get("options").is_none());
+            let messages = body["messages"].as_array().unwrap();
+            let call = &messages[1]["tool_calls"][0];
+            assert_eq!(call["id"], "call_1");
+            assert_eq!(call["function"]["arguments"], r#"{"text":"hi"}"#);
+            assert_eq!(messages[2]["tool_call_id"], "call_1");
+            assert!(messages[2].get("tool_name").is_none());
+        }
+    }
+
+    #[test]
+    fn omits_unset_tools_and_temperature() {
+        let request = ChatRequest {
+            tools: Vec::new(),
+            temperature: None,
+            ..request()
+        };
+        let body = endpoint(Provider::OpenAi).body(&request);
+        assert!(body.get("tools").is_none());
+        assert!(body.get("temperature").is_none());
+    }
+
+    #[test]
+    fn parses_ollama_lines() {
+        let ollama = endpoint(Provider::Ollama);
+        assert_eq!(
+            ollama
+                .parse_line(r#"{"message":{"content":"Hel"},"done":false}"#)
+                .unwrap(),
+            Chunk::Token("Hel".to_string())
+        );
+        assert_eq!(
+            ollama
+                .parse_line(r#"{"message":{"content":""},"done":true}"#)
+                .unwrap(),
+            Chunk::Done
+        );
+        assert_eq!(ollama.parse_line("  ").unwrap(), Chunk::Empty);
+        assert_eq!(
+            ollama
+                .parse_line(
+                    r#"{"message":{"tool_calls":[{"function":{"name":"upper","arguments":{"text":"hi"}}}]}}"#
+                )
+                .unwrap(),
+            Chunk::ToolCalls(vec![ToolCallDelta {
+                index: None,
+                id: None,
+                name: Some("upper".to_string()),
+                arguments: r#"{"text":"hi"}"#.to_string(),
+            }])
+        );
+        let error = ollama.parse_line(r#"{"error":"model not found"}"#);
+        assert!(error.unwrap_err().to_string().contains("model not found"));
+        assert!(ollama.parse_line("not json").is_err());
+    }
+
+    #[test]
+    fn parses_server_sent_events() {
+        for provider in [Provider::LmStudio, Provider::OpenAi] {
+            let endpoint = endpoint(provider);
+            assert_eq!(
+                endpoint
+                    .parse_line(r#"data: {"choices":[{"delta":{"content":"Hel"}}]}"#)
+                    .unwrap(),
+                Chunk::Token("Hel".to_string())
+            );
+            assert_eq!(endpoint.parse_line("data: [DONE]").unwrap(), Chunk::Done);
+            assert_eq!(endpoint.parse_line(": keep-alive").unwrap(), Chunk::Empty);
+            assert_eq!(endpoint.parse_line("event: message").unwrap(), Chunk::Empty);
+            assert_eq!(
+                endpoint
+                    .parse_line(r#"data: {"choices":[{"delta":{"role":"assistant"}}]}"#)
+                    .unwrap(),
+                Chunk::Empty
+            );
+            assert_eq!(
+                endpoint
+                    .parse_line(
+                        r#"data: {"choices":[{"delta":{"tool_calls":[{"index":0,"id":"call_1","function":{"name":"upper","arguments":"{\"te"}}]}}]}"#
+                    )
+                    .unwrap(),
+                Chunk::ToolCalls(vec![ToolCallDelta {
+                    index: Some(0),
+                    id: Some("call_1".to_string()),
+                    name: Some("upper".to_string()),
+                    arguments: r#"{"te"#.to_string(),
+                }])
+            );
+            let error = endpoint.parse_line(r#"data: {"error":{"message":"rate limited"}}"#);
+            assert!(error.unwrap_err().to_string().contains("rate limited"));
+        }
+    }
+
+    #[test]
+    fn extracts_error_messages() {
+        assert_eq!(
+            error_message(&json!({ "error": "bad model" })),
+            Some("bad model".to_string())
+        );
+        assert_eq!(
+            error_message(&json!({ "error": { "message": "bad key" } })),
+            Some("bad key".to_string())
+        );
+        assert_eq!(
+            error_message(&json!({ "error": { "code": 1 } })),
+            Some("unknown error".to_string())
+        );
+        assert_eq!(error_message(&json!({ "message": {} })), None);
+    }
+}
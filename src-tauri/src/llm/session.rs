@@ -0,0 +1,447 @@
+use super::provider::{ChatMessage, ChatRequest, Provider};
+use super::tools::{chat_with_tools, Toolbox};
+use crate::cache;
+use crate::engine::{Config, Entry, PROMPT_MARK};
+use crate::error::{AppError, ErrorKind};
+use crate::script::Execution;
+use crate::CHAT_SESSIONS;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::sync::Mutex;
+use tauri::Manager;
+
+/// 保存对话的目录，每个对话一个 JSON 文件
+const SESSIONS_DIR: &str = "sessions";
+
+/// 保存的对话数量上限，超出时删除最久未更新的对话
+const MAX_SESSIONS: usize = 100;
+
+/// 默认的上下文长度 (token)
+pub const DEFAULT_CONTEXT_TOKENS: usize = 8192;
+
+/// 每条消息除内容外额外占用的 token 数
+const MESSAGE_OVERHEAD_TOKENS: usize = 4;
+
+/// 多轮对话，标识与触发记录的标识相同
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Session {
+    /// 对话标识，即触发记录的标识
+    pub id: String,
+    /// 提供方
+    #[serde(default)]
+    pub provider: Provider,
+    /// 模型名称
+    pub model: String,
+    /// 全部消息，包括系统提示词和工具调用
+    #[serde(default)]
+    pub messages: Vec<ChatMessage>,
+    /// 作为工具提供给模型的脚本标识
+    #[serde(default)]
+    pub tools: Vec<String>,
+    /// 创建对话的提示词标识，用于缓存第一条回复
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub action: Option<String>,
+    /// 采样温度，为空时使用模型的默认值
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub temperature: Option<f64>,
+    /// 第一条回复是否来自缓存
+    #[serde(default)]
+    pub cached: bool,
+    /// 最后更新时间
+    #[serde(default)]
+    pub updated_at: String,
+}
+
+impl Session {
+    /// 以提示词的触发记录创建新对话，与弹窗发送提示词时创建的对话相同
+    pub fn from_entry(entry: &Entry) -> Result<Self, AppError> {
+        let provider = match &entry.provider {
+            Some(provider) => serde_json::from_value(serde_json::Value::from(provider.as_str()))?,
+            None => Provider::default(),
+        };
+        let messages = entry
+            .system_prompt
+            .as_deref()
+            .map(str::trim)
+            .filter(|prompt| !prompt.is_empty())
+            .map(|prompt| vec![ChatMessage::new("system", prompt)])
+            .unwrap_or_default();
+        Ok(Self {
+            id: entry.id.clone(),
+            provider,
+            model: entry.model.clone().unwrap_or_default(),
+            messages,
+            tools: entry.tools.clone().unwrap_or_default(),
+            action: entry.action_label.clone(),
+            temperature: entry.temperature,
+            cached: false,
+            updated_at: String::new(),
+        })
+    }
+}
+
+/// 模型的回复
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionReply {
+    /// 回复内容
+    pub content: String,
+    /// 是否来自缓存
+    pub cached: bool,
+}
+
+/// 使用缓存的回复创建对话
+///
+/// 对话已存在或没有可用的缓存时返回 `None`，此时应调用 [`send_message`]
+pub fn cached_reply(
+    app: &tauri::AppHandle,
+    config: &Config,
+    seed: &Session,
+    message: &str,
+) -> Result<Option<SessionReply>, AppError> {
+    let Some((_, key)) = cache_key(config, seed, message) else {
+        return Ok(None);
+    };
+    if load_session(app, &seed.id)?.is_some() {
+        return Ok(None);
+    }
+    let Some(content) = cache::lookup(app, &config.cache, &key) else {
+        return Ok(None);
+    };
+    let mut session = seed.clone();
+    session.cached = true;
+    session.messages.push(ChatMessage::new("user", message));
+    session
+        .messages
+        .push(ChatMessage::new("assistant", content.clone()));
+    session.updated_at = chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true);
+    save_session(app, &session)?;
+    Ok(Some(SessionReply {
+        content,
+        cached: true,
+    }))
+}
+
+/// 删除新对话第一条消息缓存的回复，没有缓存时忽略
+pub fn remove_cached_reply(
+    app: &tauri::AppHandle,
+    config: &Config,
+    seed: &Session,
+    message: &str,
+) -> Result<(), AppError> {
+    match cache_key(config, seed, message) {
+        Some((_, key)) => cache::remove(app, &key),
+        None => Ok(()),
+    }
+}
+
+/// 在对话中发送一条消息，返回模型的回复
+///
+/// 对话不存在时以 `seed` 创建。发送前按上下文长度裁剪较早的消息，
+/// 保存的对话仍保留全部消息。对话指定了工具时，工具调用和结果传给 `on_step`
+/// 并保存到对话中。取消时保存已生成的部分回复，失败时不保存本次消息
+///
+/// 启用缓存时，温度为 0 的提示词创建的对话会缓存第一条回复
+pub async fn send_message(
+    app: &tauri::AppHandle,
+    config: &Config,
+    seed: Session,
+    message: String,
+    execution: &Execution,
+    mut on_token: impl FnMut(&str),
+    mut on_step: impl FnMut(&ChatMessage),
+) -> Result<SessionReply, AppError> {
+    let stored = load_session(app, &seed.id)?;
+    let key = match stored {
+        Some(_) => None,
+        None => cache_key(config, &seed, &message),
+    };
+    let mut session = stored.unwrap_or(seed);
+    session.messages.push(ChatMessage::new("user", message));
+    let request = ChatRequest {
+        provider: session.provider,
+        model: session.model.clone(),
+        messages: context_window(
+            &session.messages,
+            config.llm.context_tokens.unwrap_or(DEFAULT_CONTEXT_TOKENS),
+        ),
+        tools: Vec::new(),
+        temperature: session.temperature,
+    };
+    let toolbox = Toolbox::new(app, config, &session.tools);
+
+    // 当前这一轮已生成的文本，工具调用时计入调用消息
+    let partial = Mutex::new(String::new());
+    let mut steps = Vec::new();
+    let result = chat_with_tools(
+        &request,
+        &config.llm,
+        toolbox.as_ref(),
+        execution,
+        |token| {
+            if let Ok(mut partial) = partial.lock() {
+                partial.push_str(token);
+            }
+            on_token(token);
+        },
+        |step| {
+            if let Ok(mut partial) = partial.lock() {
+                partial.clear();
+            }
+            steps.push(step.clone());
+            on_step(step);
+        },
+    )
+    .await
+    .map(|content| SessionReply {
+        content,
+        cached: false,
+    });
+    let partial = partial.into_inner().unwrap_or_default();
+    match &result {
+        Ok(reply) => {
+            if let Some((action, key)) = &key {
+                cache::store(app, &config.cache, key, action, &reply.content);
+            }
+            session.messages.extend(steps);
+            session
+                .messages
+                .push(ChatMessage::new("assistant", reply.content.clone()));
+        }
+        Err(e) if e.kind() == Some(ErrorKind::Cancelled) => {
+            if steps.is_empty() && partial.is_empty() {
+                return result;
+            }
+            session.messages.extend(steps);
+            if !partial.is_empty() {
+                session
+                    .messages
+                    .push(ChatMessage::new("assistant", partial));
+            }
+        }
+        Err(_) => return result,
+    }
+    session.updated_at = chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true);
+    save_session(app, &session)?;
+    result
+}
+
+/// 计算新对话第一条消息的缓存键
+///
+/// 只缓存温度为 0 且不使用工具的提示词，系统提示词和模型也计入动作版本
+fn cache_key(config: &Config, seed: &Session, message: &str) -> Option<(String, String)> {
+    if !config.cache.enabled || !seed.tools.is_empty() {
+        return None;
+    }
+    let action = format!("{}{}", PROMPT_MARK, seed.action.as_deref()?);
+    let prompt = config.prompt(&action)?;
+    if prompt.temperature != Some(0.0) || seed.temperature != Some(0.0) || !prompt.tools.is_empty()
+    {
+        return None;
+    }
+    let version = (prompt, seed.provider, &seed.model, &seed.messages);
+    let key = cache::cache_key(&action, &version, message);
+    Some((action, key))
+}
+
+/// 选出发送给模型的消息
+///
+/// 系统提示词始终保留，其余消息从最新的开始向前选取，直到超出上下文长度。
+/// 最新的消息即使超出也会发送，第一条选出的消息总是用户消息
+pub fn context_window(messages: &[ChatMessage], max_tokens: usize) -> Vec<ChatMessage> {
+    let (system, rest): (Vec<_>, Vec<_>) = messages.iter().partition(|m| m.role == "system");
+    let mut budget = max_tokens.saturating_sub(system.iter().map(|m| estimate_tokens(m)).sum());
+    let mut start = rest.len();
+    for (index, message) in rest.iter().enumerate().rev() {
+        let tokens = estimate_tokens(message);
+        if tokens > budget && start < rest.len() {
+            break;
+        }
+        budget = budget.saturating_sub(tokens);
+        start = index;
+    }
+    // 不以模型的回复开头
+    while start + 1 < rest.len() && rest[start].role != "user" {
+        start += 1;
+    }
+    system
+        .into_iter()
+        .chain(rest[start..].iter().copied())
+        .cloned()
+        .collect()
+}
+
+/// 粗略估计消息占用的 token 数，按平均每 4 个字节一个 token 计算
+fn estimate_tokens(message: &ChatMessage) -> usize {
+    let arguments: usize = message
+        .tool_calls
+        .iter()
+        .map(|call| call.name.len() + call.arguments.to_string().len())
+        .sum();
+    (message.content.len() + arguments).div_ceil(4) + MESSAGE_OVERHEAD_TOKENS
+}
+
+/// 读取保存的对话
+pub fn load_session(app: &tauri::AppHandle, id: &str) -> Result<Option<Session>, AppError> {
+    let path = session_file(app, id)?;
+    let _guard = CHAT_SESSIONS.lock()?;
+    match std::fs::read(&path) {
+        Ok(bytes) => Ok(Some(serde_json::from_slice(&bytes)?)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// 删除保存的对话，不存在时忽略
+pub fn delete_session(app: &tauri::AppHandle, id: &str) -> Result<(), AppError> {
+    let path = session_file(app, id)?;
+    let _guard = CHAT_SESSIONS.lock()?;
+    match std::fs::remove_file(path) {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+        _ => Ok(()),
+    }
+}
+
+/// 保存对话，并删除超出数量上限的旧对话
+fn save_session(app: &tauri::AppHandle, session: &Session) -> Result<(), AppError> {
+    let path = session_file(app, &session.id)?;
+    let _guard = CHAT_SESSIONS.lock()?;
+    let dir = sessions_dir(app)?;
+    std::fs::create_dir_all(&dir)?;
+    std::fs::write(&path, serde_json::to_vec(session)?)?;
+
+    let mut files: Vec<_> = std::fs::read_dir(&dir)?
+        .filter_map(Result::ok)
+        .filter_map(|entry| {
+            let modified = entry.metadata().and_then(|m| m.modified()).ok()?;
+            Some((modified, entry.path()))
+        })
+        .collect();
+    if files.len() > MAX_SESSIONS {
+        files.sort();
+        for (_, path) in &files[..files.len() - MAX_SESSIONS] {
+            if let Err(e) = std::fs::remove_file(path) {
+                eprintln!("[WARNING] Failed to remove old session: {}", e);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// 保存对话的目录
+fn sessions_dir(app: &tauri::AppHandle) -> Result<PathBuf, AppError> {
+    let dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("App data directory is not available: {}", e))?;
+    Ok(dir.join(SESSIONS_DIR))
+}
+
+/// 对话文件路径，标识只能包含字母、数字和连字符
+fn session_file(app: &tauri::AppHandle, id: &str) -> Result<PathBuf, AppError> {
+    if id.is_empty() || !id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-') {
+        return Err(format!("Invalid session id '{}'", id).into());
+    }
+    Ok(sessions_dir(app)?.join(format!("{}.json", id)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::llm::ToolCall;
+
+    /// 估计为 14 个 token 的消息
+    fn message(role: &str, letter: char) -> ChatMessage {
+        ChatMessage::new(role, letter.to_string().repeat(40))
+    }
+
+    fn contents(messages: &[ChatMessage]) -> Vec<char> {
+        messages
+            .iter()
+            .map(|m| m.content.chars().next().unwrap())
+            .collect()
+    }
+
+    fn conversation() -> Vec<ChatMessage> {
+        vec![
+            message("system", 's'),
+            message("user", 'a'),
+            message("assistant", 'b'),
+            message("user", 'c'),
+            message("assistant", 'd'),
+            message("user", 'e'),
+        ]
+    }
+
+    #[test]
+    fn keeps_everything_within_budget() {
+        let messages = conversation();
+        assert_eq!(
+            contents(&context_window(&messages, DEFAULT_CONTEXT_TOKENS)),
+            ['s', 'a', 'b', 'c', 'd', 'e']
+        );
+    }
+
+    #[test]
+    fn drops_oldest_messages_and_keeps_system_prompt() {
+        let messages = conversation();
+        assert_eq!(
+            contents(&context_window(&messages, 14 * 4)),
+            ['s', 'c', 'd', 'e']
+        );
+    }
+
+    #[test]
+    fn never_starts_with_a_reply() {
+        let messages = conversation();
+        // 预算可以容纳 d、e 两条消息，但不能以模型的回复开头
+        assert_eq!(contents(&context_window(&messages, 14 * 3)), ['s', 'e']);
+    }
+
+    #[test]
+    fn always_sends_the_latest_message() {
+        let mut messages = conversation();
+        messages.push(ChatMessage::new("user", "x".repeat(4000)));
+        assert_eq!(contents(&context_window(&messages, 14 * 3)), ['s', 'x']);
+        assert_eq!(contents(&context_window(&messages, 0)), ['s', 'x']);
+    }
+
+    #[test]
+    fn counts_tool_calls_towards_the_budget() {
+        let call = ToolCall {
+            id: "call_1".to_string(),
+            name: "upper".to_string(),
+            arguments: serde_json::json!({ "text": "y".repeat(400) }),
+        };
+        let with_call = ChatMessage {
+            tool_calls: vec![call],
+            ..ChatMessage::new("assistant", "")
+        };
+        assert!(estimate_tokens(&with_call) > 100);
+    }
+
+    #[test]
+    fn seeds_sessions_from_prompt_entries() {
+        let entry = Entry {
+            id: "entry".to_string(),
+            action_label: Some("translate".to_string()),
+            provider: Some("lmstudio".to_string()),
+            model: Some("qwen".to_string()),
+            system_prompt: Some("  Translate to English.  ".to_string()),
+            temperature: Some(0.0),
+            ..Default::default()
+        };
+        let session = Session::from_entry(&entry).unwrap();
+        assert_eq!(session.provider, Provider::LmStudio);
+        assert_eq!(session.action.as_deref(), Some("translate"));
+        assert_eq!(session.messages.len(), 1);
+        assert_eq!(session.messages[0].content, "Translate to English.");
+
+        let entry = Entry {
+            system_prompt: Some(" ".to_string()),
+            ..entry
+        };
+        assert!(Session::from_entry(&entry).unwrap().messages.is_empty());
+    }
+}
@@ -0,0 +1,195 @@
+use super::client::{chat, complete};
+use super::provider::{ChatMessage, ChatRequest, LlmOptions, ToolCall, ToolDefinition};
+use crate::engine::{Config, SCRIPT_MARK};
+use crate::error::{AppError, ErrorKind};
+use crate::script::{self, Context, Data, Execution, ScriptAction, ScriptOptions};
+use serde_json::{json, Value};
+use tauri_plugin_clipboard_manager::ClipboardExt;
+
+/// 一次对话中调用工具的最多轮数，最后一轮不再提供工具，要求模型直接回答
+const MAX_TOOL_ROUNDS: usize = 5;
+
+/// 返回给模型的工具结果长度上限（字符）
+const MAX_TOOL_OUTPUT: usize = 8000;
+
+/// 作为工具提供给模型的脚本
+struct Tool {
+    /// 工具名称，只包含字母、数字、下划线和连字符
+    name: String,
+    /// 脚本
+    script: ScriptAction,
+}
+
+/// 作为工具提供给模型的一组脚本
+pub struct Toolbox {
+    /// 应用句柄
+    app: tauri::AppHandle,
+    /// 工具列表
+    tools: Vec<Tool>,
+    /// 脚本运行选项
+    options: ScriptOptions,
+}
+
+impl Toolbox {
+    /// 根据脚本标识创建工具集，没有可用的脚本时返回 `None`
+    pub fn new(app: &tauri::AppHandle, config: &Config, ids: &[String]) -> Option<Self> {
+        let mut tools: Vec<Tool> = Vec::new();
+        for (index, id) in ids.iter().enumerate() {
+            let Some(script) = config.scripts.iter().find(|s| &s.id == id) else {
+                eprintln!("[WARNING] Tool script '{}' not found", id);
+                continue;
+            };
+            let mut name = tool_name(id, index);
+            if tools.iter().any(|tool| tool.name == name) {
+                name = format!("{}_{}", name, index + 1);
+            }
+            tools.push(Tool {
+                name,
+                script: script.clone(),
+            });
+        }
+        (!tools.is_empty()).then(|| Self {
+            app: app.clone(),
+            tools,
+            options: config.script_options.clone(),
+        })
+    }
+
+    /// 提供给模型的工具定义
+    pub fn definitions(&self) -> Vec<ToolDefinition> {
+        self.tools
+            .iter()
+            .map(|tool| ToolDefinition {
+                name: tool.name.clone(),
+                description: format!(
+                    "Runs the TextGO script \"{}\" with the given text as input and returns its output",
+                    tool.script.id
+                ),
+                parameters: json!({
+                    "type": "object",
+                    "properties": {
+                        "text": {
+                            "type": "string",
+                            "description": "Input text passed to the script as the selected text",
+                        },
+                    },
+                    "required": ["text"],
+                }),
+            })
+            .collect()
+    }
+
+    /// 执行一次工具调用，返回交给模型的结果
+    ///
+    /// 脚本执行失败时以错误信息作为结果，由模型决定如何处理；对话被取消时返回错误
+    async fn call(&self, call: &ToolCall, parent: &Execution) -> Result<String, AppError> {
+        let Some(tool) = self.tools.iter().find(|tool| tool.name == call.name) else {
+            return Ok(format!("Error: unknown tool '{}'", call.name));
+        };
+        let text = match &call.arguments {
+            Value::String(text) => text.as_str(),
+            arguments => match arguments["text"].as_str() {
+                Some(text) => text,
+                None => return Ok("Error: missing string argument 'text'".to_string()),
+            },
+        };
+        let script = &tool.script;
+        let data = Data {
+            selection: text.to_string(),
+            clipboard: self.app.clipboard().read_text().unwrap_or_default(),
+            datetime: chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true),
+            context: Context {
+                action: format!("{}{}", SCRIPT_MARK, script.id),
+                ..Default::default()
+            },
+        };
+        let execution = Execution::new(script.timeout)?
+            .sandboxed(script.sandbox.unwrap_or_default())
+            .stream_to(&self.app)
+            .cancelled_with(parent);
+        let task = script::run_script(script, &data, &self.options, &execution);
+        match script::audited(&self.app, "script", &script.id, &data, &execution, task).await {
+            Ok(output) => Ok(truncate(output)),
+            Err(e) if e.kind() == Some(ErrorKind::Cancelled) => Err(e),
+            Err(e) => Ok(format!("Error: {}", e)),
+        }
+    }
+}
+
+/// 调用大模型，模型请求调用工具时执行对应的脚本并将结果交回模型，直到得到最终回复
+///
+/// 工具调用消息和结果消息按顺序传给 `on_step`，生成的文本传给 `on_token`，
+/// 返回最终回复。包括工具执行在内的总时间受执行的超时时间限制。
+/// 未提供工具时与 [`chat`] 相同
+pub async fn chat_with_tools(
+    request: &ChatRequest,
+    options: &LlmOptions,
+    toolbox: Option<&Toolbox>,
+    execution: &Execution,
+    mut on_token: impl FnMut(&str),
+    mut on_step: impl FnMut(&ChatMessage),
+) -> Result<String, AppError> {
+    let Some(toolbox) = toolbox else {
+        return chat(request, options, execution, on_token).await;
+    };
+    let endpoint = options.endpoint(request.provider).await?;
+    let mut request = request.clone();
+    let rounds = async {
+        let mut round = 0;
+        loop {
+            request.tools = if round < MAX_TOOL_ROUNDS {
+                toolbox.definitions()
+            } else {
+                Vec::new()
+            };
+            let reply = complete(&endpoint, &request, &mut on_token).await?;
+            if reply.tool_calls.is_empty() || round >= MAX_TOOL_ROUNDS {
+                return Ok(reply.content);
+            }
+
+            let message = ChatMessage {
+                tool_calls: reply.tool_calls,
+                ..ChatMessage::new("assistant", reply.content)
+            };
+            on_step(&message);
+            let mut results = Vec::with_capacity(message.tool_calls.len());
+            for call in &message.tool_calls {
+                let result = ChatMessage::tool_result(call, toolbox.call(call, execution).await?);
+                on_step(&result);
+                results.push(result);
+            }
+            request.messages.push(message);
+            request.messages.extend(results);
+            round += 1;
+        }
+    };
+    execution.supervise(None, rounds).await
+}
+
+/// 根据脚本标识生成工具名称，替换不支持的字符
+fn tool_name(id: &str, index: usize) -> String {
+    let name: String = id
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '_' || c == '-' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .take(64)
+        .collect();
+    if name.chars().all(|c| c == '_') {
+        format!("script_{}", index + 1)
+    } else {
+        name
+    }
+}
+
+/// 截断过长的工具结果
+fn truncate(output: String) -> String {
+    match output.char_indices().nth(MAX_TOOL_OUTPUT) {
+        Some((end, _)) => format!("{}\n[output truncated]", &output[..end]),
+        None => output,
+    }
+}
@@ -97,3 +97,47 @@ pub fn get_selected_text() -> Result<String, AppError> {
         Ok(text_string)
     }
 }
+
+/// 获取前台应用的名称 (macOS 实现)
+///
+/// 通过 Accessibility API 读取焦点应用的标题，没有权限或获取失败时返回 `None`
+pub fn get_focused_app_name() -> Option<String> {
+    unsafe {
+        if !AXIsProcessTrusted() {
+            return None;
+        }
+
+        let sys_el = AXUIElementCreateSystemWide();
+        if sys_el.is_null() {
+            return None;
+        }
+        let _sys_guard = CFGuard(sys_el);
+
+        // 获取焦点应用
+        let mut app_el: *const c_void = std::ptr::null();
+        let result = AXUIElementCopyAttributeValue(
+            sys_el,
+            CFString::new("AXFocusedApplication").as_concrete_TypeRef() as *const c_void,
+            &mut app_el,
+        );
+        if result != 0 || app_el.is_null() {
+            return None;
+        }
+        let _app_guard = CFGuard(app_el);
+
+        // 获取应用标题
+        let mut title: *const c_void = std::ptr::null();
+        let result = AXUIElementCopyAttributeValue(
+            app_el,
+            CFString::new("AXTitle").as_concrete_TypeRef() as *const c_void,
+            &mut title,
+        );
+        if result != 0 || title.is_null() {
+            return None;
+        }
+        let _title_guard = CFGuard(title);
+
+        let title = title as *const core_foundation::string::__CFString;
+        Some(CFString::wrap_under_get_rule(title).to_string())
+    }
+}
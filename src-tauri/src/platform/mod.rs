@@ -7,3 +7,14 @@ pub mod windows;
 pub use macos::get_selected_text;
 #[cfg(target_os = "windows")]
 pub use windows::get_selected_text;
+
+#[cfg(target_os = "macos")]
+pub use macos::get_focused_app_name;
+#[cfg(target_os = "windows")]
+pub use windows::get_focused_app_name;
+
+/// 获取前台应用的名称 (当前平台不支持)
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+pub fn get_focused_app_name() -> Option<String> {
+    None
+}
@@ -0,0 +1,273 @@
+use super::process::Execution;
+use super::runner::Data;
+use crate::engine::STORE_FILE;
+use crate::error::{AppError, ErrorKind};
+use crate::AUDIT_LOG;
+use serde::{Deserialize, Serialize};
+use std::future::Future;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+use tauri::Manager;
+use tauri_plugin_store::StoreExt;
+
+/// 审计日志文件，每行一条 JSON 记录
+const AUDIT_FILE: &str = "audit.jsonl";
+
+/// 审计日志文件的大小上限（字节），超出时只保留较新的一半记录
+const MAX_AUDIT_BYTES: u64 = 4 * 1024 * 1024;
+
+/// 查询时默认返回的记录数量
+const DEFAULT_QUERY_LIMIT: usize = 200;
+
+/// 执行结果的状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditStatus {
+    /// 执行成功
+    Success,
+    /// 执行失败
+    Failure,
+    /// 执行超时
+    Timeout,
+    /// 执行被取消
+    Cancelled,
+}
+
+/// 一次执行的审计记录
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuditRecord {
+    /// 记录标识
+    pub id: String,
+    /// 动作类型：script 或 command
+    pub action_type: String,
+    /// 动作标识
+    pub action: String,
+    /// 开始时间
+    pub started_at: String,
+    /// 执行耗时（毫秒）
+    pub duration_ms: u64,
+    /// 执行结果的状态
+    pub status: AuditStatus,
+    /// 子进程的退出码
+    #[serde(default)]
+    pub exit_code: Option<i32>,
+    /// 运行环境名称
+    #[serde(default)]
+    pub runtime: Option<String>,
+    /// 可执行文件路径
+    #[serde(default)]
+    pub program: Option<String>,
+    /// 运行环境版本
+    #[serde(default)]
+    pub version: Option<String>,
+    /// 标准输出，超出上限的部分被截断
+    #[serde(default)]
+    pub stdout: String,
+    /// 标准错误，超出上限的部分被截断
+    #[serde(default)]
+    pub stderr: String,
+    /// 输出是否被截断
+    #[serde(default)]
+    pub truncated: bool,
+    /// 输入文本的字符数
+    pub input_length: usize,
+    /// 输入文本，仅在用户开启记录输入时保存
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub input: Option<String>,
+    /// 执行失败时的错误信息
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// 审计日志的查询条件
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuditQuery {
+    /// 动作标识，为空时不限
+    #[serde(default)]
+    pub action: Option<String>,
+    /// 只返回未成功的记录
+    #[serde(default)]
+    pub failed_only: bool,
+    /// 起始时间（RFC 3339），为空时不限
+    #[serde(default)]
+    pub since: Option<String>,
+    /// 最多返回的记录数量
+    #[serde(default)]
+    pub limit: Option<usize>,
+}
+
+/// 等待执行结束并写入审计日志
+///
+/// 写入失败只打印错误，不影响执行结果
+pub async fn audited(
+    app: &tauri::AppHandle,
+    action_type: &str,
+    action: &str,
+    data: &Data,
+    execution: &Execution,
+    task: impl Future<Output = Result<String, AppError>>,
+) -> Result<String, AppError> {
+    let started_at = chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true);
+    let start = Instant::now();
+    let result = task.await;
+
+    let trace = execution.trace();
+    let record = AuditRecord {
+        id: uuid::Uuid::new_v4().to_string(),
+        action_type: action_type.to_string(),
+        action: action.to_string(),
+        started_at,
+        duration_ms: start.elapsed().as_millis() as u64,
+        status: match &result {
+            Ok(_) => AuditStatus::Success,
+            Err(e) => match e.kind() {
+                Some(ErrorKind::Timeout) => AuditStatus::Timeout,
+                Some(ErrorKind::Cancelled) => AuditStatus::Cancelled,
+                None => AuditStatus::Failure,
+            },
+        },
+        exit_code: trace.exit_code,
+        runtime: trace.runtime,
+        program: trace.program,
+        version: trace.version,
+        stdout: trace.stdout,
+        stderr: trace.stderr,
+        truncated: trace.truncated,
+        input_length: data.selection.chars().count(),
+        input: records_input(app).then(|| execution.redact(&data.selection)),
+        error: result.as_ref().err().map(|e| e.to_string()),
+    };
+    match audit_file(app) {
+        Ok(file) => {
+            if let Err(e) = append(&file, &record) {
+                eprintln!("[ERROR] Failed to write audit log: {}", e);
+            }
+        }
+        Err(e) => eprintln!("[ERROR] Failed to write audit log: {}", e),
+    }
+    result
+}
+
+/// 查询审计日志，按时间从新到旧返回
+pub fn query_audit(
+    app: &tauri::AppHandle,
+    query: &AuditQuery,
+) -> Result<Vec<AuditRecord>, AppError> {
+    let file = audit_file(app)?;
+    let _guard = AUDIT_LOG.lock()?;
+    let since = query
+        .since
+        .as_deref()
+        .map(chrono::DateTime::parse_from_rfc3339)
+        .transpose()
+        .map_err(|e| format!("Invalid start time: {}", e))?;
+
+    let records = read_records(&file)?
+        .into_iter()
+        .rev()
+        .filter(|record| query.action.as_ref().is_none_or(|a| *a == record.action))
+        .filter(|record| !query.failed_only || record.status != AuditStatus::Success)
+        .filter(|record| {
+            since.is_none_or(|since| {
+                chrono::DateTime::parse_from_rfc3339(&record.started_at)
+                    .is_ok_and(|started| started >= since)
+            })
+        })
+        .take(query.limit.unwrap_or(DEFAULT_QUERY_LIMIT))
+        .collect();
+    Ok(records)
+}
+
+/// 清除审计日志，指定动作时只清除该动作的记录
+///
+/// 返回清除的记录数量
+pub fn clear_audit(app: &tauri::AppHandle, action: Option<&str>) -> Result<usize, AppError> {
+    let file = audit_file(app)?;
+    let _guard = AUDIT_LOG.lock()?;
+    let records = read_records(&file)?;
+    let total = records.len();
+    let kept: Vec<AuditRecord> = match action {
+        Some(action) => records.into_iter().filter(|r| r.action != action).collect(),
+        None => Vec::new(),
+    };
+    let cleared = total - kept.len();
+    if kept.is_empty() {
+        match std::fs::remove_file(&file) {
+            Ok(()) => {}
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => return Err(e.into()),
+        }
+    } else {
+        write_records(&file, &kept)?;
+    }
+    Ok(cleared)
+}
+
+/// 审计日志文件路径
+fn audit_file(app: &tauri::AppHandle) -> Result<PathBuf, AppError> {
+    let dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("App data directory is not available: {}", e))?;
+    Ok(dir.join(AUDIT_FILE))
+}
+
+/// 用户是否开启了记录输入文本
+fn records_input(app: &tauri::AppHandle) -> bool {
+    app.store(STORE_FILE)
+        .ok()
+        .and_then(|store| store.get("auditInput"))
+        .and_then(|value| value.as_bool())
+        .unwrap_or(false)
+}
+
+/// 追加一条记录，文件超出大小上限时只保留较新的一半
+fn append(file: &Path, record: &AuditRecord) -> std::io::Result<()> {
+    let _guard = AUDIT_LOG
+        .lock()
+        .map_err(|e| std::io::Error::other(e.to_string()))?;
+    if let Some(dir) = file.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    let mut line = serde_json::to_vec(record)?;
+    line.push(b'\n');
+    std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(file)?
+        .write_all(&line)?;
+
+    if std::fs::metadata(file)?.len() > MAX_AUDIT_BYTES {
+        let records = read_records(file)?;
+        write_records(file, &records[records.len() / 2..])?;
+    }
+    Ok(())
+}
+
+/// 读取全部记录，跳过无法解析的行
+fn read_records(file: &Path) -> std::io::Result<Vec<AuditRecord>> {
+    let content = match std::fs::read_to_string(file) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e),
+    };
+    Ok(content
+        .lines()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect())
+}
+
+/// 写入临时文件后替换，避免中途失败丢失全部记录
+fn write_records(file: &Path, records: &[AuditRecord]) -> std::io::Result<()> {
+    let mut content = Vec::new();
+    for record in records {
+        serde_json::to_writer(&mut content, record)?;
+        content.push(b'\n');
+    }
+    let temp = file.with_extension("jsonl.tmp");
+    std::fs::write(&temp, content)?;
+    std::fs::rename(temp, file)
+}
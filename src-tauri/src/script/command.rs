@@ -0,0 +1,205 @@
+use super::process::Execution;
+use super::runner::Data;
+use super::runtime::common_search_path;
+use super::sandbox::Sandbox;
+use super::secrets::action_env;
+use crate::engine::Concurrency;
+use crate::error::AppError;
+use crate::template;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// 选中文本传递给程序的方式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum InputMode {
+    /// 写入标准输入
+    #[default]
+    Stdin,
+    /// 替换参数中的 `{{selection}}`，未使用时追加为最后一个参数，以 `-` 开头的文本会被拒绝
+    Argument,
+    /// 写入临时文件，替换参数中的 `{{file}}`，未使用时追加为最后一个参数
+    File,
+}
+
+/// 标准错误输出的处理方式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StderrMode {
+    /// 忽略
+    #[default]
+    Ignore,
+    /// 追加到结果末尾
+    Append,
+    /// 有输出即视为执行失败
+    Fail,
+}
+
+/// 命令行动作
+///
+/// 直接启动程序并逐个传递参数，不经过 shell 解析
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CommandAction {
+    /// 动作标识
+    pub id: String,
+    /// 程序名称或路径
+    pub program: String,
+    /// 参数模板
+    #[serde(default)]
+    pub args: Vec<String>,
+    /// 输入方式
+    #[serde(default)]
+    pub input: InputMode,
+    /// 视为成功的退出码，为空时只有 0 视为成功
+    #[serde(default)]
+    pub success_codes: Vec<i32>,
+    /// 标准错误输出的处理方式
+    #[serde(default)]
+    pub stderr: StderrMode,
+    /// 静默执行
+    #[serde(default)]
+    pub quiet_mode: Option<bool>,
+    /// 超时时间（秒）
+    #[serde(default)]
+    pub timeout: Option<u64>,
+    /// 沙箱级别
+    #[serde(default)]
+    pub sandbox: Option<Sandbox>,
+    /// 正在运行时再次触发的处理方式
+    #[serde(default)]
+    pub concurrency: Option<Concurrency>,
+    /// 额外设置的环境变量
+    #[serde(default)]
+    pub env: BTreeMap<String, String>,
+    /// 使用的密钥名称，运行时从系统钥匙串读取并以同名环境变量传递
+    #[serde(default)]
+    pub secrets: Vec<String>,
+}
+
+/// 在指定的执行中运行命令行动作
+pub async fn run_command(
+    action: &CommandAction,
+    data: &Data,
+    execution: &Execution,
+) -> Result<String, AppError> {
+    let program = action.program.trim();
+    if program.is_empty() {
+        return Err("Command program is empty".into());
+    }
+
+    let dir = tempfile::Builder::new().prefix("textgo-").tempdir()?;
+    let file = dir.path().join("input.txt");
+    let file_path = file.to_string_lossy();
+    let vars = [("file", file_path.as_ref())];
+    let mut args = action
+        .args
+        .iter()
+        .map(|arg| template::render(arg, data, &vars))
+        .collect::<Result<Vec<_>, _>>()?;
+    check_options(&action.args, &args)?;
+
+    // 根据输入方式传递选中的文本
+    let mut input = None;
+    match action.input {
+        InputMode::Stdin => input = Some(data.selection.as_bytes()),
+        InputMode::Argument => {
+            if !action
+                .args
+                .iter()
+                .any(|arg| template::uses(arg, "selection"))
+            {
+                if data.selection.starts_with('-') {
+                    return Err(
+                        "Selected text starting with '-' cannot be passed as an argument".into(),
+                    );
+                }
+                args.push(data.selection.clone());
+            }
+        }
+        InputMode::File => {
+            std::fs::write(&file, &data.selection)?;
+            if !action.args.iter().any(|arg| template::uses(arg, "file")) {
+                args.push(file_path.to_string());
+            }
+        }
+    }
+
+    let vars = action_env(&action.env, &action.secrets, execution).await?;
+    execution.set_runtime(None, program, None);
+    let mut command = execution.command(program, dir.path())?;
+    command
+        .args(&args)
+        .env("PATH", common_search_path())
+        .envs(vars.iter().map(|(key, value)| (key, value)));
+    let child = execution
+        .spawn(&mut command)
+        .map_err(|e| format!("Failed to execute '{}': {}", program, e))?;
+    let output = execution.wait(child, input).await?;
+
+    // 输出会出现在结果、错误信息和记录中，先隐藏其中的密钥值
+    let stdout = execution.redact(&String::from_utf8_lossy(&output.stdout));
+    let stderr = execution.redact(&String::from_utf8_lossy(&output.stderr));
+    let stderr = stderr.trim_end();
+    let success = match output.status.code() {
+        Some(code) if action.success_codes.is_empty() => code == 0,
+        Some(code) => action.success_codes.contains(&code),
+        // 被信号终止
+        None => false,
+    };
+    if !success {
+        let status = output
+            .status
+            .code()
+            .map_or_else(|| "signal".to_string(), |code| code.to_string());
+        return Err(format!("Command exited with {}: {}", status, stderr).into());
+    }
+
+    let result = stdout.trim_end_matches(['\r', '\n']);
+    match action.stderr {
+        StderrMode::Fail if !stderr.is_empty() => {
+            Err(format!("Command wrote to stderr: {}", stderr).into())
+        }
+        StderrMode::Append if !stderr.is_empty() => Ok(format!("{}\n{}", result, stderr)),
+        _ => Ok(result.to_string()),
+    }
+}
+
+/// 检查替换后的参数，只有模板本身以 `-` 开头的参数才能作为选项
+///
+/// 选中的文本、剪贴板等内容以 `-` 开头时会被程序解析为选项，拒绝执行
+fn check_options(templates: &[String], args: &[String]) -> Result<(), AppError> {
+    for (index, (template, arg)) in templates.iter().zip(args).enumerate() {
+        if arg.starts_with('-') && !template.starts_with('-') {
+            return Err(format!(
+                "Argument {} starts with '-' after substitution and would be parsed as an option",
+                index + 1
+            )
+            .into());
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn strings(values: &[&str]) -> Vec<String> {
+        values.iter().map(|value| value.to_string()).collect()
+    }
+
+    #[test]
+    fn allows_options_from_templates() {
+        let templates = strings(&["-n", "--query={{selection}}", "{{selection}}"]);
+        let args = strings(&["-n", "--query=-rf", "text"]);
+        assert!(check_options(&templates, &args).is_ok());
+    }
+
+    #[test]
+    fn rejects_options_from_input() {
+        let templates = strings(&["-n", "{{selection}}"]);
+        let args = strings(&["-n", "--output=/tmp/x"]);
+        assert!(check_options(&templates, &args).is_err());
+    }
+}
@@ -0,0 +1,319 @@
+use super::process::Execution;
+use super::runner::{is_deno, ScriptAction, ScriptOptions};
+use super::runtime::Runtime;
+use crate::error::AppError;
+use crate::INSTALLING_DEPENDENCIES;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::ffi::{OsStr, OsString};
+use std::path::{Path, PathBuf};
+use std::process::Output;
+use std::time::Duration;
+
+/// 依赖环境所在的目录
+const ENVS_DIR: &str = "envs";
+
+/// Python 包缓存目录，离线时从中安装
+const WHEELS_DIR: &str = "wheels";
+
+/// npm 缓存目录
+const NPM_CACHE_DIR: &str = "npm-cache";
+
+/// 记录环境中已安装依赖的文件
+const STAMP_FILE: &str = ".textgo-deps.json";
+
+/// 安装依赖的超时时间
+pub const INSTALL_TIMEOUT: Duration = Duration::from_secs(600);
+
+/// 依赖环境的类型
+#[derive(Debug, Clone, Copy)]
+enum Kind {
+    /// Python 虚拟环境
+    Venv,
+    /// Node.js 的 node_modules 目录
+    NodeModules,
+}
+
+impl Kind {
+    /// 根据运行环境确定依赖环境的类型，不支持时返回 `None`
+    fn of(runtime: &Runtime, program: &str) -> Option<Self> {
+        match runtime.lang.as_str() {
+            "python" => Some(Self::Venv),
+            "javascript" if !matches!(runtime.id.as_str(), "deno" | "bun") && !is_deno(program) => {
+                Some(Self::NodeModules)
+            }
+            _ => None,
+        }
+    }
+}
+
+/// 环境中已安装的依赖
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct Stamp {
+    /// 创建环境时使用的解释器
+    program: String,
+    /// 依赖列表
+    dependencies: Vec<String>,
+}
+
+/// 已安装依赖的隔离环境
+#[derive(Debug)]
+pub struct DepsEnv {
+    /// 运行脚本使用的可执行文件
+    pub program: String,
+    /// 运行脚本时额外设置的环境变量
+    pub env: Vec<(&'static str, OsString)>,
+}
+
+/// 判断运行环境是否支持安装依赖
+pub fn supports_dependencies(runtime: &Runtime, program: &str) -> bool {
+    Kind::of(runtime, program).is_some()
+}
+
+/// 准备脚本的依赖环境，依赖或解释器变化时重新创建
+///
+/// 安装过程的输出通过执行的实时输出发送
+pub async fn prepare_env(
+    action: &ScriptAction,
+    runtime: &Runtime,
+    program: &str,
+    options: &ScriptOptions,
+    execution: &Execution,
+) -> Result<DepsEnv, AppError> {
+    let kind = Kind::of(runtime, program)
+        .ok_or_else(|| format!("{} scripts cannot declare dependencies", runtime.name))?;
+    let data_dir = options
+        .data_dir
+        .as_deref()
+        .ok_or("App data directory is not available")?;
+    let dir = env_dir(data_dir, action);
+    let mut dependencies: Vec<String> = action
+        .dependencies
+        .iter()
+        .map(|dep| dep.trim().to_string())
+        .filter(|dep| !dep.is_empty())
+        .collect();
+    dependencies.sort();
+    dependencies.dedup();
+    let stamp = Stamp {
+        program: program.to_string(),
+        dependencies,
+    };
+
+    // 同一时间只安装一个环境，避免同时执行同一脚本时重复安装
+    let _guard = INSTALLING_DEPENDENCIES.lock().await;
+    if read_stamp(&dir).as_ref() != Some(&stamp) {
+        let stage = execution.stage(INSTALL_TIMEOUT);
+        stage.emit(
+            "stdout",
+            &format!("Installing dependencies: {}", stamp.dependencies.join(", ")),
+        );
+        if dir.exists() {
+            std::fs::remove_dir_all(&dir)?;
+        }
+        std::fs::create_dir_all(&dir)?;
+
+        let search_path = runtime.search_path();
+        let installed = match kind {
+            Kind::Venv => {
+                install_python(
+                    &stage,
+                    program,
+                    &dir,
+                    &stamp.dependencies,
+                    data_dir,
+                    options,
+                    &search_path,
+                )
+                .await
+            }
+            Kind::NodeModules => {
+                install_node(
+                    &stage,
+                    program,
+                    &dir,
+                    &stamp.dependencies,
+                    data_dir,
+                    options,
+                    &search_path,
+                )
+                .await
+            }
+        };
+        if let Err(e) = installed {
+            // 清理安装失败的环境，下次执行时重新安装
+            let _ = std::fs::remove_dir_all(&dir);
+            return Err(e);
+        }
+        std::fs::write(dir.join(STAMP_FILE), serde_json::to_vec(&stamp)?)?;
+        stage.emit("stdout", "Dependencies installed");
+    }
+
+    Ok(match kind {
+        Kind::Venv => DepsEnv {
+            program: venv_python(&dir).display().to_string(),
+            env: vec![("VIRTUAL_ENV", dir.into_os_string())],
+        },
+        Kind::NodeModules => DepsEnv {
+            program: program.to_string(),
+            env: vec![("NODE_PATH", dir.join("node_modules").into_os_string())],
+        },
+    })
+}
+
+/// 创建虚拟环境并安装 Python 包
+///
+/// 优先从本地缓存离线安装，缺少的包下载到缓存后再安装
+async fn install_python(
+    stage: &Execution,
+    program: &str,
+    dir: &Path,
+    dependencies: &[String],
+    data_dir: &Path,
+    options: &ScriptOptions,
+    search_path: &OsStr,
+) -> Result<(), AppError> {
+    let venv = [OsString::from("-m"), "venv".into(), dir.into()];
+    let output = run(stage, program.as_ref(), &venv, search_path).await?;
+    check(&output, "Failed to create virtual environment")?;
+
+    let python = venv_python(dir);
+    let wheels = data_dir.join(WHEELS_DIR);
+    std::fs::create_dir_all(&wheels)?;
+    let pip = |command: &str| -> Vec<OsString> {
+        [
+            "-m",
+            "pip",
+            command,
+            "--disable-pip-version-check",
+            "--no-input",
+        ]
+        .into_iter()
+        .map(OsString::from)
+        .collect()
+    };
+    let mut install = pip("install");
+    install.extend([
+        "--no-index".into(),
+        "--find-links".into(),
+        wheels.clone().into(),
+    ]);
+    install.extend(dependencies.iter().map(OsString::from));
+
+    let output = run(stage, python.as_os_str(), &install, search_path).await?;
+    if output.status.success() {
+        return Ok(());
+    }
+
+    stage.emit("stdout", "Downloading missing packages to the local cache");
+    let mut download = pip("download");
+    download.extend(["--dest".into(), wheels.into()]);
+    if let Some(index) = options
+        .pip_index_url
+        .as_deref()
+        .filter(|url| !url.is_empty())
+    {
+        download.extend(["--index-url".into(), index.into()]);
+    }
+    download.extend(dependencies.iter().map(OsString::from));
+    let output = run(stage, python.as_os_str(), &download, search_path).await?;
+    check(&output, "Failed to download packages")?;
+
+    let output = run(stage, python.as_os_str(), &install, search_path).await?;
+    check(&output, "Failed to install packages")
+}
+
+/// 使用 npm 安装 Node.js 包，缓存中已有的包不再下载
+async fn install_node(
+    stage: &Execution,
+    program: &str,
+    dir: &Path,
+    dependencies: &[String],
+    data_dir: &Path,
+    options: &ScriptOptions,
+    search_path: &OsStr,
+) -> Result<(), AppError> {
+    let mut args: Vec<OsString> = ["install", "--no-audit", "--no-fund", "--prefer-offline"]
+        .into_iter()
+        .map(OsString::from)
+        .collect();
+    args.extend([
+        "--prefix".into(),
+        dir.into(),
+        "--cache".into(),
+        data_dir.join(NPM_CACHE_DIR).into(),
+    ]);
+    if let Some(registry) = options
+        .npm_registry
+        .as_deref()
+        .filter(|url| !url.is_empty())
+    {
+        args.extend(["--registry".into(), registry.into()]);
+    }
+    args.extend(dependencies.iter().map(OsString::from));
+
+    let output = run(stage, &npm(program), &args, search_path).await?;
+    check(&output, "Failed to install packages")
+}
+
+/// 运行安装命令，输出实时发送到前端
+async fn run(
+    stage: &Execution,
+    program: &OsStr,
+    args: &[OsString],
+    search_path: &OsStr,
+) -> Result<Output, AppError> {
+    let mut command = tokio::process::Command::new(program);
+    command.args(args).env("PATH", search_path);
+    let child = stage
+        .spawn(&mut command)
+        .map_err(|e| format!("Failed to start {}: {}", program.to_string_lossy(), e))?;
+    stage.wait(child, None).await
+}
+
+/// 检查命令是否成功，失败时附带标准错误的最后一行
+fn check(output: &Output, message: &str) -> Result<(), AppError> {
+    if output.status.success() {
+        return Ok(());
+    }
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    match stderr.lines().map(str::trim).rfind(|line| !line.is_empty()) {
+        Some(line) => Err(format!("{}: {}", message, line).into()),
+        None => Err(format!("{}: exited with {}", message, output.status).into()),
+    }
+}
+
+/// 脚本的依赖环境目录，按脚本语言和标识区分
+fn env_dir(data_dir: &Path, action: &ScriptAction) -> PathBuf {
+    let digest = Sha256::digest(format!("{}:{}", action.lang, action.id));
+    let name: String = digest[..8].iter().map(|b| format!("{:02x}", b)).collect();
+    data_dir.join(ENVS_DIR).join(name)
+}
+
+/// 读取环境中已安装的依赖
+fn read_stamp(dir: &Path) -> Option<Stamp> {
+    let bytes = std::fs::read(dir.join(STAMP_FILE)).ok()?;
+    serde_json::from_slice(&bytes).ok()
+}
+
+/// 虚拟环境中的 Python 解释器
+fn venv_python(dir: &Path) -> PathBuf {
+    #[cfg(target_os = "windows")]
+    return dir.join("Scripts").join("python.exe");
+    #[cfg(not(target_os = "windows"))]
+    return dir.join("bin").join("python");
+}
+
+/// 与 Node.js 位于同一目录的 npm，找不到时从 PATH 中查找
+fn npm(program: &str) -> OsString {
+    #[cfg(target_os = "windows")]
+    let name = "npm.cmd";
+    #[cfg(not(target_os = "windows"))]
+    let name = "npm";
+    Path::new(program)
+        .parent()
+        .map(|dir| dir.join(name))
+        .filter(|npm| npm.is_file())
+        .map(PathBuf::into_os_string)
+        .unwrap_or_else(|| name.into())
+}
@@ -0,0 +1,319 @@
+use super::runner::ScriptOptions;
+use super::runtime::{Registry, Runtime};
+use crate::DETECTED_RUNTIMES;
+use serde::Serialize;
+use std::ffi::OsStr;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::time::Duration;
+use tokio::process::Command;
+
+/// 查询版本的超时时间
+const VERSION_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// 缓存的检测结果，以及检测时使用的运行选项
+pub type DetectionCache = Option<(String, Vec<RuntimeStatus>)>;
+
+/// 运行环境的检测结果
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RuntimeStatus {
+    /// 运行环境标识
+    pub id: String,
+    /// 显示名称
+    pub name: String,
+    /// 可执行的脚本语言
+    pub lang: String,
+    /// 可执行文件路径，未找到时为空
+    pub path: Option<String>,
+    /// 版本信息
+    pub version: Option<String>,
+    /// 可执行文件的 CPU 架构
+    pub arch: Option<String>,
+    /// 是否使用自定义路径
+    pub custom: bool,
+    /// 是否可用于执行脚本
+    pub available: bool,
+    /// 发现的问题
+    pub problems: Vec<String>,
+}
+
+impl RuntimeStatus {
+    /// 创建尚未检测的结果
+    fn new(runtime: &Runtime, custom: bool) -> Self {
+        Self {
+            id: runtime.id.clone(),
+            name: runtime.name.clone(),
+            lang: runtime.lang.clone(),
+            path: None,
+            version: None,
+            arch: None,
+            custom,
+            available: false,
+            problems: Vec::new(),
+        }
+    }
+}
+
+/// 检测所有已注册的运行环境
+///
+/// 结果按运行选项缓存，`refresh` 为 true 时忽略缓存重新检测
+pub async fn scan_runtimes(options: &ScriptOptions, refresh: bool) -> Vec<RuntimeStatus> {
+    let key = cache_key(options);
+    if !refresh {
+        if let Some(statuses) = cached(&key) {
+            return statuses;
+        }
+    }
+
+    // 并行检测，每个运行环境最多等待一次版本查询
+    let registry = Registry::new(&options.runtimes);
+    let mut tasks = tokio::task::JoinSet::new();
+    for (index, runtime) in registry.runtimes().iter().enumerate() {
+        let custom = options
+            .custom_path(&runtime.lang)
+            .filter(|program| {
+                registry
+                    .resolve_custom(&runtime.lang, program)
+                    .is_some_and(|r| r.id == runtime.id)
+            })
+            .map(str::to_string);
+        let runtime = runtime.clone();
+        tasks.spawn(async move { (index, detect(&runtime, custom).await) });
+    }
+    let mut results = tasks.join_all().await;
+    results.sort_by_key(|(index, _)| *index);
+
+    let mut statuses = vec![embedded_status()];
+    statuses.extend(results.into_iter().map(|(_, status)| status));
+    if let Ok(mut detected) = DETECTED_RUNTIMES.lock() {
+        *detected = Some((key, statuses.clone()));
+    }
+    statuses
+}
+
+/// 获取与运行选项对应的缓存检测结果
+pub fn cached_runtimes(options: &ScriptOptions) -> Option<Vec<RuntimeStatus>> {
+    cached(&cache_key(options))
+}
+
+/// 读取缓存，运行选项变化后缓存失效
+fn cached(key: &str) -> Option<Vec<RuntimeStatus>> {
+    let detected = DETECTED_RUNTIMES.lock().ok()?;
+    match detected.as_ref() {
+        Some((cached_key, statuses)) if cached_key == key => Some(statuses.clone()),
+        _ => None,
+    }
+}
+
+/// 影响检测结果的运行选项
+fn cache_key(options: &ScriptOptions) -> String {
+    serde_json::to_string(&(&options.node_path, &options.python_path, &options.runtimes))
+        .unwrap_or_default()
+}
+
+/// 内置 JavaScript 引擎的状态，始终可用
+pub(super) fn embedded_status() -> RuntimeStatus {
+    RuntimeStatus {
+        id: "embedded".to_string(),
+        name: "Embedded JavaScript".to_string(),
+        lang: "javascript".to_string(),
+        path: std::env::current_exe()
+            .ok()
+            .map(|path| path.display().to_string()),
+        version: Some("Boa 0.18".to_string()),
+        arch: Some(std::env::consts::ARCH.to_string()),
+        custom: false,
+        available: true,
+        problems: Vec::new(),
+    }
+}
+
+/// 检测单个运行环境
+async fn detect(runtime: &Runtime, custom: Option<String>) -> RuntimeStatus {
+    let mut status = RuntimeStatus::new(runtime, custom.is_some());
+    let search_path = runtime.search_path();
+    let path = match &custom {
+        Some(program) => which(program, &search_path),
+        None => runtime
+            .executables
+            .iter()
+            .find_map(|name| which(name, &search_path)),
+    };
+    let Some(path) = path else {
+        status.problems.push(match &custom {
+            Some(program) => format!("Custom path '{}' does not exist", program),
+            None => format!("{} not found in PATH", runtime.executables.join(", ")),
+        });
+        return status;
+    };
+    status.path = Some(path.display().to_string());
+
+    // 在其他架构上通过转译运行时启动较慢，且可能无法加载本机扩展
+    status.arch = arch(&path);
+    if let Some(arch) = &status.arch {
+        let host = std::env::consts::ARCH;
+        if arch != "universal" && arch != host {
+            status.problems.push(format!(
+                "Built for {}, runs under emulation on {}",
+                arch, host
+            ));
+        }
+    }
+
+    let store_alias = is_store_alias(&path);
+    match version(runtime, &path, &search_path).await {
+        Ok(version) if store_alias && version.contains("Microsoft Store") => {
+            status.problems.push(store_alias_problem(runtime));
+        }
+        Ok(version) => {
+            status.version = Some(version);
+            status.available = true;
+        }
+        Err(_) if store_alias => status.problems.push(store_alias_problem(runtime)),
+        Err(e) => status
+            .problems
+            .push(format!("Failed to query version: {}", e)),
+    }
+    status
+}
+
+/// 查询运行环境的版本，返回输出的第一个非空行
+async fn version(runtime: &Runtime, path: &Path, search_path: &OsStr) -> Result<String, String> {
+    let mut command = Command::new(path);
+    command
+        .args(&runtime.version_args)
+        .env("PATH", search_path)
+        .envs(&runtime.env)
+        .stdin(Stdio::null())
+        .kill_on_drop(true);
+    #[cfg(target_os = "windows")]
+    command.creation_flags(0x08000000); // CREATE_NO_WINDOW
+
+    let output = tokio::time::timeout(VERSION_TIMEOUT, command.output())
+        .await
+        .map_err(|_| "timed out".to_string())?
+        .map_err(|e| e.to_string())?;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let first_line = |text: &str| {
+        text.lines()
+            .map(str::trim)
+            .find(|line| !line.is_empty())
+            .map(str::to_string)
+    };
+    // 部分解释器（如 Python 2）将版本写入标准错误
+    let line = first_line(&stdout).or_else(|| first_line(&stderr));
+    match line {
+        Some(line) if output.status.success() => Ok(line),
+        Some(line) => Err(line),
+        None if output.status.success() => Err("no version output".to_string()),
+        None => Err(format!("exited with {}", output.status)),
+    }
+}
+
+/// 在 PATH 中查找可执行文件，包含路径分隔符时直接检查该路径
+fn which(program: &str, search_path: &OsStr) -> Option<PathBuf> {
+    let program = Path::new(program);
+    if program.components().count() > 1 {
+        return is_executable(program).then(|| program.to_path_buf());
+    }
+    for dir in std::env::split_paths(search_path) {
+        let candidate = dir.join(program);
+        #[cfg(target_os = "windows")]
+        {
+            if candidate.extension().is_none() {
+                for ext in ["exe", "cmd", "bat"] {
+                    let candidate = candidate.with_extension(ext);
+                    if is_executable(&candidate) {
+                        return Some(candidate);
+                    }
+                }
+                continue;
+            }
+        }
+        if is_executable(&candidate) {
+            return Some(candidate);
+        }
+    }
+    None
+}
+
+/// 判断路径是否为可执行文件
+fn is_executable(path: &Path) -> bool {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        path.metadata()
+            .is_ok_and(|meta| meta.is_file() && meta.permissions().mode() & 0o111 != 0)
+    }
+    #[cfg(not(unix))]
+    {
+        // 应用执行别名是无法直接读取元数据的重解析点
+        path.is_file() || std::fs::symlink_metadata(path).is_ok_and(|meta| !meta.is_dir())
+    }
+}
+
+/// 从可执行文件头部读取 CPU 架构，脚本或无法识别的格式返回 `None`
+fn arch(path: &Path) -> Option<String> {
+    let mut header = [0u8; 1024];
+    let len = std::fs::File::open(path).ok()?.read(&mut header).ok()?;
+    let header = &header[..len];
+    let u16_at = |offset: usize| {
+        let bytes = header.get(offset..offset + 2)?;
+        Some(u16::from_le_bytes([bytes[0], bytes[1]]))
+    };
+    let u32_at = |offset: usize| {
+        let bytes = header.get(offset..offset + 4)?;
+        Some(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    };
+
+    let arch = match header.get(..4)? {
+        // ELF
+        [0x7f, b'E', b'L', b'F'] => match u16_at(18)? {
+            0x03 => "x86",
+            0x28 => "arm",
+            0x3e => "x86_64",
+            0xb7 => "aarch64",
+            0xf3 => "riscv64",
+            _ => return None,
+        },
+        // Mach-O
+        [0xcf, 0xfa, 0xed, 0xfe] => match u32_at(4)? {
+            0x0100_0007 => "x86_64",
+            0x0100_000c => "aarch64",
+            _ => return None,
+        },
+        [0xca, 0xfe, 0xba, 0xbe] => "universal",
+        // PE
+        [b'M', b'Z', _, _] => {
+            let offset = u32_at(0x3c)? as usize;
+            if header.get(offset..offset + 4)? != b"PE\0\0" {
+                return None;
+            }
+            match u16_at(offset + 4)? {
+                0x014c => "x86",
+                0x8664 => "x86_64",
+                0xaa64 => "aarch64",
+                _ => return None,
+            }
+        }
+        _ => return None,
+    };
+    Some(arch.to_string())
+}
+
+/// 判断是否为 Windows 应用商店的应用执行别名
+fn is_store_alias(path: &Path) -> bool {
+    path.components()
+        .any(|component| component.as_os_str().eq_ignore_ascii_case("WindowsApps"))
+}
+
+/// 应用商店占位程序的问题描述
+fn store_alias_problem(runtime: &Runtime) -> String {
+    format!(
+        "Windows Store placeholder for {}, install it from the official website or disable the app execution alias",
+        runtime.name
+    )
+}
@@ -0,0 +1,448 @@
+use super::detect::embedded_status;
+use super::process::Execution;
+use super::runner::Data;
+use crate::error::AppError;
+use boa_engine::builtins::promise::PromiseState;
+use boa_engine::object::builtins::JsPromise;
+use boa_engine::object::ObjectInitializer;
+use boa_engine::property::Attribute;
+use boa_engine::{js_string, Context, JsResult, JsValue, NativeFunction, Source};
+use std::io::{ErrorKind, Read};
+use std::path::Path;
+
+/// 以内置 JavaScript 引擎启动应用程序时使用的参数
+const EMBEDDED_FLAG: &str = "--textgo-embedded-js";
+
+/// 内置引擎进程的内存上限（字节）
+const MEMORY_LIMIT: u64 = 256 * 1024 * 1024;
+
+/// 内置引擎进程的 CPU 时间上限（秒），作为脚本整体的指令预算
+///
+/// 与执行的超时时间不同，等待输入输出的时间不计入该预算
+const CPU_TIME_LIMIT_SECS: u64 = 30;
+
+/// 单个循环的迭代次数上限，用于尽早结束死循环，总体开销由 CPU 时间上限约束
+const LOOP_ITERATION_LIMIT: u64 = 10_000_000;
+
+/// 函数调用的递归深度上限
+const RECURSION_LIMIT: usize = 256;
+
+/// 只有外部运行环境才提供的全局对象
+const HOST_GLOBALS: &[&str] = &[
+    "require", "Deno", "Bun", "Buffer", "fetch", "module", "exports",
+];
+
+/// 只有 Node 才提供的 `process` 成员，脚本自身定义的 `process` 函数不受影响
+const HOST_PROCESS_MEMBERS: &[&str] = &[
+    "env", "argv", "stdin", "stdout", "stderr", "exit", "cwd", "platform", "versions", "nextTick",
+];
+
+/// 脚本源码中的词法单元
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Token<'a> {
+    /// 标识符或关键字
+    Ident(&'a str),
+    /// 字符串、模板字符串或数字
+    Literal,
+    /// 单个标点符号
+    Punct(char),
+}
+
+/// 判断脚本是否使用了内置引擎不提供的 API
+///
+/// 按词法单元匹配，忽略注释和字符串中的内容
+pub fn needs_host(code: &str) -> bool {
+    let tokens = tokenize(code);
+    tokens.iter().enumerate().any(|(i, token)| {
+        let Token::Ident(name) = *token else {
+            return false;
+        };
+        // 属性访问（如 `obj.require`）不是全局对象
+        if i > 0 && tokens[i - 1] == Token::Punct('.') {
+            return false;
+        }
+        match name {
+            // 模块语法只能在外部运行环境中使用
+            "import" | "export" => true,
+            "process" => {
+                tokens.get(i + 1) == Some(&Token::Punct('.'))
+                    && matches!(
+                        tokens.get(i + 2),
+                        Some(Token::Ident(member)) if HOST_PROCESS_MEMBERS.contains(member)
+                    )
+            }
+            _ => HOST_GLOBALS.contains(&name),
+        }
+    })
+}
+
+/// 判断内置引擎的执行错误是否由未定义的全局对象引起
+///
+/// 静态检测只覆盖常见的宿主 API，脚本使用的其他全局对象（如 `setTimeout`）需要改用外部运行环境执行
+pub fn missing_global(message: &str) -> bool {
+    message
+        .split("ReferenceError: ")
+        .skip(1)
+        .filter_map(|rest| rest.split_once(" is not defined"))
+        .any(|(name, _)| {
+            !name.is_empty()
+                && name
+                    .chars()
+                    .all(|c| c.is_alphanumeric() || c == '_' || c == '$')
+        })
+}
+
+/// 将脚本源码拆分为词法单元
+///
+/// 不区分正则表达式字面量，只用于检测脚本使用的 API
+fn tokenize(code: &str) -> Vec<Token<'_>> {
+    let bytes = code.as_bytes();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < bytes.len() {
+        let byte = bytes[i];
+        match byte {
+            b'/' if bytes.get(i + 1) == Some(&b'/') => {
+                while i < bytes.len() && bytes[i] != b'\n' {
+                    i += 1;
+                }
+            }
+            b'/' if bytes.get(i + 1) == Some(&b'*') => {
+                i = code[i + 2..]
+                    .find("*/")
+                    .map_or(bytes.len(), |end| i + end + 4);
+            }
+            b'"' | b'\'' | b'`' => {
+                i += 1;
+                while i < bytes.len() && bytes[i] != byte {
+                    i += if bytes[i] == b'\\' { 2 } else { 1 };
+                }
+                i += 1;
+                tokens.push(Token::Literal);
+            }
+            b'0'..=b'9' => {
+                while i < bytes.len() && (bytes[i].is_ascii_alphanumeric() || bytes[i] == b'.') {
+                    i += 1;
+                }
+                tokens.push(Token::Literal);
+            }
+            _ if byte.is_ascii_alphabetic() || byte == b'_' || byte == b'$' || byte >= 0x80 => {
+                let start = i;
+                while i < bytes.len()
+                    && (bytes[i].is_ascii_alphanumeric()
+                        || bytes[i] == b'_'
+                        || bytes[i] == b'$'
+                        || bytes[i] >= 0x80)
+                {
+                    i += 1;
+                }
+                tokens.push(Token::Ident(&code[start..i]));
+            }
+            _ if byte.is_ascii_whitespace() => i += 1,
+            _ => {
+                tokens.push(Token::Punct(byte as char));
+                i += 1;
+            }
+        }
+    }
+    tokens
+}
+
+/// 使用内置引擎运行 JavaScript 脚本
+///
+/// 引擎在重新启动的应用程序进程中运行，以便限制内存并在超时或取消时终止
+pub async fn run_embedded(
+    code: &str,
+    data: &Data,
+    execution: &Execution,
+) -> Result<String, AppError> {
+    let exe = std::env::current_exe()?;
+    let dir = tempfile::Builder::new().prefix("textgo-").tempdir()?;
+    let script = dir.path().join("script.js");
+    let result = dir.path().join("result");
+    std::fs::write(&script, code)?;
+
+    let status = embedded_status();
+    execution.set_runtime(Some(&status.name), &exe.to_string_lossy(), status.version);
+    let mut command = execution.command(&exe, dir.path())?;
+    command.arg(EMBEDDED_FLAG).arg(&script).arg(&result);
+    let child = execution
+        .spawn(&mut command)
+        .map_err(|e| format!("Failed to start embedded JavaScript engine: {}", e))?;
+    let output = execution
+        .wait(child, Some(&serde_json::to_vec(data)?))
+        .await?;
+
+    if !output.status.success() {
+        if exceeded_cpu_time(&output.status) {
+            return Err("JavaScript execution failed: instruction budget exceeded".into());
+        }
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        // 超出内存上限时分配失败，进程直接中止
+        if stderr.contains("memory allocation of") {
+            return Err("JavaScript execution failed: memory limit exceeded".into());
+        }
+        return Err(format!("JavaScript execution failed: {}", stderr.trim_end()).into());
+    }
+    match std::fs::read(&result) {
+        Ok(bytes) => Ok(String::from_utf8_lossy(&bytes).into_owned()),
+        Err(e) if e.kind() == ErrorKind::NotFound => {
+            Err("Script exited without returning a result".into())
+        }
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// 以内置引擎进程启动时执行脚本并返回退出码，否则返回 `None`
+///
+/// 需要在应用程序初始化之前调用
+pub fn embedded_main() -> Option<i32> {
+    let mut args = std::env::args_os().skip(1);
+    if args.next()? != EMBEDDED_FLAG {
+        return None;
+    }
+    let (Some(script), Some(result)) = (args.next(), args.next()) else {
+        eprintln!("Usage: {} <script> <result>", EMBEDDED_FLAG);
+        return Some(2);
+    };
+
+    if let Err(e) = limit_resources() {
+        // 无法限制资源时拒绝执行，而不是在没有上限的情况下运行
+        eprintln!("Failed to limit resources: {}", e);
+        return Some(1);
+    }
+    match evaluate(Path::new(&script), Path::new(&result)) {
+        Ok(()) => Some(0),
+        Err(e) => {
+            eprintln!("{}", e);
+            Some(1)
+        }
+    }
+}
+
+/// 读取标准输入中的数据，调用脚本中的 `process(data)` 并写入结果文件
+fn evaluate(script: &Path, result: &Path) -> Result<(), String> {
+    let code = std::fs::read_to_string(script).map_err(|e| e.to_string())?;
+    let mut input = String::new();
+    std::io::stdin()
+        .read_to_string(&mut input)
+        .map_err(|e| e.to_string())?;
+    let data: serde_json::Value = serde_json::from_str(&input).map_err(|e| e.to_string())?;
+
+    let mut context = Context::default();
+    let limits = context.runtime_limits_mut();
+    limits.set_loop_iteration_limit(LOOP_ITERATION_LIMIT);
+    limits.set_recursion_limit(RECURSION_LIMIT);
+    register_console(&mut context).map_err(|e| e.to_string())?;
+
+    let output = call_process(&code, &data, &mut context).map_err(|e| e.to_string())?;
+    std::fs::write(result, output).map_err(|e| e.to_string())
+}
+
+/// 执行脚本并调用其中定义的 `process` 函数
+fn call_process(code: &str, data: &serde_json::Value, context: &mut Context) -> JsResult<String> {
+    context.eval(Source::from_bytes(code))?;
+    let process = context
+        .global_object()
+        .get(js_string!("process"), context)?;
+    let Some(process) = process.as_callable() else {
+        return Err(boa_engine::JsNativeError::reference()
+            .with_message("process(data) function is not defined")
+            .into());
+    };
+
+    let data = JsValue::from_json(data, context)?;
+    let mut value = process.call(&JsValue::undefined(), &[data], context)?;
+    // 等待异步函数返回的 Promise
+    if let Some(object) = value.as_promise() {
+        let promise = JsPromise::from_object(object.clone())?;
+        context.run_jobs();
+        value = match promise.state() {
+            PromiseState::Fulfilled(value) => value,
+            PromiseState::Rejected(reason) => return Err(boa_engine::JsError::from_opaque(reason)),
+            PromiseState::Pending => {
+                return Err(boa_engine::JsNativeError::error()
+                    .with_message("process(data) returned a promise that never settled")
+                    .into())
+            }
+        };
+    }
+
+    match &value {
+        JsValue::String(text) => Ok(text.to_std_string_escaped()),
+        JsValue::Undefined => Ok(String::new()),
+        _ => Ok(value.to_json(context)?.to_string()),
+    }
+}
+
+/// 注册将输出写入标准输出和标准错误的 `console` 对象
+fn register_console(context: &mut Context) -> JsResult<()> {
+    let log = NativeFunction::from_fn_ptr(|_, args, context| {
+        println!("{}", format_args(args, context));
+        Ok(JsValue::undefined())
+    });
+    let error = NativeFunction::from_fn_ptr(|_, args, context| {
+        eprintln!("{}", format_args(args, context));
+        Ok(JsValue::undefined())
+    });
+    let console = ObjectInitializer::new(context)
+        .function(log.clone(), js_string!("log"), 0)
+        .function(log.clone(), js_string!("info"), 0)
+        .function(log, js_string!("debug"), 0)
+        .function(error.clone(), js_string!("warn"), 0)
+        .function(error, js_string!("error"), 0)
+        .build();
+    context.register_global_property(js_string!("console"), console, Attribute::all())
+}
+
+/// 以空格连接 `console` 方法的参数，字符串不加引号
+fn format_args(args: &[JsValue], context: &mut Context) -> String {
+    args.iter()
+        .map(|arg| match arg {
+            JsValue::String(text) => text.to_std_string_escaped(),
+            JsValue::Object(_) => match arg.to_json(context) {
+                Ok(json) => json.to_string(),
+                Err(_) => arg.display().to_string(),
+            },
+            _ => arg.display().to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// 限制当前进程可使用的内存和 CPU 时间
+#[cfg(unix)]
+fn limit_resources() -> std::io::Result<()> {
+    let limits = [
+        (libc::RLIMIT_DATA, MEMORY_LIMIT),
+        (libc::RLIMIT_CPU, CPU_TIME_LIMIT_SECS),
+    ];
+    for (resource, value) in limits {
+        let mut limit = libc::rlimit {
+            rlim_cur: 0,
+            rlim_max: 0,
+        };
+        // SAFETY: getrlimit 只写入传入的结构体
+        if unsafe { libc::getrlimit(resource, &mut limit) } != 0 {
+            return Err(std::io::Error::last_os_error());
+        }
+        // 沙箱可能已设置更低的上限，非特权进程不能提高硬限制，只在更低时覆盖
+        let value = (value as libc::rlim_t).min(limit.rlim_max);
+        let limit = libc::rlimit {
+            rlim_cur: value.min(limit.rlim_cur),
+            rlim_max: value,
+        };
+        // SAFETY: setrlimit 只读取传入的结构体
+        if unsafe { libc::setrlimit(resource, &limit) } != 0 {
+            return Err(std::io::Error::last_os_error());
+        }
+    }
+    Ok(())
+}
+
+/// 将当前进程加入限制内存和 CPU 时间的作业对象
+#[cfg(windows)]
+fn limit_resources() -> std::io::Result<()> {
+    use windows::core::PCWSTR;
+    use windows::Win32::System::JobObjects::{
+        AssignProcessToJobObject, CreateJobObjectW, JobObjectExtendedLimitInformation,
+        SetInformationJobObject, JOBOBJECT_EXTENDED_LIMIT_INFORMATION,
+        JOB_OBJECT_LIMIT_PROCESS_MEMORY, JOB_OBJECT_LIMIT_PROCESS_TIME,
+    };
+    use windows::Win32::System::Threading::GetCurrentProcess;
+
+    let mut info = JOBOBJECT_EXTENDED_LIMIT_INFORMATION::default();
+    info.BasicLimitInformation.LimitFlags =
+        JOB_OBJECT_LIMIT_PROCESS_MEMORY | JOB_OBJECT_LIMIT_PROCESS_TIME;
+    // 用户态 CPU 时间以 100 纳秒为单位
+    info.BasicLimitInformation.PerProcessUserTimeLimit = (CPU_TIME_LIMIT_SECS * 10_000_000) as i64;
+    info.ProcessMemoryLimit = MEMORY_LIMIT as usize;
+    // SAFETY: 作业对象句柄在进程退出前保持有效，结构体大小与信息类别一致
+    unsafe {
+        let job = CreateJobObjectW(None, PCWSTR::null())?;
+        SetInformationJobObject(
+            job,
+            JobObjectExtendedLimitInformation,
+            &info as *const _ as *const std::ffi::c_void,
+            std::mem::size_of::<JOBOBJECT_EXTENDED_LIMIT_INFORMATION>() as u32,
+        )?;
+        AssignProcessToJobObject(job, GetCurrentProcess())?;
+    }
+    Ok(())
+}
+
+/// 其他平台无法限制资源，拒绝使用内置引擎
+#[cfg(not(any(unix, windows)))]
+fn limit_resources() -> std::io::Result<()> {
+    Err(std::io::Error::new(
+        std::io::ErrorKind::Unsupported,
+        "resource limits are not supported on this platform",
+    ))
+}
+
+/// 判断进程是否因超出 CPU 时间上限而终止
+fn exceeded_cpu_time(status: &std::process::ExitStatus) -> bool {
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::ExitStatusExt;
+        status.signal() == Some(libc::SIGXCPU)
+    }
+    #[cfg(windows)]
+    {
+        // 作业对象在超出 CPU 时间时以 ERROR_NOT_ENOUGH_QUOTA 终止进程
+        status.code() == Some(1816)
+    }
+    #[cfg(not(any(unix, windows)))]
+    {
+        let _ = status;
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detects_module_syntax_and_node_apis() {
+        let scripts = [
+            "import fs from 'fs';\nfunction process(data) { return ''; }",
+            "const { readFileSync } = require ('fs');",
+            "const lib = await import('./lib.js');",
+            "export function process(data) { return data.selection; }",
+            "function process(data) { process.stdout.write(data.selection); }",
+            "function process(data) { return process.env.HOME; }",
+            "function process(data) { return Buffer.from(data.selection).toString('base64'); }",
+        ];
+        for script in scripts {
+            assert!(needs_host(script), "{}", script);
+        }
+    }
+
+    #[test]
+    fn ignores_comments_strings_and_properties() {
+        let scripts = [
+            "function process(data) { return data.selection.toUpperCase(); }",
+            "// require('fs')\nfunction process(data) { return 'import x from \"y\"'; }",
+            "/* process.env */ function process(data) { return `require(${data.selection})`; }",
+            "function process(data) { return data.import + data.require; }",
+            "function process(data) { return JSON.stringify(data, null, 2); }",
+        ];
+        for script in scripts {
+            assert!(!needs_host(script), "{}", script);
+        }
+    }
+
+    #[test]
+    fn detects_missing_globals() {
+        assert!(missing_global(
+            "JavaScript execution failed: ReferenceError: Buffer is not defined"
+        ));
+        assert!(!missing_global(
+            "JavaScript execution failed: ReferenceError: process(data) function is not defined"
+        ));
+        assert!(!missing_global(
+            "JavaScript execution failed: TypeError: not a callable function"
+        ));
+    }
+}
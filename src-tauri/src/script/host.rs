@@ -0,0 +1,286 @@
+use super::command::run_command;
+use super::process::Execution;
+use super::runner::{run_script, Data};
+use crate::engine::Config;
+use crate::error::AppError;
+use crate::llm::{self, ChatMessage, ChatRequest, Provider};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use tauri_plugin_clipboard_manager::ClipboardExt;
+use tauri_plugin_dialog::{DialogExt, MessageDialogButtons};
+use tauri_plugin_notification::NotificationExt;
+use tokio::sync::oneshot;
+
+/// 脚本写入标准错误的请求行前缀，其余内容为 JSON-RPC 请求
+pub const HOST_REQUEST_MARK: &str = "\x1etextgo-rpc ";
+
+/// Python 宿主 API 模块，写入工作区后可通过 `import textgo` 使用
+pub const PYTHON_HOST: &str = include_str!("textgo.py");
+
+/// JavaScript 宿主 API，由运行器加载为全局的 `textgo` 对象
+pub const JAVASCRIPT_HOST: &str = include_str!("textgo.js");
+
+/// 通过 `run_action` 嵌套执行动作的最大深度
+const MAX_NESTED_DEPTH: usize = 4;
+
+/// JSON-RPC 错误码：请求格式错误
+const INVALID_REQUEST: i64 = -32600;
+
+/// JSON-RPC 错误码：方法不存在
+const METHOD_NOT_FOUND: i64 = -32601;
+
+/// JSON-RPC 错误码：参数错误
+const INVALID_PARAMS: i64 = -32602;
+
+/// 自定义错误码：未授予权限
+const PERMISSION_DENIED: i64 = -32001;
+
+/// 自定义错误码：执行失败
+const REQUEST_FAILED: i64 = -32000;
+
+/// 脚本可以使用的宿主 API 权限
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HostPermission {
+    /// 读取剪贴板
+    Clipboard,
+    /// 发送系统通知
+    Notify,
+    /// 调用大模型
+    Prompt,
+    /// 执行其他动作
+    RunAction,
+    /// 询问用户
+    AskUser,
+}
+
+impl HostPermission {
+    /// 解析权限名称
+    pub fn parse(name: &str) -> Option<Self> {
+        serde_json::from_value(Value::String(name.to_string())).ok()
+    }
+}
+
+/// JSON-RPC 请求
+#[derive(Debug, Deserialize)]
+struct Request {
+    /// 请求标识
+    #[serde(default)]
+    id: Value,
+    /// 方法名称
+    method: String,
+    /// 参数
+    #[serde(default)]
+    params: Value,
+}
+
+/// 请求失败的错误码和信息
+type Failure = (i64, String);
+
+/// 脚本运行期间处理宿主 API 请求
+pub struct Host {
+    /// 应用句柄
+    app: tauri::AppHandle,
+    /// 授予的权限
+    permissions: Vec<HostPermission>,
+}
+
+impl Host {
+    /// 创建宿主，未授予任何权限时返回 `None`
+    pub fn new(app: &tauri::AppHandle, permissions: &[HostPermission]) -> Option<Self> {
+        (!permissions.is_empty()).then(|| Self {
+            app: app.clone(),
+            permissions: permissions.to_vec(),
+        })
+    }
+
+    /// 处理一行请求，返回 JSON-RPC 响应
+    pub async fn handle(&self, line: &str, execution: &Execution) -> String {
+        let (id, result) = match serde_json::from_str::<Request>(line) {
+            Ok(request) => {
+                let result = self.call(&request.method, &request.params, execution).await;
+                (request.id, result)
+            }
+            Err(e) => (
+                Value::Null,
+                Err((INVALID_REQUEST, format!("Invalid request: {}", e))),
+            ),
+        };
+        let response = match result {
+            Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+            Err((code, message)) => {
+                json!({ "jsonrpc": "2.0", "id": id, "error": { "code": code, "message": message } })
+            }
+        };
+        response.to_string()
+    }
+
+    /// 检查权限后调用方法
+    async fn call(
+        &self,
+        method: &str,
+        params: &Value,
+        execution: &Execution,
+    ) -> Result<Value, Failure> {
+        let permission = match method {
+            "clipboard.read" => HostPermission::Clipboard,
+            "notify" => HostPermission::Notify,
+            "prompt" => HostPermission::Prompt,
+            "run_action" => HostPermission::RunAction,
+            "ask_user" => HostPermission::AskUser,
+            _ => {
+                return Err((
+                    METHOD_NOT_FOUND,
+                    format!("Unknown host method '{}'", method),
+                ))
+            }
+        };
+        if !self.permissions.contains(&permission) {
+            return Err((
+                PERMISSION_DENIED,
+                format!("Permission '{}' is not granted to this script", method),
+            ));
+        }
+
+        let failed = |e: AppError| (REQUEST_FAILED, e.to_string());
+        match permission {
+            HostPermission::Clipboard => {
+                let text = self.app.clipboard().read_text().unwrap_or_default();
+                Ok(Value::String(text))
+            }
+            HostPermission::Notify => {
+                let message = string_param(params, "message")?;
+                let title = optional_param(params, "title").unwrap_or("TextGO");
+                self.app
+                    .notification()
+                    .builder()
+                    .title(title)
+                    .body(message)
+                    .show()
+                    .map_err(|e| (REQUEST_FAILED, e.to_string()))?;
+                Ok(Value::Null)
+            }
+            HostPermission::Prompt => {
+                let provider = match optional_param(params, "provider") {
+                    Some(name) => serde_json::from_value(Value::String(name.to_string()))
+                        .map_err(|_| (INVALID_PARAMS, format!("Unknown provider '{}'", name)))?,
+                    None => Provider::default(),
+                };
+                let mut messages = vec![ChatMessage::new("user", string_param(params, "text")?)];
+                if let Some(system) = optional_param(params, "system") {
+                    messages.insert(0, ChatMessage::new("system", system));
+                }
+                let request = ChatRequest {
+                    provider,
+                    model: string_param(params, "model")?.to_string(),
+                    messages,
+                    tools: Vec::new(),
+                    temperature: None,
+                };
+                self.prompt(&request, execution)
+                    .await
+                    .map(Value::String)
+                    .map_err(failed)
+            }
+            HostPermission::RunAction => {
+                let action = string_param(params, "action")?;
+                let text = optional_param(params, "text").unwrap_or_default();
+                // 嵌套执行会再次处理宿主 API 请求，需要装箱以避免无限大小的 future
+                Box::pin(self.run_action(action, text, execution))
+                    .await
+                    .map(Value::String)
+                    .map_err(failed)
+            }
+            HostPermission::AskUser => {
+                let question = string_param(params, "question")?;
+                let (tx, rx) = oneshot::channel();
+                self.app
+                    .dialog()
+                    .message(question)
+                    .title(optional_param(params, "title").unwrap_or("TextGO"))
+                    .buttons(MessageDialogButtons::YesNo)
+                    .show(move |answer| {
+                        let _ = tx.send(answer);
+                    });
+                let answer = rx
+                    .await
+                    .map_err(|_| (REQUEST_FAILED, "Dialog was closed".to_string()))?;
+                Ok(Value::Bool(answer))
+            }
+        }
+    }
+
+    /// 调用大模型并返回完整的回复
+    async fn prompt(&self, request: &ChatRequest, parent: &Execution) -> Result<String, AppError> {
+        let config = Config::load(&self.app)?;
+        // 脚本被取消时请求随之中止，单独的执行只用于限制时间
+        let execution = Execution::new(Some(llm::LLM_TIMEOUT_SECS))?.cancelled_with(parent);
+        llm::chat(request, &config.llm, &execution, |_| {}).await
+    }
+
+    /// 执行脚本或命令行动作并返回结果
+    async fn run_action(
+        &self,
+        name: &str,
+        text: &str,
+        parent: &Execution,
+    ) -> Result<String, AppError> {
+        if parent.depth() >= MAX_NESTED_DEPTH {
+            return Err(format!(
+                "Nested action '{}' exceeds the maximum depth of {}",
+                name, MAX_NESTED_DEPTH
+            )
+            .into());
+        }
+        let config = Config::load(&self.app)?;
+        let action = config
+            .resolve_action(name)
+            .ok_or_else(|| format!("Action '{}' not found", name))?;
+        let data = Data {
+            selection: text.to_string(),
+            clipboard: self.app.clipboard().read_text().unwrap_or_default(),
+            datetime: chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true),
+            ..Default::default()
+        };
+
+        // 嵌套执行不能使用比当前脚本更弱的沙箱，否则沙箱中的脚本可以借此逃逸
+        if let Some(script) = config.script(&action) {
+            let execution = Execution::new(script.timeout)?
+                .sandboxed(script.sandbox.unwrap_or_default().max(parent.sandbox))
+                .stream_to(&self.app)
+                .nested_in(parent)
+                .cancelled_with(parent);
+            Box::pin(run_script(
+                script,
+                &data,
+                &config.script_options,
+                &execution,
+            ))
+            .await
+        } else if let Some(command) = config.command(&action) {
+            let execution = Execution::new(command.timeout)?
+                .sandboxed(command.sandbox.unwrap_or_default().max(parent.sandbox))
+                .stream_to(&self.app)
+                .nested_in(parent)
+                .cancelled_with(parent);
+            run_command(command, &data, &execution).await
+        } else {
+            Err(format!("Action '{}' cannot be run from a script", name).into())
+        }
+    }
+}
+
+/// 读取必需的字符串参数
+fn string_param<'a>(params: &'a Value, name: &str) -> Result<&'a str, Failure> {
+    optional_param(params, name).ok_or_else(|| {
+        (
+            INVALID_PARAMS,
+            format!("Missing string parameter '{}'", name),
+        )
+    })
+}
+
+/// 读取可选的字符串参数
+fn optional_param<'a>(params: &'a Value, name: &str) -> Option<&'a str> {
+    params.get(name).and_then(Value::as_str)
+}
@@ -0,0 +1,386 @@
+use super::host::HostPermission;
+use super::runner::ScriptAction;
+use super::runtime::{Registry, Runtime};
+use super::sandbox::Sandbox;
+use super::wasm::WASM_LANG;
+use crate::engine::Concurrency;
+use crate::error::AppError;
+use crate::SCRIPT_LIBRARY;
+use notify::{RecursiveMode, Watcher};
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::time::Duration;
+use tauri::Emitter;
+
+/// 前置元数据的分隔行
+const FRONT_MATTER_FENCE: &str = "---";
+
+/// 前置元数据可以使用的注释前缀
+const COMMENT_PREFIXES: &[&str] = &["//", "#", "--", ";;"];
+
+/// 编译后的 WebAssembly 模块文件扩展名，按路径加载
+const WASM_EXTENSION: &str = "wasm";
+
+/// WebAssembly 文本格式文件扩展名
+const WAT_EXTENSION: &str = "wat";
+
+/// 目录变化后等待文件写入完成的时间
+const RELOAD_DELAY: Duration = Duration::from_millis(300);
+
+/// 前置元数据中的一项：行号、键和值
+type Field = (usize, String, String);
+
+/// 解析错误：出错的行号和错误信息
+type ParseError = (Option<usize>, String);
+
+/// 脚本目录中的解析问题
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LibraryIssue {
+    /// 文件路径
+    pub path: String,
+    /// 出错的行号，从 1 开始
+    pub line: Option<usize>,
+    /// 错误信息
+    pub message: String,
+}
+
+/// 脚本目录的加载结果
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Library {
+    /// 脚本目录
+    pub dir: Option<String>,
+    /// 成功加载的脚本
+    pub scripts: Vec<ScriptAction>,
+    /// 解析失败的文件
+    pub issues: Vec<LibraryIssue>,
+}
+
+/// 正在监听的脚本目录
+pub struct WatchedLibrary {
+    /// 目录路径
+    dir: PathBuf,
+    /// 最近一次的加载结果
+    library: Library,
+    /// 文件监听器，丢弃时停止监听
+    _watcher: notify::RecommendedWatcher,
+}
+
+/// 获取脚本目录中最近一次加载的结果
+pub fn script_library() -> Library {
+    SCRIPT_LIBRARY
+        .lock()
+        .ok()
+        .and_then(|watched| watched.as_ref().map(|w| w.library.clone()))
+        .unwrap_or_default()
+}
+
+/// 加载并监听脚本目录，目录中的文件变化时重新加载并发送 `script-library` 事件
+///
+/// 目录不存在时自动创建，替换之前监听的目录
+pub fn watch_library(
+    app: &tauri::AppHandle,
+    dir: PathBuf,
+    runtimes: Vec<Runtime>,
+) -> Result<Library, AppError> {
+    let mut watched = SCRIPT_LIBRARY.lock()?;
+    std::fs::create_dir_all(&dir)?;
+
+    let (tx, rx) = mpsc::channel();
+    let mut watcher =
+        notify::recommended_watcher(move |event: notify::Result<notify::Event>| match event {
+            Ok(event) if !event.kind.is_access() => {
+                let _ = tx.send(());
+            }
+            Ok(_) => {}
+            Err(e) => eprintln!("[ERROR] Failed to watch scripts directory: {}", e),
+        })
+        .map_err(|e| format!("Failed to watch scripts directory: {}", e))?;
+    watcher
+        .watch(&dir, RecursiveMode::NonRecursive)
+        .map_err(|e| format!("Failed to watch {}: {}", dir.display(), e))?;
+
+    let library = load_library(&dir, &runtimes);
+    *watched = Some(WatchedLibrary {
+        dir: dir.clone(),
+        library: library.clone(),
+        _watcher: watcher,
+    });
+
+    // 监听器被替换后发送端随之丢弃，线程退出
+    let app = app.clone();
+    std::thread::spawn(move || {
+        while rx.recv().is_ok() {
+            // 合并短时间内的连续变化
+            while rx.recv_timeout(RELOAD_DELAY).is_ok() {}
+            let library = load_library(&dir, &runtimes);
+            match SCRIPT_LIBRARY.lock() {
+                Ok(mut watched) => match watched.as_mut() {
+                    Some(watched) if watched.dir == dir => watched.library = library.clone(),
+                    _ => break,
+                },
+                Err(_) => break,
+            }
+            if let Err(e) = app.emit("script-library", &library) {
+                eprintln!("[ERROR] Failed to emit script library event: {}", e);
+            }
+        }
+    });
+
+    Ok(library)
+}
+
+/// 加载目录中的全部脚本
+///
+/// 无法识别类型的文件会被忽略，解析失败的文件记录为问题
+pub fn load_library(dir: &Path, runtimes: &[Runtime]) -> Library {
+    let registry = Registry::new(runtimes);
+    let mut library = Library {
+        dir: Some(dir.display().to_string()),
+        ..Default::default()
+    };
+    let entries = match std::fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) => {
+            library.issues.push(LibraryIssue {
+                path: dir.display().to_string(),
+                line: None,
+                message: e.to_string(),
+            });
+            return library;
+        }
+    };
+
+    let mut paths: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.is_file())
+        .filter(|path| {
+            path.file_name()
+                .is_some_and(|name| !name.to_string_lossy().starts_with('.'))
+        })
+        .collect();
+    paths.sort();
+
+    for path in paths {
+        let issue = |line: Option<usize>, message: String| LibraryIssue {
+            path: path.display().to_string(),
+            line,
+            message,
+        };
+        // 编译后的模块是二进制文件，运行时按路径读取
+        let binary = path
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case(WASM_EXTENSION));
+        let source = match binary {
+            true => String::new(),
+            false => match std::fs::read_to_string(&path) {
+                Ok(source) => source,
+                Err(e) => {
+                    library.issues.push(issue(None, e.to_string()));
+                    continue;
+                }
+            },
+        };
+        match parse_script(&path, &source, &registry) {
+            Ok(Some(script)) => {
+                if let Some(existing) = library.scripts.iter().find(|s| s.id == script.id) {
+                    let message = format!(
+                        "Name '{}' is already used by {}",
+                        script.id,
+                        existing.source.as_deref().unwrap_or_default()
+                    );
+                    library.issues.push(issue(None, message));
+                } else {
+                    library.scripts.push(script);
+                }
+            }
+            Ok(None) => {}
+            Err((line, message)) => library.issues.push(issue(line, message)),
+        }
+    }
+    library
+}
+
+/// 解析脚本文件
+///
+/// 文件开头可以包含注释形式的前置元数据，以 `---` 行开始和结束，每行为 `key: value`
+/// 无法确定脚本语言时返回 `None`，解析失败时返回出错的行号和错误信息
+fn parse_script(
+    path: &Path,
+    source: &str,
+    registry: &Registry,
+) -> Result<Option<ScriptAction>, ParseError> {
+    let stem = path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let extension = path
+        .extension()
+        .map(|ext| ext.to_string_lossy().to_ascii_lowercase())
+        .unwrap_or_default();
+    let mut script = ScriptAction {
+        id: stem,
+        lang: match extension.as_str() {
+            WASM_EXTENSION | WAT_EXTENSION => WASM_LANG.to_string(),
+            _ => registry
+                .runtimes()
+                .iter()
+                .find(|r| !r.extension.is_empty() && r.extension == extension)
+                .map(|r| r.lang.clone())
+                .unwrap_or_default(),
+        },
+        script: match extension.as_str() {
+            WASM_EXTENSION => path.display().to_string(),
+            _ => source.to_string(),
+        },
+        quiet_mode: None,
+        pure: None,
+        timeout: None,
+        sandbox: None,
+        concurrency: None,
+        dependencies: Vec::new(),
+        env: Default::default(),
+        secrets: Vec::new(),
+        tests: Vec::new(),
+        wasm: Default::default(),
+        permissions: Vec::new(),
+        source: Some(path.display().to_string()),
+    };
+
+    for (line, key, value) in front_matter(source)? {
+        let invalid = |expected: &str| {
+            Err((
+                Some(line),
+                format!(
+                    "Invalid value '{}' for '{}', expected {}",
+                    value, key, expected
+                ),
+            ))
+        };
+        match key.as_str() {
+            "name" if !value.is_empty() => script.id = value,
+            "name" => return invalid("a non-empty name"),
+            "lang" if value != WASM_LANG && registry.candidates(&value).is_empty() => {
+                return Err((
+                    Some(line),
+                    format!("Unsupported script language: {}", value),
+                ))
+            }
+            "lang" => script.lang = value,
+            "quietMode" => match value.as_str() {
+                "true" => script.quiet_mode = Some(true),
+                "false" => script.quiet_mode = Some(false),
+                _ => return invalid("true or false"),
+            },
+            "pure" => match value.as_str() {
+                "true" => script.pure = Some(true),
+                "false" => script.pure = Some(false),
+                _ => return invalid("true or false"),
+            },
+            "timeout" => match value.parse::<u64>() {
+                Ok(secs) if secs > 0 => script.timeout = Some(secs),
+                _ => return invalid("a positive number of seconds"),
+            },
+            "sandbox" => match value.as_str() {
+                "none" => script.sandbox = Some(Sandbox::None),
+                "restricted" => script.sandbox = Some(Sandbox::Restricted),
+                "offline" => script.sandbox = Some(Sandbox::Offline),
+                _ => return invalid("none, restricted or offline"),
+            },
+            "concurrency" => match value.as_str() {
+                "drop" => script.concurrency = Some(Concurrency::Drop),
+                "queue" => script.concurrency = Some(Concurrency::Queue),
+                "restart" => script.concurrency = Some(Concurrency::Restart),
+                _ => return invalid("drop, queue or restart"),
+            },
+            "dependencies" => script.dependencies = split_list(&value),
+            "secrets" => script.secrets = split_list(&value),
+            "permissions" => {
+                let names = split_list(&value);
+                match names
+                    .iter()
+                    .map(|name| HostPermission::parse(name))
+                    .collect()
+                {
+                    Some(permissions) => script.permissions = permissions,
+                    None => return invalid("clipboard, notify, prompt, run_action or ask_user"),
+                }
+            }
+            "fuel" => match value.parse::<u64>() {
+                Ok(fuel) if fuel > 0 => script.wasm.fuel = Some(fuel),
+                _ => return invalid("a positive number"),
+            },
+            "memoryMb" => match value.parse::<u64>() {
+                Ok(mb) if mb > 0 => script.wasm.memory_mb = Some(mb),
+                _ => return invalid("a positive number of MiB"),
+            },
+            "readDirs" => script.wasm.read_dirs = split_list(&value),
+            "writeDirs" => script.wasm.write_dirs = split_list(&value),
+            _ => return Err((Some(line), format!("Unknown metadata key '{}'", key))),
+        }
+    }
+
+    if script.lang.is_empty() {
+        return Ok(None);
+    }
+    Ok(Some(script))
+}
+
+/// 拆分以逗号分隔的列表
+fn split_list(value: &str) -> Vec<String> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|item| !item.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// 读取前置元数据，返回行号、键和值
+fn front_matter(source: &str) -> Result<Vec<Field>, ParseError> {
+    let mut lines = source
+        .lines()
+        .enumerate()
+        .map(|(index, line)| (index + 1, line.trim()))
+        // 跳过解释器声明行
+        .skip_while(|(index, line)| *index == 1 && line.starts_with("#!"));
+
+    let Some((start, first)) = lines.next() else {
+        return Ok(Vec::new());
+    };
+    let Some(prefix) = COMMENT_PREFIXES
+        .iter()
+        .find(|prefix| first.strip_prefix(**prefix).map(str::trim) == Some(FRONT_MATTER_FENCE))
+    else {
+        return Ok(Vec::new());
+    };
+
+    let mut fields = Vec::new();
+    for (line, text) in lines {
+        let Some(text) = text.strip_prefix(prefix).map(str::trim) else {
+            break;
+        };
+        if text == FRONT_MATTER_FENCE {
+            return Ok(fields);
+        }
+        if text.is_empty() {
+            continue;
+        }
+        let Some((key, value)) = text.split_once(':') else {
+            return Err((
+                Some(line),
+                format!("Expected 'key: value', found '{}'", text),
+            ));
+        };
+        fields.push((line, key.trim().to_string(), value.trim().to_string()));
+    }
+    Err((
+        Some(start),
+        format!(
+            "Front matter is not closed with '{} {}'",
+            prefix, FRONT_MATTER_FENCE
+        ),
+    ))
+}
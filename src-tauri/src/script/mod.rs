@@ -0,0 +1,32 @@
+mod audit;
+mod command;
+mod deps;
+mod detect;
+mod embedded;
+mod host;
+mod library;
+mod output;
+mod pool;
+mod process;
+mod runner;
+mod runtime;
+mod sandbox;
+mod secrets;
+mod testing;
+mod wasm;
+
+pub use audit::*;
+pub use command::*;
+pub use deps::*;
+pub use detect::*;
+pub use embedded::*;
+pub use library::*;
+pub use output::*;
+pub use pool::*;
+pub use process::*;
+pub use runner::*;
+pub use runtime::Runtime;
+pub use sandbox::Sandbox;
+pub use secrets::*;
+pub use testing::*;
+pub use wasm::*;
@@ -0,0 +1,226 @@
+use super::sandbox::Sandbox;
+use crate::error::AppError;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// 结构化结果中允许的字段
+const OUTPUT_FIELDS: &[&str] = &["paste", "popup", "notify", "clipboard", "open", "chain"];
+
+/// 弹窗内容的渲染格式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PopupFormat {
+    /// 纯文本
+    #[default]
+    Text,
+    /// Markdown
+    Markdown,
+    /// HTML
+    Html,
+}
+
+/// 弹窗内容
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Popup {
+    /// 内容
+    pub content: String,
+    /// 渲染格式
+    #[serde(default)]
+    pub format: PopupFormat,
+}
+
+/// 系统通知
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Notification {
+    /// 标题，为空时使用动作名称
+    #[serde(default)]
+    pub title: Option<String>,
+    /// 正文
+    pub body: String,
+}
+
+/// 后续执行的动作
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Chain {
+    /// 动作标识或名称
+    pub action: String,
+    /// 传递给下一个动作的文本，为空时使用要粘贴的文本或原选中文本
+    #[serde(default)]
+    pub selection: Option<String>,
+}
+
+/// 脚本的结构化结果
+///
+/// 脚本返回只包含以下字段的对象时，各部分分别分发，否则整个返回值作为文本处理
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScriptOutput {
+    /// 要粘贴的文本
+    #[serde(default)]
+    pub paste: Option<String>,
+    /// 弹窗显示的内容
+    #[serde(default)]
+    pub popup: Option<Popup>,
+    /// 系统通知
+    #[serde(default)]
+    pub notify: Option<Notification>,
+    /// 写入剪贴板的文本
+    #[serde(default)]
+    pub clipboard: Option<String>,
+    /// 要打开的链接或文件
+    #[serde(default)]
+    pub open: Vec<String>,
+    /// 后续执行的动作
+    #[serde(default)]
+    pub chain: Option<Chain>,
+}
+
+/// 结构化结果的原始形式，部分字段支持简写
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawOutput {
+    #[serde(default)]
+    paste: Option<String>,
+    #[serde(default)]
+    popup: Option<OneOf<Popup>>,
+    #[serde(default)]
+    notify: Option<OneOf<Notification>>,
+    #[serde(default)]
+    clipboard: Option<String>,
+    #[serde(default)]
+    open: Option<OneOrMany>,
+    #[serde(default)]
+    chain: Option<OneOf<Chain>>,
+}
+
+/// 字符串简写或完整对象
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum OneOf<T> {
+    Short(String),
+    Full(T),
+}
+
+/// 单个或多个字符串
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum OneOrMany {
+    One(String),
+    Many(Vec<String>),
+}
+
+/// 解析脚本返回值
+///
+/// 不是结构化结果时返回 `None`，字段类型或取值不合法时返回错误
+pub fn parse_output(result: &str) -> Result<Option<ScriptOutput>, AppError> {
+    let trimmed = result.trim();
+    if !trimmed.starts_with('{') {
+        return Ok(None);
+    }
+    let Ok(serde_json::Value::Object(object)) = serde_json::from_str(trimmed) else {
+        return Ok(None);
+    };
+    // 包含其他字段的对象视为普通数据
+    if object.is_empty()
+        || !object
+            .keys()
+            .all(|key| OUTPUT_FIELDS.contains(&key.as_str()))
+    {
+        return Ok(None);
+    }
+
+    let raw: RawOutput = serde_json::from_value(serde_json::Value::Object(object))
+        .map_err(|e| format!("Invalid script result: {}", e))?;
+    let output = ScriptOutput {
+        paste: raw.paste,
+        popup: raw.popup.map(|popup| match popup {
+            OneOf::Short(content) => Popup {
+                content,
+                format: PopupFormat::Markdown,
+            },
+            OneOf::Full(popup) => popup,
+        }),
+        notify: raw.notify.map(|notify| match notify {
+            OneOf::Short(body) => Notification { title: None, body },
+            OneOf::Full(notify) => notify,
+        }),
+        clipboard: raw.clipboard,
+        open: match raw.open {
+            Some(OneOrMany::One(target)) => vec![target],
+            Some(OneOrMany::Many(targets)) => targets,
+            None => Vec::new(),
+        },
+        chain: raw.chain.map(|chain| match chain {
+            OneOf::Short(action) => Chain {
+                action,
+                selection: None,
+            },
+            OneOf::Full(chain) => chain,
+        }),
+    };
+    output.validate()?;
+    Ok(Some(output))
+}
+
+impl ScriptOutput {
+    /// 校验字段取值
+    fn validate(&self) -> Result<(), AppError> {
+        for target in &self.open {
+            if !is_url(target) && !Path::new(target).is_absolute() {
+                return Err(format!(
+                    "Invalid script result: cannot open '{}', expected a URL or an absolute path",
+                    target
+                )
+                .into());
+            }
+        }
+        if let Some(chain) = &self.chain {
+            if chain.action.trim().is_empty() {
+                return Err("Invalid script result: chained action is empty".into());
+            }
+        }
+        if let Some(notify) = &self.notify {
+            if notify.body.trim().is_empty() {
+                return Err("Invalid script result: notification body is empty".into());
+            }
+        }
+        Ok(())
+    }
+
+    /// 检查沙箱中的动作是否可以分发该结果
+    ///
+    /// 沙箱中的动作只能打开网页和邮件链接，不能打开本地文件或程序
+    pub fn check_sandbox(&self, sandbox: Sandbox) -> Result<(), AppError> {
+        if !sandbox.is_enabled() {
+            return Ok(());
+        }
+        match self.open.iter().find(|target| !is_url(target)) {
+            Some(target) => Err(format!(
+                "Sandboxed actions can only open http, https and mailto links, not '{}'",
+                target
+            )
+            .into()),
+            None => Ok(()),
+        }
+    }
+
+    /// 记录中保存的结果文本
+    pub fn summary(&self) -> String {
+        self.popup
+            .as_ref()
+            .map(|popup| popup.content.clone())
+            .or_else(|| self.paste.clone())
+            .or_else(|| self.clipboard.clone())
+            .unwrap_or_default()
+    }
+}
+
+/// 判断是否为允许打开的链接
+pub fn is_url(target: &str) -> bool {
+    ["http://", "https://", "mailto:"]
+        .iter()
+        .any(|scheme| target.starts_with(scheme))
+}
@@ -0,0 +1,258 @@
+use super::process::Execution;
+use super::runner::{is_deno, Data};
+use super::runtime::Runtime;
+use crate::error::AppError;
+use crate::IDLE_WORKERS;
+use serde::Deserialize;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashSet;
+use std::ffi::OsStr;
+use std::hash::{Hash, Hasher};
+use std::process::Stdio;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::process::{Child, ChildStdin, ChildStdout, Command};
+
+/// JavaScript 工作进程源码，兼容 Node.js 和 Deno
+const JAVASCRIPT_WORKER: &str = include_str!("worker.js");
+
+/// Python 工作进程源码
+const PYTHON_WORKER: &str = include_str!("worker.py");
+
+/// 每种运行环境最多保留的空闲工作进程数
+const MAX_IDLE_WORKERS: usize = 2;
+
+/// 工作进程内存占用上限（字节），超出后重启
+const MAX_WORKER_MEMORY: u64 = 512 * 1024 * 1024;
+
+/// 支持常驻工作进程的脚本语言
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Lang {
+    JavaScript,
+    Python,
+}
+
+impl Lang {
+    /// 运行环境对应的工作进程语言，不支持时返回 `None`
+    pub fn of(runtime: &Runtime) -> Option<Self> {
+        match runtime.lang.as_str() {
+            "javascript" => Some(Lang::JavaScript),
+            "python" => Some(Lang::Python),
+            _ => None,
+        }
+    }
+
+    /// 语言名称
+    pub fn name(self) -> &'static str {
+        match self {
+            Lang::JavaScript => "JavaScript",
+            Lang::Python => "Python",
+        }
+    }
+}
+
+/// 工作进程发送的消息
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum Message {
+    /// 脚本的一行输出
+    Output { stream: String, line: String },
+    /// 脚本返回值
+    Result {
+        result: String,
+        memory: Option<u64>,
+        #[serde(default)]
+        cached: bool,
+    },
+    /// 脚本执行失败
+    Error {
+        message: String,
+        memory: Option<u64>,
+        #[serde(default)]
+        cached: bool,
+    },
+}
+
+/// 工作进程对一次请求的答复
+struct Reply {
+    result: Result<String, String>,
+    memory: Option<u64>,
+    /// 脚本是否已在工作进程中编译缓存，编译失败时为 `false`
+    cached: bool,
+}
+
+/// 常驻的脚本工作进程
+///
+/// 通过标准输入输出上的 JSON 行协议通信，一次只处理一个请求
+pub struct Worker {
+    /// 所属运行环境，作为进程池中的分组键
+    runtime: String,
+    lang: Lang,
+    child: Child,
+    stdin: ChildStdin,
+    stdout: BufReader<ChildStdout>,
+    /// 已在工作进程中编译缓存的脚本
+    scripts: HashSet<String>,
+}
+
+impl Worker {
+    /// 从进程池中取出空闲的工作进程，没有可用进程时启动新的进程
+    ///
+    /// `path` 为启动新进程时使用的 PATH 环境变量
+    pub fn checkout(lang: Lang, program: &str, path: &OsStr) -> std::io::Result<Self> {
+        let runtime = format!("{:?}:{}", lang, program);
+        if let Ok(mut idle) = IDLE_WORKERS.lock() {
+            if let Some(workers) = idle.get_mut(&runtime) {
+                while let Some(mut worker) = workers.pop() {
+                    // 丢弃已经退出的进程
+                    if matches!(worker.child.try_wait(), Ok(None)) {
+                        return Ok(worker);
+                    }
+                }
+            }
+        }
+        Self::spawn(runtime, lang, program, path)
+    }
+
+    /// 启动新的工作进程
+    fn spawn(runtime: String, lang: Lang, program: &str, path: &OsStr) -> std::io::Result<Self> {
+        let mut command = Command::new(program);
+        match lang {
+            Lang::JavaScript if is_deno(program) => command.arg("eval").arg(JAVASCRIPT_WORKER),
+            Lang::JavaScript => command.arg("-e").arg(JAVASCRIPT_WORKER),
+            Lang::Python => command
+                .arg("-c")
+                .arg(PYTHON_WORKER)
+                .env("PYTHONIOENCODING", "utf-8"),
+        };
+        command
+            .env("PATH", path)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::inherit())
+            .kill_on_drop(true);
+        #[cfg(unix)]
+        command.process_group(0);
+        #[cfg(target_os = "windows")]
+        command.creation_flags(0x08000000); // CREATE_NO_WINDOW
+
+        let mut child = command.spawn()?;
+        let stdin = child.stdin.take().ok_or(std::io::ErrorKind::BrokenPipe)?;
+        let stdout = child.stdout.take().ok_or(std::io::ErrorKind::BrokenPipe)?;
+        Ok(Self {
+            runtime,
+            lang,
+            child,
+            stdin,
+            stdout: BufReader::new(stdout),
+            scripts: HashSet::new(),
+        })
+    }
+
+    /// 在工作进程中执行脚本
+    ///
+    /// 执行成功或脚本报错后，工作进程回到进程池；超时、取消、崩溃或内存超限时终止
+    pub async fn run(
+        mut self,
+        code: &str,
+        data: &Data,
+        execution: &Execution,
+    ) -> Result<String, AppError> {
+        let mut hasher = DefaultHasher::new();
+        code.hash(&mut hasher);
+        let key = format!("{:016x}", hasher.finish());
+
+        // 已缓存的脚本不再重复发送源码
+        let cached = self.scripts.contains(&key);
+        let mut request = serde_json::to_vec(&serde_json::json!({
+            "id": execution.id,
+            "key": key,
+            "code": (!cached).then_some(code),
+            "data": data,
+        }))?;
+        request.push(b'\n');
+
+        let pid = self.child.id();
+        let Reply {
+            result,
+            memory,
+            cached,
+        } = execution
+            .supervise(pid, self.exchange(&request, execution))
+            .await?;
+        // 只记录工作进程确认已缓存的脚本，否则下次仍发送源码
+        if cached {
+            self.scripts.insert(key);
+        } else {
+            self.scripts.remove(&key);
+        }
+
+        let lang = self.lang;
+        if memory.is_some_and(|memory| memory > MAX_WORKER_MEMORY) {
+            eprintln!(
+                "[INFO] Restarting {} worker due to memory growth",
+                self.runtime
+            );
+        } else {
+            self.checkin();
+        }
+        result.map_err(|message| format!("{} execution failed: {}", lang.name(), message).into())
+    }
+
+    /// 发送请求并读取消息，直到收到执行结果
+    async fn exchange(&mut self, request: &[u8], execution: &Execution) -> Result<Reply, AppError> {
+        self.stdin.write_all(request).await?;
+        self.stdin.flush().await?;
+
+        let mut line = String::new();
+        loop {
+            line.clear();
+            if self.stdout.read_line(&mut line).await? == 0 {
+                return Err(format!("{} worker exited unexpectedly", self.lang.name()).into());
+            }
+            match serde_json::from_str::<Message>(&line) {
+                Ok(Message::Output { stream, line }) => {
+                    let stream = if stream == "stderr" {
+                        "stderr"
+                    } else {
+                        "stdout"
+                    };
+                    execution.emit(stream, &line);
+                }
+                Ok(Message::Result {
+                    result,
+                    memory,
+                    cached,
+                }) => {
+                    return Ok(Reply {
+                        result: Ok(result),
+                        memory,
+                        cached,
+                    })
+                }
+                Ok(Message::Error {
+                    message,
+                    memory,
+                    cached,
+                }) => {
+                    return Ok(Reply {
+                        result: Err(message),
+                        memory,
+                        cached,
+                    })
+                }
+                // 脚本绕过重定向直接写入标准输出的内容
+                Err(_) => execution.emit("stdout", &line),
+            }
+        }
+    }
+
+    /// 放回进程池，超出空闲数量上限时终止
+    fn checkin(self) {
+        if let Ok(mut idle) = IDLE_WORKERS.lock() {
+            let workers = idle.entry(self.runtime.clone()).or_default();
+            if workers.len() < MAX_IDLE_WORKERS {
+                workers.push(self);
+            }
+        }
+    }
+}
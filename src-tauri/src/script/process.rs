@@ -0,0 +1,442 @@
+use super::host::{Host, HOST_REQUEST_MARK};
+use super::sandbox::Sandbox;
+use crate::error::{AppError, ErrorKind};
+use crate::RUNNING_EXECUTIONS;
+use serde::Serialize;
+use std::ffi::OsStr;
+use std::future::Future;
+use std::path::Path;
+use std::process::{Output, Stdio};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tauri::Emitter;
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncWriteExt, BufReader};
+use tokio::process::{Child, Command};
+use tokio::sync::{mpsc, Notify};
+
+/// 脚本默认超时时间（秒）
+pub const DEFAULT_TIMEOUT_SECS: u64 = 30;
+
+/// 执行记录中每个输出流保留的长度上限（字节）
+const MAX_TRACE_OUTPUT: usize = 16 * 1024;
+
+/// 一次脚本执行
+///
+/// 创建时登记到全局执行表中以便取消，离开作用域时自动移除
+pub struct Execution {
+    /// 执行标识
+    pub id: String,
+    /// 超时时间
+    pub timeout: Duration,
+    /// 沙箱级别
+    pub sandbox: Sandbox,
+    /// 取消信号
+    cancel: Arc<Notify>,
+    /// 用于发送实时输出事件的应用句柄
+    app: Option<tauri::AppHandle>,
+    /// 是否登记在全局执行表中
+    registered: bool,
+    /// 需要从输出和错误信息中隐藏的密钥值
+    concealed: Arc<Mutex<Vec<String>>>,
+    /// 执行过程的记录
+    trace: Arc<Mutex<Trace>>,
+    /// 通过宿主 API 嵌套执行的层数
+    depth: usize,
+}
+
+/// 执行过程的记录，用于写入审计日志
+#[derive(Debug, Clone, Default)]
+pub struct Trace {
+    /// 运行环境名称
+    pub runtime: Option<String>,
+    /// 可执行文件路径
+    pub program: Option<String>,
+    /// 运行环境版本
+    pub version: Option<String>,
+    /// 最后一个子进程的退出码
+    pub exit_code: Option<i32>,
+    /// 标准输出，超出上限的部分被截断
+    pub stdout: String,
+    /// 标准错误，超出上限的部分被截断
+    pub stderr: String,
+    /// 输出是否被截断，截断后的输出不再记录
+    pub truncated: bool,
+}
+
+/// 实时输出事件
+#[derive(Debug, Clone, Serialize)]
+pub struct OutputLine {
+    /// 执行标识
+    pub id: String,
+    /// 输出流名称：stdout 或 stderr
+    pub stream: &'static str,
+    /// 输出的一行内容（不含换行符）
+    pub line: String,
+}
+
+impl Execution {
+    /// 创建执行并登记到全局执行表中
+    pub fn new(timeout_secs: Option<u64>) -> Result<Self, AppError> {
+        let execution = Self {
+            id: uuid::Uuid::new_v4().to_string(),
+            timeout: Duration::from_secs(
+                timeout_secs
+                    .filter(|secs| *secs > 0)
+                    .unwrap_or(DEFAULT_TIMEOUT_SECS),
+            ),
+            sandbox: Sandbox::None,
+            cancel: Arc::new(Notify::new()),
+            app: None,
+            registered: true,
+            concealed: Arc::default(),
+            trace: Arc::default(),
+            depth: 0,
+        };
+        RUNNING_EXECUTIONS
+            .lock()?
+            .insert(execution.id.clone(), execution.cancel.clone());
+        Ok(execution)
+    }
+
+    /// 逐行发送 `execution-output` 事件
+    pub fn stream_to(mut self, app: &tauri::AppHandle) -> Self {
+        self.app = Some(app.clone());
+        self
+    }
+
+    /// 创建使用相同标识、取消信号和输出目标的阶段性执行
+    ///
+    /// 用于安装依赖等耗时较长的准备工作，超时时间单独计算且不受沙箱限制
+    pub fn stage(&self, timeout: Duration) -> Self {
+        Self {
+            id: self.id.clone(),
+            timeout,
+            sandbox: Sandbox::None,
+            cancel: self.cancel.clone(),
+            app: self.app.clone(),
+            registered: false,
+            concealed: self.concealed.clone(),
+            // 准备阶段的输出不计入执行记录
+            trace: Arc::default(),
+            depth: self.depth,
+        }
+    }
+
+    /// 标记为由另一个执行通过宿主 API 发起的执行
+    pub fn nested_in(mut self, parent: &Execution) -> Self {
+        self.depth = parent.depth + 1;
+        self
+    }
+
+    /// 通过宿主 API 嵌套执行的层数
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    /// 用于发送实时输出事件的应用句柄
+    pub fn app(&self) -> Option<&tauri::AppHandle> {
+        self.app.as_ref()
+    }
+
+    /// 使用外部的取消信号，由调度器在重新开始同一动作时取消本次执行
+    ///
+    /// 通过执行标识取消时同样触发该信号
+    pub fn cancelled_by(mut self, cancel: &Arc<Notify>) -> Self {
+        self.cancel = cancel.clone();
+        if let Ok(mut executions) = RUNNING_EXECUTIONS.lock() {
+            executions.insert(self.id.clone(), cancel.clone());
+        }
+        self
+    }
+
+    /// 使用另一个执行的取消信号，取消该执行时同时取消本次执行
+    pub fn cancelled_with(self, other: &Execution) -> Self {
+        let cancel = other.cancel.clone();
+        self.cancelled_by(&cancel)
+    }
+
+    /// 在指定级别的沙箱中运行
+    pub fn sandboxed(mut self, sandbox: Sandbox) -> Self {
+        self.sandbox = sandbox;
+        self
+    }
+
+    /// 创建命令并应用沙箱限制，`dir` 为本次执行的临时目录
+    pub fn command(&self, program: impl AsRef<OsStr>, dir: &Path) -> Result<Command, AppError> {
+        let mut command = Command::new(program);
+        self.sandbox.apply(&mut command, dir, self.timeout)?;
+        Ok(command)
+    }
+
+    /// 创建并启动命令，子进程放入独立的进程组以便整体终止
+    pub fn spawn(&self, command: &mut Command) -> std::io::Result<Child> {
+        command
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true);
+        #[cfg(unix)]
+        command.process_group(0);
+        command.spawn()
+    }
+
+    /// 写入标准输入并等待子进程结束
+    ///
+    /// 标准输出和标准错误按行读取，设置了应用句柄时实时发送到前端
+    pub async fn wait(&self, child: Child, input: Option<&[u8]>) -> Result<Output, AppError> {
+        self.wait_with_host(child, input, None).await
+    }
+
+    /// 写入标准输入并等待子进程结束，期间处理脚本发出的宿主 API 请求
+    ///
+    /// 提供宿主时，输入数据后追加换行并保持标准输入打开，请求从标准错误中以
+    /// [`HOST_REQUEST_MARK`] 开头的行读取，响应逐行写入标准输入
+    pub async fn wait_with_host(
+        &self,
+        mut child: Child,
+        input: Option<&[u8]>,
+        host: Option<&Host>,
+    ) -> Result<Output, AppError> {
+        let pid = child.id();
+        let stdin = child.stdin.take();
+        let stdout = child.stdout.take();
+        let stderr = child.stderr.take();
+        let (responses, mut pending) = mpsc::unbounded_channel::<String>();
+        let wait = async {
+            let write = async {
+                if let (Some(input), Some(mut stdin)) = (input, stdin) {
+                    stdin.write_all(input).await?;
+                    if host.is_some() {
+                        stdin.write_all(b"\n").await?;
+                        stdin.flush().await?;
+                        // 标准错误读取结束时通道关闭
+                        while let Some(response) = pending.recv().await {
+                            let line = format!("{}\n", response);
+                            // 脚本已退出时写入失败，不再发送响应
+                            if stdin.write_all(line.as_bytes()).await.is_err()
+                                || stdin.flush().await.is_err()
+                            {
+                                break;
+                            }
+                        }
+                    }
+                    drop(stdin); // 关闭 stdin
+                }
+                Ok::<_, std::io::Error>(())
+            };
+            let host = host.map(|host| (host, responses));
+            let (written, stdout, stderr) = tokio::join!(
+                write,
+                self.read_lines(stdout, "stdout", None),
+                self.read_lines(stderr, "stderr", host)
+            );
+            written?;
+            let status = child.wait().await?;
+            if let Ok(mut trace) = self.trace.lock() {
+                trace.exit_code = status.code();
+            }
+            Ok::<_, AppError>(Output {
+                status,
+                stdout: stdout?,
+                stderr: stderr?,
+            })
+        };
+
+        self.supervise(pid, wait).await
+    }
+
+    /// 等待任务完成，超时或被取消时终止整个进程树，并返回对应类型的错误
+    pub async fn supervise<T>(
+        &self,
+        pid: Option<u32>,
+        task: impl Future<Output = Result<T, AppError>>,
+    ) -> Result<T, AppError> {
+        tokio::select! {
+            result = task => result,
+            _ = tokio::time::sleep(self.timeout) => {
+                kill_tree(pid);
+                Err(AppError::with_kind(
+                    ErrorKind::Timeout,
+                    format!("Execution timed out after {}s", self.timeout.as_secs()),
+                ))
+            }
+            _ = self.cancel.notified() => {
+                kill_tree(pid);
+                Err(AppError::with_kind(ErrorKind::Cancelled, "Execution cancelled"))
+            }
+        }
+    }
+
+    /// 登记需要隐藏的密钥值
+    pub fn conceal(&self, value: &str) {
+        if value.is_empty() {
+            return;
+        }
+        if let Ok(mut concealed) = self.concealed.lock() {
+            concealed.push(value.to_string());
+        }
+    }
+
+    /// 将文本中登记过的密钥值替换为占位符
+    pub fn redact(&self, text: &str) -> String {
+        let mut text = text.to_string();
+        if let Ok(concealed) = self.concealed.lock() {
+            for value in concealed.iter() {
+                text = text.replace(value.as_str(), "******");
+            }
+        }
+        text
+    }
+
+    /// 记录实际使用的运行环境
+    pub fn set_runtime(&self, runtime: Option<&str>, program: &str, version: Option<String>) {
+        if let Ok(mut trace) = self.trace.lock() {
+            trace.runtime = runtime.map(str::to_string);
+            trace.program = Some(program.to_string());
+            trace.version = version;
+        }
+    }
+
+    /// 获取执行过程的记录
+    pub fn trace(&self) -> Trace {
+        self.trace
+            .lock()
+            .map(|trace| trace.clone())
+            .unwrap_or_default()
+    }
+
+    /// 记录一行输出并在设置了应用句柄时实时发送
+    pub fn emit(&self, stream: &'static str, line: &str) {
+        let line = self.redact(line.trim_end_matches(['\r', '\n']));
+        if let Ok(mut trace) = self.trace.lock() {
+            let Trace {
+                stdout,
+                stderr,
+                truncated,
+                ..
+            } = &mut *trace;
+            let output = if stream == "stderr" { stderr } else { stdout };
+            // 截断后不再记录，避免记录中出现不连续的输出
+            if !*truncated {
+                let remaining = MAX_TRACE_OUTPUT.saturating_sub(output.len() + 1);
+                if line.len() <= remaining {
+                    output.push_str(&line);
+                    output.push('\n');
+                } else {
+                    // 保留超出上限的行中能容纳的部分
+                    let end = (0..=remaining)
+                        .rev()
+                        .find(|end| line.is_char_boundary(*end))
+                        .unwrap_or(0);
+                    if end > 0 {
+                        output.push_str(&line[..end]);
+                        output.push('\n');
+                    }
+                    *truncated = true;
+                }
+            }
+        }
+        if let Some(app) = &self.app {
+            let payload = OutputLine {
+                id: self.id.clone(),
+                stream,
+                line,
+            };
+            if let Err(e) = app.emit("execution-output", payload) {
+                eprintln!("[ERROR] Failed to emit output event: {}", e);
+            }
+        }
+    }
+
+    /// 按行读取输出流，返回完整的输出内容
+    ///
+    /// 提供宿主时处理宿主 API 请求并发送响应，请求行不计入输出
+    async fn read_lines(
+        &self,
+        reader: Option<impl AsyncRead + Unpin>,
+        stream: &'static str,
+        host: Option<(&Host, mpsc::UnboundedSender<String>)>,
+    ) -> std::io::Result<Vec<u8>> {
+        let mut output = Vec::new();
+        let Some(reader) = reader else {
+            return Ok(output);
+        };
+        let mut reader = BufReader::new(reader);
+        let mut line = Vec::new();
+        loop {
+            line.clear();
+            if reader.read_until(b'\n', &mut line).await? == 0 {
+                break;
+            }
+            if let Some((host, responses)) = &host {
+                if let Some(request) = line.strip_prefix(HOST_REQUEST_MARK.as_bytes()) {
+                    let response = host
+                        .handle(String::from_utf8_lossy(request).trim_end(), self)
+                        .await;
+                    let _ = responses.send(response);
+                    continue;
+                }
+            }
+            output.extend_from_slice(&line);
+            self.emit(stream, &String::from_utf8_lossy(&line));
+        }
+        Ok(output)
+    }
+}
+
+impl Drop for Execution {
+    fn drop(&mut self) {
+        if !self.registered {
+            return;
+        }
+        if let Ok(mut executions) = RUNNING_EXECUTIONS.lock() {
+            executions.remove(&self.id);
+        }
+    }
+}
+
+/// 取消正在运行的执行
+pub fn cancel(id: &str) -> Result<bool, AppError> {
+    let executions = RUNNING_EXECUTIONS.lock()?;
+    match executions.get(id) {
+        Some(cancel) => {
+            signal_cancel(cancel);
+            Ok(true)
+        }
+        None => Ok(false),
+    }
+}
+
+/// 触发取消信号，唤醒共享该信号的所有执行
+pub fn signal_cancel(cancel: &Notify) {
+    // 嵌套执行与父执行共享同一信号，需要唤醒全部等待者
+    cancel.notify_waiters();
+    // notify_one 会保留许可，即使子进程尚未启动也能生效
+    cancel.notify_one();
+}
+
+/// 终止整个进程树
+#[cfg(unix)]
+fn kill_tree(pid: Option<u32>) {
+    if let Some(pid) = pid {
+        // 子进程是进程组的组长，进程组标识等于其进程标识
+        unsafe {
+            libc::killpg(pid as libc::pid_t, libc::SIGKILL);
+        }
+    }
+}
+
+/// 终止整个进程树
+#[cfg(target_os = "windows")]
+fn kill_tree(pid: Option<u32>) {
+    use std::os::windows::process::CommandExt;
+
+    const CREATE_NO_WINDOW: u32 = 0x08000000;
+
+    if let Some(pid) = pid {
+        let _ = std::process::Command::new("taskkill")
+            .args(["/T", "/F", "/PID", &pid.to_string()])
+            .creation_flags(CREATE_NO_WINDOW)
+            .output();
+    }
+}
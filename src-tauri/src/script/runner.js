@@ -0,0 +1,55 @@
+// TextGO JavaScript 脚本运行器，兼容 Node.js 和 Deno
+//
+// 用法: node runner.js <脚本路径> <结果路径>
+// 从标准输入的第一行读取 JSON 数据，调用脚本中的 process(data) 函数，
+// 并将返回值写入结果文件，脚本中的 console.log 输出不会影响返回值
+// 同一目录下的 textgo.js 提供宿主 API，其余的标准输入行为宿主 API 的响应
+const isDeno = typeof Deno !== 'undefined';
+// 用户脚本定义的 process 函数会覆盖 Node.js 的全局 process 对象，提前保存
+const host = globalThis.process;
+
+// 运行器可能被当作 CommonJS 或 ES 模块加载，统一使用动态导入
+async function readText(path) {
+  if (isDeno) {
+    return Deno.readTextFileSync(path);
+  }
+  const fs = await import('node:fs');
+  return fs.readFileSync(path, 'utf8');
+}
+
+async function writeText(path, text) {
+  if (isDeno) {
+    return Deno.writeTextFileSync(path, text);
+  }
+  const fs = await import('node:fs');
+  return fs.writeFileSync(path, text, 'utf8');
+}
+
+async function main() {
+  const [scriptPath, resultPath] = isDeno ? Deno.args : host.argv.slice(2);
+  (0, eval)(await readText(scriptPath.replace(/[^\\/]*$/, 'textgo.js')));
+  const data = JSON.parse(await globalThis.textgo._readLine());
+
+  // 间接调用 eval 的代码无法访问模块作用域中的 require，提供按脚本路径和 NODE_PATH 解析的版本
+  if (!isDeno && typeof globalThis.require === 'undefined') {
+    const { createRequire } = await import('node:module');
+    globalThis.require = createRequire(scriptPath);
+  }
+
+  // 间接调用 eval，使脚本中的函数声明成为全局函数
+  (0, eval)(await readText(scriptPath));
+
+  const process = globalThis.process;
+  if (process === host || typeof process !== 'function') {
+    throw new ReferenceError('process(data) function is not defined');
+  }
+  const result = await process(data);
+
+  await writeText(resultPath, typeof result === 'string' ? result : JSON.stringify(result));
+  globalThis.textgo._close();
+}
+
+main().catch((error) => {
+  console.error(error);
+  isDeno ? Deno.exit(1) : host.exit(1);
+});
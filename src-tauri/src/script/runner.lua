@@ -0,0 +1,30 @@
+-- TextGO Lua 脚本运行器
+--
+-- 用法: lua runner.lua <脚本路径> <结果路径>
+-- 从标准输入读取选中的文本，其余数据通过 TEXTGO_* 环境变量传递，
+-- 调用脚本中的 process(data) 函数，并将返回值写入结果文件
+local script_path, result_path = arg[1], arg[2]
+
+local data = {
+  selection = io.read("*a"),
+  clipboard = os.getenv("TEXTGO_CLIPBOARD") or "",
+  datetime = os.getenv("TEXTGO_DATETIME") or "",
+  context = {
+    key = os.getenv("TEXTGO_KEY") or "",
+    case = os.getenv("TEXTGO_CASE") or "",
+    action = os.getenv("TEXTGO_ACTION") or "",
+  },
+}
+
+-- 在独立的环境中加载脚本，未定义的全局变量回退到标准库
+local scope = setmetatable({}, { __index = _G })
+local chunk = assert(loadfile(script_path, "t", scope))
+chunk()
+if type(scope.process) ~= "function" then
+  error("process(data) function is not defined")
+end
+
+local result = scope.process(data)
+local file = assert(io.open(result_path, "wb"))
+file:write(tostring(result == nil and "" or result))
+file:close()
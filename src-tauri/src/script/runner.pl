@@ -0,0 +1,32 @@
+# TextGO Perl 脚本运行器
+#
+# 用法: perl runner.pl <脚本路径> <结果路径>
+# 从标准输入读取 JSON 数据，调用脚本中的 process($data) 函数，
+# 并将返回值写入结果文件，脚本中的 print 输出不会影响返回值
+use strict;
+use warnings;
+use JSON::PP;
+
+my ($script_path, $result_path) = @ARGV;
+my $json = JSON::PP->new->utf8->canonical;
+my $data = $json->decode(do { local $/; binmode STDIN; <STDIN> });
+
+# 在独立的包中加载脚本，避免与运行器的变量冲突
+my $source = do {
+    open my $file, '<:raw', $script_path or die "Failed to read script: $!\n";
+    local $/;
+    <$file>;
+};
+eval "package TextGO::Script;\n#line 1 \"$script_path\"\n$source\n;1" or die $@;
+
+my $process = TextGO::Script->can('process') or die "process(\$data) function is not defined\n";
+my $result = $process->($data);
+
+open my $file, '>:raw', $result_path or die "Failed to write result: $!\n";
+if (ref $result) {
+    print {$file} $json->encode($result);
+} else {
+    utf8::encode($result) if utf8::is_utf8($result);
+    print {$file} $result // '';
+}
+close $file;
@@ -0,0 +1,20 @@
+# TextGO PowerShell 脚本运行器
+#
+# 用法: pwsh -File runner.ps1 <脚本路径> <结果路径>
+# 从标准输入读取 JSON 数据，调用脚本中的 process 函数，
+# 并将返回值写入结果文件，脚本中的 Write-Host 输出不会影响返回值
+param([string]$ScriptPath, [string]$ResultPath)
+$ErrorActionPreference = 'Stop'
+
+$Data = [Console]::In.ReadToEnd() | ConvertFrom-Json
+
+. $ScriptPath
+if (-not (Get-Command process -CommandType Function -ErrorAction SilentlyContinue)) {
+    throw 'process function is not defined'
+}
+
+$Result = process $Data
+if ($Result -isnot [string]) {
+    $Result = $Result | ConvertTo-Json -Compress -Depth 10
+}
+[System.IO.File]::WriteAllText($ResultPath, [string]$Result, [System.Text.UTF8Encoding]::new($false))
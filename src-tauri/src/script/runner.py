@@ -0,0 +1,32 @@
+# TextGO Python 脚本运行器
+#
+# 用法: python runner.py <脚本路径> <结果路径>
+# 从标准输入的第一行读取 JSON 数据，调用脚本中的 process(data) 函数，
+# 并将返回值写入结果文件，脚本中的 print 输出不会影响返回值
+# 同一目录下的 textgo 模块提供宿主 API，其余的标准输入行为宿主 API 的响应
+import json
+import sys
+
+import textgo
+
+
+def main():
+    script_path, result_path = sys.argv[1], sys.argv[2]
+    data = json.loads(sys.stdin.buffer.readline().decode("utf-8"))
+
+    with open(script_path, encoding="utf-8") as file:
+        source = file.read()
+    scope = {"__name__": "__textgo__", "__file__": script_path, "textgo": textgo}
+    exec(compile(source, script_path, "exec"), scope)
+
+    process = scope.get("process")
+    if not callable(process):
+        raise NameError("process(data) function is not defined")
+    result = process(data)
+
+    with open(result_path, "w", encoding="utf-8") as file:
+        file.write(result if isinstance(result, str) else json.dumps(result, ensure_ascii=False))
+
+
+if __name__ == "__main__":
+    main()
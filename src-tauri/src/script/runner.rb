@@ -0,0 +1,18 @@
+# TextGO Ruby 脚本运行器
+#
+# 用法: ruby runner.rb <脚本路径> <结果路径>
+# 从标准输入读取 JSON 数据，调用脚本中的 process(data) 方法，
+# 并将返回值写入结果文件，脚本中的 puts 输出不会影响返回值
+require 'json'
+
+script_path, result_path = ARGV
+data = JSON.parse($stdin.binmode.read.force_encoding('UTF-8'))
+
+# 在独立的模块中加载脚本，避免污染运行器的作用域
+scope = Module.new
+scope.module_eval(File.read(script_path, encoding: 'UTF-8'), script_path)
+scope.extend(scope)
+raise NameError, 'process(data) method is not defined' unless scope.respond_to?(:process)
+
+result = scope.process(data)
+File.write(result_path, result.is_a?(String) ? result : JSON.generate(result), encoding: 'UTF-8')
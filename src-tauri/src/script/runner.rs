@@ -0,0 +1,519 @@
+use super::deps::{prepare_env, supports_dependencies, DepsEnv};
+use super::detect::cached_runtimes;
+use super::embedded::{missing_global, needs_host, run_embedded};
+use super::host::{Host, HostPermission, JAVASCRIPT_HOST, PYTHON_HOST};
+use super::pool::{Lang, Worker};
+use super::process::Execution;
+use super::runtime::{InputFormat, Registry, Runtime};
+use super::sandbox::Sandbox;
+use super::secrets::action_env;
+use super::testing::TestCase;
+use super::wasm::{run_wasm, WasmOptions, WASM_LANG};
+use crate::engine::Concurrency;
+use crate::error::AppError;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::ffi::OsString;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+
+/// 通过环境变量传递的剪贴板文本长度上限（字节），超出时不传递
+const MAX_ENV_CLIPBOARD: usize = 32 * 1024;
+
+/// 通过标准输入传递给脚本的数据
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Data {
+    /// 选中的文本
+    pub selection: String,
+    /// 剪贴板文本
+    #[serde(default)]
+    pub clipboard: String,
+    /// 当前日期时间
+    #[serde(default)]
+    pub datetime: String,
+    /// 触发上下文
+    #[serde(default)]
+    pub context: Context,
+}
+
+/// 脚本的触发上下文
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Context {
+    /// 触发键位
+    #[serde(default)]
+    pub key: String,
+    /// 匹配到的文本类型
+    #[serde(default)]
+    pub case: String,
+    /// 执行的动作标识
+    #[serde(default)]
+    pub action: String,
+    /// 触发时前台应用的名称
+    #[serde(default)]
+    pub app: String,
+}
+
+impl Data {
+    /// 以文本方式传递数据时使用的环境变量
+    pub(super) fn env(&self) -> [(&'static str, &str); 6] {
+        let clipboard = if self.clipboard.len() <= MAX_ENV_CLIPBOARD {
+            self.clipboard.as_str()
+        } else {
+            ""
+        };
+        [
+            ("TEXTGO_CLIPBOARD", clipboard),
+            ("TEXTGO_DATETIME", self.datetime.as_str()),
+            ("TEXTGO_KEY", self.context.key.as_str()),
+            ("TEXTGO_CASE", self.context.case.as_str()),
+            ("TEXTGO_ACTION", self.context.action.as_str()),
+            ("TEXTGO_APP", self.context.app.as_str()),
+        ]
+    }
+}
+
+/// 脚本动作
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScriptAction {
+    /// 脚本标识
+    pub id: String,
+    /// 脚本语言
+    pub lang: String,
+    /// 脚本内容
+    pub script: String,
+    /// 静默执行
+    #[serde(default)]
+    pub quiet_mode: Option<bool>,
+    /// 返回值只取决于选中的文本，启用缓存时可以复用之前的结果
+    #[serde(default)]
+    pub pure: Option<bool>,
+    /// 超时时间（秒）
+    #[serde(default)]
+    pub timeout: Option<u64>,
+    /// 沙箱级别
+    #[serde(default)]
+    pub sandbox: Option<Sandbox>,
+    /// 正在运行时再次触发的处理方式
+    #[serde(default)]
+    pub concurrency: Option<Concurrency>,
+    /// 依赖的包，安装在脚本独立的环境中
+    #[serde(default)]
+    pub dependencies: Vec<String>,
+    /// 额外设置的环境变量
+    #[serde(default)]
+    pub env: BTreeMap<String, String>,
+    /// 使用的密钥名称，运行时从系统钥匙串读取并以同名环境变量传递
+    #[serde(default)]
+    pub secrets: Vec<String>,
+    /// 测试用例
+    #[serde(default)]
+    pub tests: Vec<TestCase>,
+    /// WebAssembly 模块的运行选项
+    #[serde(default)]
+    pub wasm: WasmOptions,
+    /// 授予脚本的宿主 API 权限
+    #[serde(default)]
+    pub permissions: Vec<HostPermission>,
+    /// 从脚本目录加载时的文件路径
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
+}
+
+/// 脚本运行选项
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScriptOptions {
+    /// Node.js 路径
+    #[serde(default)]
+    pub node_path: Option<String>,
+    /// Python 路径
+    #[serde(default)]
+    pub python_path: Option<String>,
+    /// 是否启用常驻进程池
+    #[serde(default)]
+    pub warm_pool: Option<bool>,
+    /// 用户定义的运行环境
+    #[serde(default)]
+    pub runtimes: Vec<Runtime>,
+    /// 下载 Python 包使用的索引地址
+    #[serde(default)]
+    pub pip_index_url: Option<String>,
+    /// 下载 Node.js 包使用的 npm 源
+    #[serde(default)]
+    pub npm_registry: Option<String>,
+    /// 应用数据目录，依赖环境和包缓存保存在其中，由后端设置
+    #[serde(skip)]
+    pub data_dir: Option<PathBuf>,
+}
+
+impl ScriptOptions {
+    /// 指定语言的自定义解释器路径
+    pub fn custom_path(&self, lang: &str) -> Option<&str> {
+        let path = match lang {
+            "javascript" => self.node_path.as_deref(),
+            "python" => self.python_path.as_deref(),
+            _ => None,
+        };
+        path.filter(|path| !path.is_empty())
+    }
+}
+
+/// 脚本运行的临时工作区
+///
+/// 包含运行器、宿主 API 模块、用户脚本和结果文件，离开作用域时自动删除
+pub struct Workspace {
+    dir: tempfile::TempDir,
+    runner: Option<PathBuf>,
+    script: PathBuf,
+    result: PathBuf,
+}
+
+impl Workspace {
+    /// 创建工作区并写入运行器和用户脚本
+    pub fn new(runtime: &Runtime, code: &str) -> Result<Self, AppError> {
+        let dir = tempfile::Builder::new().prefix("textgo-").tempdir()?;
+        let file = |name: &str| match runtime.extension.as_str() {
+            "" => dir.path().join(name),
+            ext => dir.path().join(format!("{}.{}", name, ext)),
+        };
+        let runner = match &runtime.runner {
+            Some(source) => {
+                let runner = file("runner");
+                std::fs::write(&runner, source)?;
+                // 内置运行器从同一目录加载宿主 API 模块
+                match Lang::of(runtime) {
+                    Some(Lang::Python) => std::fs::write(file("textgo"), PYTHON_HOST)?,
+                    Some(Lang::JavaScript) => std::fs::write(file("textgo"), JAVASCRIPT_HOST)?,
+                    None => {}
+                }
+                Some(runner)
+            }
+            None => None,
+        };
+        let script = file("script");
+        let result = dir.path().join("result");
+        std::fs::write(&script, code)?;
+
+        Ok(Self {
+            dir,
+            runner,
+            script,
+            result,
+        })
+    }
+
+    /// 工作区目录
+    pub fn dir(&self) -> &Path {
+        self.dir.path()
+    }
+
+    /// 展开运行环境的参数模板
+    pub fn args(&self, runtime: &Runtime, sandbox: Sandbox) -> Vec<OsString> {
+        let mut args = Vec::new();
+        for arg in &runtime.args {
+            match arg.as_str() {
+                "{runner}" => args.extend(self.runner.iter().map(OsString::from)),
+                "{script}" => args.push(self.script.clone().into()),
+                "{result}" if self.runner.is_some() => args.push(self.result.clone().into()),
+                "{result}" => {}
+                "{permissions}" => args.extend(
+                    sandbox
+                        .deno_flags(self.dir())
+                        .into_iter()
+                        .map(OsString::from),
+                ),
+                _ => args.push(arg.into()),
+            }
+        }
+        args
+    }
+
+    /// 读取脚本返回值，没有运行器时以标准输出作为返回值
+    pub fn result(&self, stdout: &[u8]) -> Result<String, AppError> {
+        if self.runner.is_none() {
+            let stdout = String::from_utf8_lossy(stdout);
+            return Ok(stdout.trim_end_matches(['\r', '\n']).to_string());
+        }
+        match std::fs::read(&self.result) {
+            Ok(bytes) => Ok(String::from_utf8_lossy(&bytes).into_owned()),
+            Err(e) if e.kind() == ErrorKind::NotFound => {
+                Err("Script exited without returning a result".into())
+            }
+            Err(e) => Err(e.into()),
+        }
+    }
+}
+
+/// 在指定的执行中运行脚本动作
+///
+/// 按注册表中的顺序尝试可执行该语言的运行环境，使用第一个可用的。
+/// 结果会写入缓存和历史记录，返回前隐藏其中的密钥值
+pub async fn run_script(
+    action: &ScriptAction,
+    data: &Data,
+    options: &ScriptOptions,
+    execution: &Execution,
+) -> Result<String, AppError> {
+    execute_script(action, data, options, execution)
+        .await
+        .map(|result| execution.redact(&result))
+}
+
+/// 运行脚本动作并返回原始结果
+async fn execute_script(
+    action: &ScriptAction,
+    data: &Data,
+    options: &ScriptOptions,
+    execution: &Execution,
+) -> Result<String, AppError> {
+    // WebAssembly 模块在进程内的 WASI 沙箱中运行，不使用外部运行环境
+    if action.lang == WASM_LANG {
+        let vars = action_env(&action.env, &action.secrets, execution).await?;
+        return run_wasm(&action.script, &action.wasm, data, &vars, execution).await;
+    }
+
+    let registry = Registry::new(&options.runtimes);
+    let runtimes = registry.candidates(&action.lang);
+    if runtimes.is_empty() {
+        return Err(format!("Unsupported script language: {}", action.lang).into());
+    }
+
+    // 如果提供了自定义路径，根据可执行文件名称确定运行环境
+    let custom = options.custom_path(&action.lang);
+    // 未指定解释器路径时，不依赖宿主 API 和环境变量的 JavaScript 脚本可以使用内置引擎执行
+    let embedded = action.lang == "javascript"
+        && custom.is_none()
+        && action.dependencies.is_empty()
+        && action.env.is_empty()
+        && action.secrets.is_empty()
+        && action.permissions.is_empty()
+        && !needs_host(&action.script);
+
+    let mut candidates = select_candidates(&registry, action, options);
+    // 声明了依赖时，在依赖环境中运行
+    let mut deps_env = None;
+    if !action.dependencies.is_empty() {
+        let (runtime, env) = prepare_dependencies(action, candidates, options, execution).await?;
+        candidates = vec![(runtime, env.program.clone())];
+        deps_env = Some(env);
+    }
+
+    // 只在启动进程前读取密钥，不在其他位置保存
+    let vars = action_env(&action.env, &action.secrets, execution).await?;
+
+    // 启用常驻进程池时，优先在工作进程中执行，工作进程不受沙箱限制且不加载依赖环境和环境变量
+    // 工作进程不提供 TextGO 宿主 API
+    if options.warm_pool == Some(true)
+        && !execution.sandbox.is_enabled()
+        && deps_env.is_none()
+        && vars.is_empty()
+        && action.permissions.is_empty()
+    {
+        for (runtime, program) in &candidates {
+            let Some(lang) = Lang::of(runtime) else {
+                continue;
+            };
+            let path = runtime.search_path();
+            if let Ok(worker) = Worker::checkout(lang, program, &path) {
+                execution.set_runtime(
+                    Some(&runtime.name),
+                    program,
+                    runtime_version(options, runtime),
+                );
+                return worker.run(&action.script, data, execution).await;
+            }
+        }
+    }
+
+    // 没有可用的工作进程时使用内置引擎，脚本用到内置引擎缺少的全局对象时改用外部运行环境
+    let mut embedded_error = None;
+    if embedded {
+        match run_embedded(&action.script, data, execution).await {
+            Err(e) if missing_global(&e.to_string()) => {
+                eprintln!(
+                    "[WARNING] Embedded JavaScript engine is missing a global, falling back to external runtime: {}",
+                    e
+                );
+                embedded_error = Some(e);
+            }
+            result => return result,
+        }
+    }
+
+    for (runtime, program) in candidates {
+        // 创建包含运行器和脚本的临时工作区，数据通过 stdin 传递
+        let workspace = Workspace::new(runtime, &action.script)?;
+        let input = match runtime.input {
+            InputFormat::Json => serde_json::to_vec(data)?,
+            InputFormat::Text => data.selection.clone().into_bytes(),
+        };
+        execution.set_runtime(
+            Some(&runtime.name),
+            &program,
+            runtime_version(options, runtime),
+        );
+        let mut command = execution.command(&program, workspace.dir())?;
+        command
+            .args(workspace.args(runtime, execution.sandbox))
+            .env("PATH", runtime.search_path())
+            .envs(&runtime.env);
+        if runtime.input == InputFormat::Text {
+            command.envs(data.env());
+        }
+        if let Some(env) = &deps_env {
+            command.envs(env.env.iter().map(|(key, value)| (key, value)));
+        }
+        command.envs(vars.iter().map(|(key, value)| (key, value)));
+
+        // 授予了权限且运行环境使用内置运行器时，脚本可以调用宿主 API
+        let host = match (execution.app(), Lang::of(runtime)) {
+            (Some(app), Some(_)) if runtime.runner.is_some() => Host::new(app, &action.permissions),
+            _ => None,
+        };
+
+        match execution.spawn(&mut command) {
+            Ok(child) => {
+                let output = execution
+                    .wait_with_host(child, Some(&input), host.as_ref())
+                    .await?;
+
+                if output.status.success() {
+                    return workspace.result(&output.stdout);
+                }
+                let stderr = String::from_utf8_lossy(&output.stderr);
+                // 如果是找不到命令的错误，继续尝试下一个命令
+                if custom.is_none()
+                    && (stderr.contains("No such file or directory")
+                        || stderr.contains("command not found"))
+                {
+                    continue;
+                }
+                return Err(format!(
+                    "{} execution failed: {}",
+                    runtime.name,
+                    execution.redact(&stderr)
+                )
+                .into());
+            }
+            Err(e) if custom.is_some() => {
+                return Err(format!("Failed to execute custom path '{}': {}", program, e).into());
+            }
+            // 沙箱初始化失败时不再尝试其他命令
+            Err(e) if execution.sandbox.is_enabled() && e.kind() != ErrorKind::NotFound => {
+                return Err(format!("Failed to start sandboxed process: {}", e).into());
+            }
+            Err(_) => continue, // 尝试下一个命令
+        }
+    }
+
+    // 没有外部运行环境时返回内置引擎的错误
+    if let Some(e) = embedded_error {
+        return Err(e);
+    }
+    let names: Vec<&str> = runtimes.iter().map(|r| r.name.as_str()).collect();
+    Err(format!(
+        "No runtime found for {} scripts. Please install {}.",
+        action.lang,
+        names.join(" or ")
+    )
+    .into())
+}
+
+/// 安装脚本声明的依赖
+///
+/// 使用与执行时相同的运行环境，依赖已安装时直接返回
+pub async fn install_dependencies(
+    action: &ScriptAction,
+    options: &ScriptOptions,
+    execution: &Execution,
+) -> Result<(), AppError> {
+    let registry = Registry::new(&options.runtimes);
+    let candidates = select_candidates(&registry, action, options);
+    prepare_dependencies(action, candidates, options, execution).await?;
+    Ok(())
+}
+
+/// 在第一个支持依赖环境的运行环境中准备依赖
+async fn prepare_dependencies<'a>(
+    action: &ScriptAction,
+    candidates: Vec<(&'a Runtime, String)>,
+    options: &ScriptOptions,
+    execution: &Execution,
+) -> Result<(&'a Runtime, DepsEnv), AppError> {
+    let (runtime, program) = candidates
+        .into_iter()
+        .find(|(runtime, program)| supports_dependencies(runtime, program))
+        .ok_or_else(|| {
+            format!(
+                "Dependencies are not supported for {} scripts. Use Python or Node.js.",
+                action.lang
+            )
+        })?;
+    let env = prepare_env(action, runtime, &program, options, execution).await?;
+    Ok((runtime, env))
+}
+
+/// 列出可执行脚本的运行环境和可执行文件，指定了自定义路径时只使用该路径
+fn select_candidates<'a>(
+    registry: &'a Registry,
+    action: &ScriptAction,
+    options: &ScriptOptions,
+) -> Vec<(&'a Runtime, String)> {
+    match options.custom_path(&action.lang) {
+        Some(program) => registry
+            .resolve_custom(&action.lang, program)
+            .map(|runtime| (runtime, program.to_string()))
+            .into_iter()
+            .collect(),
+        None => probe_candidates(&registry.candidates(&action.lang), options),
+    }
+}
+
+/// 按优先级列出要尝试的运行环境和可执行文件
+///
+/// 已检测过运行环境时直接使用检测到的路径并跳过不可用的，全部不可用时仍逐个尝试
+fn probe_candidates<'a>(
+    runtimes: &[&'a Runtime],
+    options: &ScriptOptions,
+) -> Vec<(&'a Runtime, String)> {
+    let all = || {
+        runtimes
+            .iter()
+            .flat_map(|r| r.executables.iter().map(move |e| (*r, e.clone())))
+            .collect()
+    };
+    let Some(detected) = cached_runtimes(options) else {
+        return all();
+    };
+    let mut candidates = Vec::new();
+    for runtime in runtimes {
+        match detected.iter().find(|status| status.id == runtime.id) {
+            Some(status) if status.available => {
+                candidates.extend(status.path.clone().map(|path| (*runtime, path)));
+            }
+            Some(_) => {}
+            None => candidates.extend(runtime.executables.iter().map(|e| (*runtime, e.clone()))),
+        }
+    }
+    if candidates.is_empty() {
+        all()
+    } else {
+        candidates
+    }
+}
+
+/// 检测结果中运行环境的版本，未检测过时返回 `None`
+fn runtime_version(options: &ScriptOptions, runtime: &Runtime) -> Option<String> {
+    cached_runtimes(options)?
+        .into_iter()
+        .find(|status| status.id == runtime.id)
+        .and_then(|status| status.version)
+}
+
+/// 判断运行环境是否为 Deno
+pub fn is_deno(program: &str) -> bool {
+    Path::new(program)
+        .file_stem()
+        .is_some_and(|stem| stem.eq_ignore_ascii_case("deno"))
+}
@@ -0,0 +1,18 @@
+# TextGO Bash 脚本运行器
+#
+# 用法: bash runner.sh <脚本路径> <结果路径>
+# 加载脚本并调用其中的 process 函数，选中的文本通过标准输入传递，
+# 其余数据通过 TEXTGO_* 环境变量传递，函数的标准输出即为返回值
+set -e
+
+script_path=$1
+result_path=$2
+
+# shellcheck source=/dev/null
+source "$script_path"
+if ! declare -F process > /dev/null; then
+  echo "process function is not defined" >&2
+  exit 1
+fi
+
+process > "$result_path"
@@ -0,0 +1,361 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+
+/// JavaScript 运行器，兼容 Node.js、Deno 和 Bun
+const JAVASCRIPT_RUNNER: &str = include_str!("runner.js");
+
+/// Python 运行器
+const PYTHON_RUNNER: &str = include_str!("runner.py");
+
+/// Ruby 运行器
+const RUBY_RUNNER: &str = include_str!("runner.rb");
+
+/// Perl 运行器
+const PERL_RUNNER: &str = include_str!("runner.pl");
+
+/// Lua 运行器
+const LUA_RUNNER: &str = include_str!("runner.lua");
+
+/// Bash 运行器
+const BASH_RUNNER: &str = include_str!("runner.sh");
+
+/// PowerShell 运行器
+const POWERSHELL_RUNNER: &str = include_str!("runner.ps1");
+
+/// 常见的程序安装路径
+#[cfg(not(target_os = "windows"))]
+const COMMON_PATHS: &[&str] = &[
+    "/usr/local/bin",
+    "/opt/homebrew/bin",
+    "/opt/local/bin",
+    "/usr/bin",
+    "/bin",
+    "~/.local/bin",
+    "~/.cargo/bin",
+];
+
+/// 常见的程序安装路径
+#[cfg(target_os = "windows")]
+const COMMON_PATHS: &[&str] = &[];
+
+/// 脚本数据的传递方式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum InputFormat {
+    /// 通过标准输入传递 JSON 数据
+    #[default]
+    Json,
+    /// 通过标准输入传递选中的文本，其余数据通过 `TEXTGO_*` 环境变量传递
+    Text,
+}
+
+/// 脚本运行环境
+///
+/// 内置和用户定义的运行环境使用相同的结构描述
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Runtime {
+    /// 运行环境标识
+    pub id: String,
+    /// 显示名称
+    pub name: String,
+    /// 可执行的脚本语言
+    pub lang: String,
+    /// 可执行文件名称，按顺序查找
+    pub executables: Vec<String>,
+    /// 追加到 PATH 中的查找路径，`~` 表示用户主目录
+    #[serde(default)]
+    pub search_paths: Vec<String>,
+    /// 查询版本的参数
+    #[serde(default)]
+    pub version_args: Vec<String>,
+    /// 调用参数模板，支持 `{runner}`、`{script}`、`{result}` 和 `{permissions}`
+    pub args: Vec<String>,
+    /// 运行器源码，为空时直接运行脚本并以标准输出作为返回值
+    #[serde(default)]
+    pub runner: Option<String>,
+    /// 脚本文件扩展名
+    #[serde(default)]
+    pub extension: String,
+    /// 数据传递方式
+    #[serde(default)]
+    pub input: InputFormat,
+    /// 额外的环境变量
+    #[serde(default)]
+    pub env: HashMap<String, String>,
+}
+
+impl Runtime {
+    /// 判断程序路径是否指向该运行环境的可执行文件
+    pub fn matches(&self, program: &str) -> bool {
+        Path::new(program).file_stem().is_some_and(|stem| {
+            self.executables
+                .iter()
+                .any(|name| stem.eq_ignore_ascii_case(name.as_str()))
+        })
+    }
+
+    /// 构建查找可执行文件的 PATH 环境变量，在系统 PATH 后追加查找路径
+    pub fn search_path(&self) -> OsString {
+        search_path(&self.search_paths)
+    }
+
+    /// 设置运行器源码和脚本文件扩展名
+    fn with_runner(mut self, runner: &str, extension: &str) -> Self {
+        self.runner = Some(runner.to_string());
+        self.extension = extension.to_string();
+        self
+    }
+}
+
+/// 运行环境注册表
+pub struct Registry {
+    runtimes: Vec<Runtime>,
+}
+
+impl Registry {
+    /// 合并内置和用户定义的运行环境，标识相同时用户定义的优先
+    pub fn new(custom: &[Runtime]) -> Self {
+        let mut runtimes = builtin_runtimes();
+        for runtime in custom {
+            match runtimes.iter_mut().find(|r| r.id == runtime.id) {
+                Some(existing) => *existing = runtime.clone(),
+                None => runtimes.push(runtime.clone()),
+            }
+        }
+        Self { runtimes }
+    }
+
+    /// 全部运行环境
+    pub fn runtimes(&self) -> &[Runtime] {
+        &self.runtimes
+    }
+
+    /// 获取可执行指定语言的运行环境，按优先级排序
+    pub fn candidates(&self, lang: &str) -> Vec<&Runtime> {
+        self.runtimes.iter().filter(|r| r.lang == lang).collect()
+    }
+
+    /// 根据可执行文件名称确定自定义路径对应的运行环境，无法确定时使用该语言的第一个
+    pub fn resolve_custom(&self, lang: &str, program: &str) -> Option<&Runtime> {
+        let candidates = self.candidates(lang);
+        candidates
+            .iter()
+            .find(|r| r.matches(program))
+            .or(candidates.first())
+            .copied()
+    }
+}
+
+/// 内置的运行环境
+fn builtin_runtimes() -> Vec<Runtime> {
+    let javascript_paths = with_common(&[
+        #[cfg(target_os = "windows")]
+        "C:\\Program Files\\nodejs",
+        #[cfg(target_os = "windows")]
+        "C:\\Program Files (x86)\\nodejs",
+        #[cfg(target_os = "windows")]
+        "~\\AppData\\Local\\Programs\\nodejs",
+        #[cfg(target_os = "windows")]
+        "~\\AppData\\Roaming\\npm",
+        #[cfg(target_os = "windows")]
+        "~\\.deno\\bin",
+        #[cfg(target_os = "windows")]
+        "~\\.bun\\bin",
+        #[cfg(not(target_os = "windows"))]
+        "~/.deno/bin",
+        #[cfg(not(target_os = "windows"))]
+        "~/.bun/bin",
+    ]);
+    let standard_args = ["{runner}", "{script}", "{result}"];
+
+    vec![
+        Runtime {
+            search_paths: javascript_paths.clone(),
+            ..runtime("node", "Node.js", "javascript", &["node"], &standard_args)
+        }
+        .with_runner(JAVASCRIPT_RUNNER, "js"),
+        Runtime {
+            search_paths: javascript_paths.clone(),
+            ..runtime(
+                "deno",
+                "Deno",
+                "javascript",
+                &["deno"],
+                &["run", "{permissions}", "{runner}", "{script}", "{result}"],
+            )
+        }
+        .with_runner(JAVASCRIPT_RUNNER, "js"),
+        Runtime {
+            search_paths: javascript_paths,
+            ..runtime("bun", "Bun", "javascript", &["bun"], &standard_args)
+        }
+        .with_runner(JAVASCRIPT_RUNNER, "js"),
+        Runtime {
+            search_paths: python_paths(),
+            env: HashMap::from([("PYTHONIOENCODING".to_string(), "utf-8".to_string())]),
+            ..runtime(
+                "python",
+                "Python",
+                "python",
+                &["python3", "python"],
+                &standard_args,
+            )
+        }
+        .with_runner(PYTHON_RUNNER, "py"),
+        Runtime {
+            search_paths: with_common(&[
+                #[cfg(target_os = "windows")]
+                "C:\\Ruby34-x64\\bin",
+                #[cfg(target_os = "windows")]
+                "C:\\Ruby33-x64\\bin",
+                #[cfg(not(target_os = "windows"))]
+                "~/.rbenv/shims",
+            ]),
+            ..runtime("ruby", "Ruby", "ruby", &["ruby"], &standard_args)
+        }
+        .with_runner(RUBY_RUNNER, "rb"),
+        Runtime {
+            search_paths: with_common(&[
+                #[cfg(target_os = "windows")]
+                "C:\\Strawberry\\perl\\bin",
+            ]),
+            version_args: vec!["-e".to_string(), "print $^V".to_string()],
+            ..runtime("perl", "Perl", "perl", &["perl"], &standard_args)
+        }
+        .with_runner(PERL_RUNNER, "pl"),
+        Runtime {
+            search_paths: with_common(&[]),
+            version_args: vec!["-v".to_string()],
+            input: InputFormat::Text,
+            ..runtime(
+                "lua",
+                "Lua",
+                "lua",
+                &["lua", "lua5.4", "lua5.3"],
+                &standard_args,
+            )
+        }
+        .with_runner(LUA_RUNNER, "lua"),
+        Runtime {
+            search_paths: with_common(&[
+                #[cfg(target_os = "windows")]
+                "C:\\Program Files\\Git\\bin",
+            ]),
+            input: InputFormat::Text,
+            ..runtime("bash", "Bash", "shell", &["bash"], &standard_args)
+        }
+        .with_runner(BASH_RUNNER, "sh"),
+        Runtime {
+            search_paths: with_common(&[
+                #[cfg(target_os = "windows")]
+                "C:\\Program Files\\PowerShell\\7",
+            ]),
+            ..runtime(
+                "pwsh",
+                "PowerShell",
+                "powershell",
+                &["pwsh"],
+                &[
+                    "-NoProfile",
+                    "-NonInteractive",
+                    "-File",
+                    "{runner}",
+                    "{script}",
+                    "{result}",
+                ],
+            )
+        }
+        .with_runner(POWERSHELL_RUNNER, "ps1"),
+    ]
+}
+
+/// 创建使用默认设置的运行环境
+fn runtime(id: &str, name: &str, lang: &str, executables: &[&str], args: &[&str]) -> Runtime {
+    Runtime {
+        id: id.to_string(),
+        name: name.to_string(),
+        lang: lang.to_string(),
+        executables: executables.iter().map(|e| e.to_string()).collect(),
+        search_paths: Vec::new(),
+        version_args: vec!["--version".to_string()],
+        args: args.iter().map(|a| a.to_string()).collect(),
+        runner: None,
+        extension: String::new(),
+        input: InputFormat::Json,
+        env: HashMap::new(),
+    }
+}
+
+/// 在指定路径后追加常见的安装路径
+fn with_common(paths: &[&str]) -> Vec<String> {
+    paths
+        .iter()
+        .chain(COMMON_PATHS)
+        .map(|p| p.to_string())
+        .collect()
+}
+
+/// 常见的 Python 安装路径
+fn python_paths() -> Vec<String> {
+    #[cfg(target_os = "windows")]
+    {
+        // 添加所有常见 Python 版本及其 Scripts 目录
+        let mut paths = vec!["~\\AppData\\Local\\Microsoft\\WindowsApps".to_string()];
+        let versions = [
+            "Python313",
+            "Python312",
+            "Python311",
+            "Python310",
+            "Python39",
+        ];
+        for version in versions {
+            paths.push(format!("~\\AppData\\Local\\Programs\\Python\\{}", version));
+            paths.push(format!(
+                "~\\AppData\\Local\\Programs\\Python\\{}\\Scripts",
+                version
+            ));
+            paths.push(format!("C:\\{}", version));
+            paths.push(format!("C:\\{}\\Scripts", version));
+        }
+        paths
+    }
+    #[cfg(not(target_os = "windows"))]
+    {
+        with_common(&["~/.pyenv/shims"])
+    }
+}
+
+/// 构建查找程序的 PATH 环境变量，在系统 PATH 后追加常见的安装路径
+///
+/// 从图形界面启动时 PATH 可能不完整，用于不属于任何运行环境的命令行程序
+pub fn common_search_path() -> OsString {
+    search_path(COMMON_PATHS)
+}
+
+/// 在系统 PATH 后追加查找路径，`~` 开头的路径相对于用户主目录
+fn search_path(extra: &[impl AsRef<str>]) -> OsString {
+    let home = home_dir();
+    let mut paths: Vec<PathBuf> = std::env::var_os("PATH")
+        .map(|path| std::env::split_paths(&path).collect())
+        .unwrap_or_default();
+    for path in extra {
+        let path = path.as_ref();
+        match path.strip_prefix('~') {
+            Some(rest) => paths.push(PathBuf::from(format!("{}{}", home, rest))),
+            None => paths.push(PathBuf::from(path)),
+        }
+    }
+    std::env::join_paths(paths).unwrap_or_default()
+}
+
+/// 获取用户主目录
+fn home_dir() -> String {
+    #[cfg(target_os = "windows")]
+    let home = std::env::var("USERPROFILE").unwrap_or_default();
+    #[cfg(not(target_os = "windows"))]
+    let home = std::env::var("HOME").unwrap_or_default();
+    home
+}
@@ -0,0 +1,130 @@
+use crate::error::AppError;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::time::Duration;
+use tokio::process::Command;
+
+/// 沙箱中脚本可用的内存上限（字节）
+const MEMORY_LIMIT: u64 = 1024 * 1024 * 1024;
+
+/// 沙箱中脚本可写入的单个文件大小上限（字节）
+const FILE_SIZE_LIMIT: u64 = 64 * 1024 * 1024;
+
+/// 沙箱中保留的环境变量
+const KEPT_ENV_VARS: &[&str] = &[
+    "PATH",
+    "LANG",
+    "LC_ALL",
+    "LC_CTYPE",
+    "TZ",
+    "SYSTEMROOT",
+    "WINDIR",
+    "COMSPEC",
+    "PATHEXT",
+];
+
+/// 脚本声明的沙箱级别，按限制从弱到强排列
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Sandbox {
+    /// 不限制，脚本以当前用户的完整权限运行
+    #[default]
+    None,
+    /// 限制资源，使用临时工作目录和精简的环境变量
+    Restricted,
+    /// 在受限的基础上禁止访问网络
+    Offline,
+}
+
+impl Sandbox {
+    /// 是否启用沙箱
+    pub fn is_enabled(self) -> bool {
+        self != Sandbox::None
+    }
+
+    /// 为命令应用沙箱限制
+    ///
+    /// 需要在设置其他环境变量之前调用，`dir` 为脚本的临时工作目录
+    pub fn apply(
+        self,
+        command: &mut Command,
+        dir: &Path,
+        timeout: Duration,
+    ) -> Result<(), AppError> {
+        if !self.is_enabled() {
+            return Ok(());
+        }
+        if self == Sandbox::Offline && !cfg!(target_os = "linux") {
+            return Err("Network isolation is only supported on Linux".into());
+        }
+        // 其他平台无法限制资源，拒绝运行而不是以较弱的限制运行
+        if !cfg!(unix) {
+            return Err("Resource limits are only supported on macOS and Linux".into());
+        }
+
+        // 临时工作目录和精简的环境变量
+        command.current_dir(dir).env_clear();
+        for key in KEPT_ENV_VARS {
+            if let Some(value) = std::env::var_os(key) {
+                command.env(key, value);
+            }
+        }
+        for key in ["HOME", "USERPROFILE", "TMPDIR", "TEMP", "TMP"] {
+            command.env(key, dir);
+        }
+
+        #[cfg(unix)]
+        {
+            let cpu_secs = timeout.as_secs() + 1;
+            #[cfg(target_os = "linux")]
+            let offline = self == Sandbox::Offline;
+            // SAFETY: 只在 fork 之后的子进程中调用异步信号安全的系统调用
+            unsafe {
+                command.pre_exec(move || {
+                    let limits = [
+                        (libc::RLIMIT_CPU, cpu_secs),
+                        (libc::RLIMIT_DATA, MEMORY_LIMIT),
+                        (libc::RLIMIT_FSIZE, FILE_SIZE_LIMIT),
+                    ];
+                    for (resource, value) in limits {
+                        let limit = libc::rlimit {
+                            rlim_cur: value as libc::rlim_t,
+                            rlim_max: value as libc::rlim_t,
+                        };
+                        if libc::setrlimit(resource, &limit) != 0 {
+                            return Err(std::io::Error::last_os_error());
+                        }
+                    }
+                    // 新的网络命名空间中只有未启用的回环接口
+                    #[cfg(target_os = "linux")]
+                    if offline && libc::unshare(libc::CLONE_NEWUSER | libc::CLONE_NEWNET) != 0 {
+                        return Err(std::io::Error::last_os_error());
+                    }
+                    Ok(())
+                });
+            }
+        }
+        #[cfg(not(unix))]
+        let _ = timeout;
+        Ok(())
+    }
+
+    /// 以 Deno 运行时使用的权限参数
+    pub fn deno_flags(self, dir: &Path) -> Vec<String> {
+        let dir = dir.display();
+        match self {
+            Sandbox::None => vec!["-A".to_string()],
+            Sandbox::Restricted => vec![
+                format!("--allow-read={}", dir),
+                format!("--allow-write={}", dir),
+                "--allow-net".to_string(),
+                "--no-prompt".to_string(),
+            ],
+            Sandbox::Offline => vec![
+                format!("--allow-read={}", dir),
+                format!("--allow-write={}", dir),
+                "--no-prompt".to_string(),
+            ],
+        }
+    }
+}
@@ -0,0 +1,107 @@
+use super::process::Execution;
+use crate::error::AppError;
+use std::collections::BTreeMap;
+
+/// 系统钥匙串中使用的服务名称
+const SERVICE: &str = "TextGO";
+
+/// 保存密钥到系统钥匙串，已存在时覆盖
+pub async fn set_secret(name: String, value: String) -> Result<(), AppError> {
+    check_name(&name)?;
+    if value.is_empty() {
+        return Err(format!("Secret '{}' is empty", name).into());
+    }
+    with_entry(name, move |entry, name| {
+        entry
+            .set_password(&value)
+            .map_err(|e| format!("Failed to save secret '{}': {}", name, e).into())
+    })
+    .await
+}
+
+/// 从系统钥匙串中删除密钥，不存在时忽略
+pub async fn delete_secret(name: String) -> Result<(), AppError> {
+    with_entry(name, |entry, name| match entry.delete_credential() {
+        Ok(()) | Err(keyring::Error::NoEntry) => Ok(()),
+        Err(e) => Err(format!("Failed to delete secret '{}': {}", name, e).into()),
+    })
+    .await
+}
+
+/// 判断系统钥匙串中是否存在密钥
+pub async fn has_secret(name: String) -> Result<bool, AppError> {
+    with_entry(name, |entry, name| match entry.get_password() {
+        Ok(_) => Ok(true),
+        Err(keyring::Error::NoEntry) => Ok(false),
+        Err(e) => Err(format!("Failed to read secret '{}': {}", name, e).into()),
+    })
+    .await
+}
+
+/// 从系统钥匙串读取密钥
+pub async fn get_secret(name: String) -> Result<String, AppError> {
+    with_entry(name, |entry, name| match entry.get_password() {
+        Ok(value) => Ok(value),
+        Err(keyring::Error::NoEntry) => {
+            Err(format!("Secret '{}' is not set. Add it in the settings.", name).into())
+        }
+        Err(e) => Err(format!("Failed to read secret '{}': {}", name, e).into()),
+    })
+    .await
+}
+
+/// 生成动作运行时额外设置的环境变量
+///
+/// 密钥以同名环境变量传递，其值登记到执行中，不会出现在实时输出和错误信息里
+pub async fn action_env(
+    env: &BTreeMap<String, String>,
+    secrets: &[String],
+    execution: &Execution,
+) -> Result<Vec<(String, String)>, AppError> {
+    let mut vars = Vec::with_capacity(env.len() + secrets.len());
+    for (key, value) in env {
+        check_name(key)?;
+        vars.push((key.clone(), value.clone()));
+    }
+    for name in secrets {
+        let value = get_secret(name.clone()).await?;
+        execution.conceal(&value);
+        vars.push((name.clone(), value));
+    }
+    Ok(vars)
+}
+
+/// 在阻塞线程中访问钥匙串条目
+///
+/// 部分平台的钥匙串接口会阻塞等待系统服务，不能在异步任务中直接调用
+async fn with_entry<T: Send + 'static>(
+    name: String,
+    f: impl FnOnce(&keyring::Entry, &str) -> Result<T, AppError> + Send + 'static,
+) -> Result<T, AppError> {
+    check_name(&name)?;
+    tokio::task::spawn_blocking(move || {
+        let entry = keyring::Entry::new(SERVICE, &name)
+            .map_err(|e| format!("Failed to open secret '{}': {}", name, e))?;
+        f(&entry, &name)
+    })
+    .await
+    .map_err(|e| format!("Keyring task failed: {}", e))?
+}
+
+/// 校验名称，密钥和环境变量名只能包含字母、数字和下划线，且不能以数字开头
+fn check_name(name: &str) -> Result<(), AppError> {
+    let mut chars = name.chars();
+    let valid = chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_');
+    if valid {
+        Ok(())
+    } else {
+        Err(format!(
+            "Invalid name '{}', use letters, digits and underscores only",
+            name
+        )
+        .into())
+    }
+}
@@ -0,0 +1,148 @@
+use super::process::Execution;
+use super::runner::{run_script, Data, ScriptAction, ScriptOptions};
+use crate::error::{AppError, ErrorKind};
+use serde::{Deserialize, Serialize};
+use similar::{ChangeTag, TextDiff};
+use std::time::Instant;
+
+/// 脚本测试用例
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TestCase {
+    /// 用例名称
+    #[serde(default)]
+    pub name: String,
+    /// 作为选中文本传入的输入
+    pub input: String,
+    /// 剪贴板文本
+    #[serde(default)]
+    pub clipboard: String,
+    /// 期望的返回值，未设置期望的错误时使用
+    #[serde(default)]
+    pub output: Option<String>,
+    /// 期望的错误信息，实际的错误信息包含该文本即视为通过
+    #[serde(default)]
+    pub error: Option<String>,
+}
+
+/// 差异行的类型
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DiffTag {
+    /// 相同
+    Equal,
+    /// 期望中有而实际没有
+    Delete,
+    /// 实际中有而期望没有
+    Insert,
+}
+
+/// 期望与实际返回值的一行差异
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DiffLine {
+    /// 差异类型
+    pub tag: DiffTag,
+    /// 行内容（不含换行符）
+    pub text: String,
+}
+
+/// 测试用例的执行结果
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TestResult {
+    /// 用例序号，从 0 开始
+    pub index: usize,
+    /// 用例名称
+    pub name: String,
+    /// 是否通过
+    pub passed: bool,
+    /// 实际返回值
+    pub output: Option<String>,
+    /// 实际的错误信息
+    pub error: Option<String>,
+    /// 返回值与期望不同时的逐行差异
+    pub diff: Vec<DiffLine>,
+    /// 执行耗时（毫秒）
+    pub duration_ms: u64,
+}
+
+/// 依次执行脚本的全部测试用例
+///
+/// 每个用例都通过实际的执行器运行，超时时间按用例单独计算，取消时中止剩余用例
+pub async fn run_tests(
+    action: &ScriptAction,
+    options: &ScriptOptions,
+    execution: &Execution,
+) -> Result<Vec<TestResult>, AppError> {
+    let mut results = Vec::with_capacity(action.tests.len());
+    for (index, case) in action.tests.iter().enumerate() {
+        let data = Data {
+            selection: case.input.clone(),
+            clipboard: case.clipboard.clone(),
+            datetime: chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true),
+            ..Default::default()
+        };
+        let start = Instant::now();
+        let outcome = run_script(action, &data, options, execution).await;
+        let duration_ms = start.elapsed().as_millis() as u64;
+        if let Err(e) = &outcome {
+            if e.kind() == Some(ErrorKind::Cancelled) {
+                return Err(e.clone());
+            }
+        }
+        results.push(check(index, case, outcome, duration_ms));
+    }
+    Ok(results)
+}
+
+/// 比较执行结果与用例的期望
+fn check(
+    index: usize,
+    case: &TestCase,
+    outcome: Result<String, AppError>,
+    duration_ms: u64,
+) -> TestResult {
+    let mut result = TestResult {
+        index,
+        name: case.name.clone(),
+        passed: false,
+        output: None,
+        error: None,
+        diff: Vec::new(),
+        duration_ms,
+    };
+    match (outcome, &case.error) {
+        (Ok(output), Some(_)) => result.output = Some(output),
+        (Err(e), Some(expected)) => {
+            let message = e.to_string();
+            result.passed = message.contains(expected.as_str());
+            result.error = Some(message);
+        }
+        (Ok(output), None) => {
+            let expected = case.output.as_deref().unwrap_or_default();
+            result.passed = output == expected;
+            if !result.passed {
+                result.diff = diff(expected, &output);
+            }
+            result.output = Some(output);
+        }
+        (Err(e), None) => result.error = Some(e.to_string()),
+    }
+    result
+}
+
+/// 逐行比较期望与实际的返回值
+fn diff(expected: &str, actual: &str) -> Vec<DiffLine> {
+    TextDiff::from_lines(expected, actual)
+        .iter_all_changes()
+        .map(|change| DiffLine {
+            tag: match change.tag() {
+                ChangeTag::Equal => DiffTag::Equal,
+                ChangeTag::Delete => DiffTag::Delete,
+                ChangeTag::Insert => DiffTag::Insert,
+            },
+            text: change.value().trim_end_matches(['\r', '\n']).to_string(),
+        })
+        .collect()
+}
@@ -0,0 +1,136 @@
+// TextGO 宿主 API，兼容 Node.js 和 Deno
+//
+// 由运行器加载为全局的 textgo 对象，需要在脚本设置中授予对应的权限：
+//   await textgo.clipboard.read()            读取剪贴板文本
+//   await textgo.notify(message, title)      发送系统通知
+//   await textgo.prompt(llm, text, system, provider)  调用大模型并返回回复，provider 为 ollama、lmstudio 或 openai
+//   await textgo.runAction(action, text)     执行其他脚本或命令动作并返回结果
+//   await textgo.askUser(question)           弹出确认对话框，返回用户的选择
+// 请求以 JSON-RPC 格式写入标准错误，响应从标准输入逐行读取
+(() => {
+  const isDeno = typeof Deno !== 'undefined';
+  // 用户脚本定义的 process 函数会覆盖 Node.js 的全局 process 对象，提前保存
+  const host = globalThis.process;
+  const MARK = '\x1etextgo-rpc ';
+
+  class TextGOError extends Error {
+    constructor(message, code) {
+      super(message);
+      this.name = 'TextGOError';
+      this.code = code;
+    }
+  }
+
+  // 按行读取标准输入
+  let buffer = '';
+  let ended = false;
+  let waiting = null;
+  let reader = null;
+  let started = false;
+
+  function push(text) {
+    buffer += text;
+    if (waiting) {
+      const wake = waiting;
+      waiting = null;
+      wake();
+    }
+  }
+
+  function end() {
+    ended = true;
+    push('');
+  }
+
+  function start() {
+    started = true;
+    if (isDeno) {
+      reader = Deno.stdin.readable.pipeThrough(new TextDecoderStream()).getReader();
+      (async () => {
+        try {
+          for (;;) {
+            const { value, done } = await reader.read();
+            if (done) break;
+            push(value);
+          }
+        } catch {
+          // 读取被取消
+        }
+        end();
+      })();
+    } else {
+      host.stdin.setEncoding('utf8');
+      host.stdin.on('data', push);
+      host.stdin.on('end', end);
+    }
+  }
+
+  async function readLine() {
+    if (!started) {
+      start();
+    }
+    for (;;) {
+      const index = buffer.indexOf('\n');
+      if (index >= 0) {
+        const line = buffer.slice(0, index);
+        buffer = buffer.slice(index + 1);
+        return line;
+      }
+      if (ended) {
+        const line = buffer;
+        buffer = '';
+        return line || null;
+      }
+      await new Promise((resolve) => (waiting = resolve));
+    }
+  }
+
+  // 停止读取标准输入，使进程可以正常退出
+  function close() {
+    if (isDeno) {
+      reader?.cancel().catch(() => {});
+    } else {
+      host.stdin.destroy();
+    }
+  }
+
+  let nextId = 0;
+  // 请求依次处理，按顺序等待响应
+  let queue = Promise.resolve();
+
+  function call(method, params = {}) {
+    const id = ++nextId;
+    const request = JSON.stringify({ jsonrpc: '2.0', id, method, params });
+    const result = queue.then(async () => {
+      if (isDeno) {
+        Deno.stderr.writeSync(new TextEncoder().encode(MARK + request + '\n'));
+      } else {
+        host.stderr.write(MARK + request + '\n');
+      }
+      const line = await readLine();
+      if (line === null) {
+        throw new TextGOError('TextGO host API is not available');
+      }
+      const response = JSON.parse(line);
+      if (response.error) {
+        throw new TextGOError(response.error.message, response.error.code);
+      }
+      return response.result;
+    });
+    queue = result.catch(() => {});
+    return result;
+  }
+
+  globalThis.textgo = {
+    TextGOError,
+    clipboard: {
+      read: () => call('clipboard.read')
+    },
+    notify: (message, title) => call('notify', title ? { message, title } : { message }),
+    prompt: (llm, text, system, provider) => call('prompt', { model: llm, text, system, provider }),
+    runAction: (action, text = '') => call('run_action', { action, text }),
+    askUser: (question) => call('ask_user', { question }),
+    _readLine: readLine,
+    _close: close
+  };
+})();
@@ -0,0 +1,68 @@
+# TextGO 宿主 API
+#
+# 在脚本中通过 `import textgo` 使用，需要在脚本设置中授予对应的权限：
+#   textgo.clipboard.read()            读取剪贴板文本
+#   textgo.notify(message, title)      发送系统通知
+#   textgo.prompt(llm, text, system, provider)  调用大模型并返回回复，provider 为 ollama、lmstudio 或 openai
+#   textgo.run_action(action, text)    执行其他脚本或命令动作并返回结果
+#   textgo.ask_user(question)          弹出确认对话框，返回用户的选择
+# 请求以 JSON-RPC 格式写入标准错误，响应从标准输入逐行读取
+import json
+import sys
+
+_MARK = "\x1etextgo-rpc "
+_next_id = 0
+
+
+class TextGOError(Exception):
+    """宿主 API 调用失败"""
+
+    def __init__(self, message, code=None):
+        super().__init__(message)
+        self.code = code
+
+
+def _call(method, **params):
+    global _next_id
+    _next_id += 1
+    request = {"jsonrpc": "2.0", "id": _next_id, "method": method, "params": params}
+    sys.stderr.write(_MARK + json.dumps(request, ensure_ascii=False) + "\n")
+    sys.stderr.flush()
+
+    line = sys.stdin.buffer.readline()
+    if not line:
+        raise TextGOError("TextGO host API is not available")
+    response = json.loads(line.decode("utf-8"))
+    error = response.get("error")
+    if error:
+        raise TextGOError(error.get("message", "Unknown error"), error.get("code"))
+    return response.get("result")
+
+
+class _Clipboard:
+    def read(self):
+        return _call("clipboard.read")
+
+
+clipboard = _Clipboard()
+
+
+def notify(message, title=None):
+    _call("notify", message=message, **({"title": title} if title else {}))
+
+
+def prompt(llm, text, system=None, provider=None):
+    params = {"model": llm, "text": text}
+    if system:
+        params["system"] = system
+    if provider:
+        params["provider"] = provider
+    return _call("prompt", **params)
+
+
+def run_action(action, text=""):
+    return _call("run_action", action=action, text=text)
+
+
+def ask_user(question):
+    return _call("ask_user", question=question)
@@ -0,0 +1,253 @@
+use super::process::Execution;
+use super::runner::Data;
+use crate::error::AppError;
+use crate::WASM_MODULES;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use wasmtime::{Config, Engine, Linker, Module, Store, StoreLimits, StoreLimitsBuilder, Trap};
+use wasmtime_wasi::pipe::MemoryOutputPipe;
+use wasmtime_wasi::preview1::{self, WasiP1Ctx};
+use wasmtime_wasi::{DirPerms, FilePerms, I32Exit, WasiCtxBuilder};
+
+/// WebAssembly 脚本的语言标识
+pub const WASM_LANG: &str = "wasm";
+
+/// 运行环境名称
+const RUNTIME_NAME: &str = "WebAssembly";
+
+/// 默认的燃料上限，大致等于可执行的指令数
+const DEFAULT_FUEL: u64 = 10_000_000_000;
+
+/// 默认的线性内存上限（MiB）
+const DEFAULT_MEMORY_MB: u64 = 256;
+
+/// 每消耗多少燃料让出一次执行，以便响应超时和取消
+const FUEL_YIELD_INTERVAL: u64 = 1_000_000;
+
+/// 标准输出和标准错误的缓冲区上限（字节）
+const MAX_OUTPUT_BYTES: usize = 1024 * 1024;
+
+/// 缓存的已编译模块数量上限
+const MAX_CACHED_MODULES: usize = 32;
+
+/// WebAssembly 模块的运行选项
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WasmOptions {
+    /// 燃料上限，为空时使用默认值
+    #[serde(default)]
+    pub fuel: Option<u64>,
+    /// 线性内存上限（MiB），为空时使用默认值
+    #[serde(default)]
+    pub memory_mb: Option<u64>,
+    /// 允许只读访问的目录，在模块中以相同的路径访问
+    #[serde(default)]
+    pub read_dirs: Vec<String>,
+    /// 允许读写的目录
+    #[serde(default)]
+    pub write_dirs: Vec<String>,
+}
+
+/// 已编译模块的缓存，按模块内容的哈希值索引
+///
+/// 模块只能在编译它的引擎中使用，因此引擎与模块一同保存
+#[derive(Default)]
+pub struct ModuleCache {
+    /// 编译和运行模块的引擎，首次使用时创建
+    engine: Option<Engine>,
+    /// 已编译的模块
+    modules: HashMap<String, Module>,
+}
+
+impl ModuleCache {
+    /// 获取引擎，首次使用时按运行限制创建
+    fn engine(&mut self) -> Result<Engine, AppError> {
+        if let Some(engine) = &self.engine {
+            return Ok(engine.clone());
+        }
+        let mut config = Config::new();
+        config.async_support(true).consume_fuel(true);
+        let engine = Engine::new(&config)
+            .map_err(|e| format!("Failed to create WebAssembly engine: {}", e))?;
+        self.engine = Some(engine.clone());
+        Ok(engine)
+    }
+}
+
+/// 模块实例的宿主状态
+struct State {
+    /// WASI 上下文
+    wasi: WasiP1Ctx,
+    /// 内存等资源限制
+    limits: StoreLimits,
+}
+
+/// 运行 WebAssembly 模块
+///
+/// `source` 为 WAT 文本或模块文件路径。模块需要导出 `memory`、`alloc(len) -> ptr`
+/// 和 `process(ptr, len) -> i64`，返回值的高 32 位为输出的地址，低 32 位为输出的长度。
+/// 选中文本以 UTF-8 写入 `alloc` 分配的内存，其余数据通过环境变量传递。
+/// 模块在 WASI 中运行，只能访问选项中授权的目录，不能访问网络
+pub async fn run_wasm(
+    source: &str,
+    options: &WasmOptions,
+    data: &Data,
+    vars: &[(String, String)],
+    execution: &Execution,
+) -> Result<String, AppError> {
+    let (module, origin) = load_module(source).await?;
+    execution.set_runtime(Some(RUNTIME_NAME), &origin, None);
+
+    let stdout = MemoryOutputPipe::new(MAX_OUTPUT_BYTES);
+    let stderr = MemoryOutputPipe::new(MAX_OUTPUT_BYTES);
+    let mut wasi = WasiCtxBuilder::new();
+    wasi.stdout(stdout.clone())
+        .stderr(stderr.clone())
+        .envs(&data.env())
+        .envs(vars);
+    for (dirs, dir_perms, file_perms) in [
+        (&options.read_dirs, DirPerms::READ, FilePerms::READ),
+        (&options.write_dirs, DirPerms::all(), FilePerms::all()),
+    ] {
+        for dir in dirs {
+            wasi.preopened_dir(dir, dir, dir_perms, file_perms)
+                .map_err(|e| format!("Failed to grant access to '{}': {}", dir, e))?;
+        }
+    }
+
+    let memory_mb = options
+        .memory_mb
+        .filter(|mb| *mb > 0)
+        .unwrap_or(DEFAULT_MEMORY_MB);
+    let limits = StoreLimitsBuilder::new()
+        .memory_size((memory_mb * 1024 * 1024) as usize)
+        .trap_on_grow_failure(true)
+        .build();
+    let mut store = Store::new(
+        module.engine(),
+        State {
+            wasi: wasi.build_p1(),
+            limits,
+        },
+    );
+    store.limiter(|state| &mut state.limits);
+    store.set_fuel(
+        options
+            .fuel
+            .filter(|fuel| *fuel > 0)
+            .unwrap_or(DEFAULT_FUEL),
+    )?;
+    store.fuel_async_yield_interval(Some(FUEL_YIELD_INTERVAL))?;
+
+    let mut linker = Linker::new(module.engine());
+    preview1::add_to_linker_async(&mut linker, |state: &mut State| &mut state.wasi)?;
+
+    let input = data.selection.as_bytes();
+    let task = async {
+        let instance = linker.instantiate_async(&mut store, &module).await?;
+        // 以 reactor 方式编译的模块需要先初始化
+        if let Ok(initialize) = instance.get_typed_func::<(), ()>(&mut store, "_initialize") {
+            initialize.call_async(&mut store, ()).await?;
+        }
+        let memory = instance
+            .get_memory(&mut store, "memory")
+            .ok_or_else(|| wasmtime::Error::msg("Module does not export 'memory'"))?;
+        let alloc = instance.get_typed_func::<i32, i32>(&mut store, "alloc")?;
+        let process = instance.get_typed_func::<(i32, i32), i64>(&mut store, "process")?;
+
+        let len = i32::try_from(input.len())?;
+        let ptr = alloc.call_async(&mut store, len).await?;
+        memory.write(&mut store, ptr as u32 as usize, input)?;
+        let packed = process.call_async(&mut store, (ptr, len)).await? as u64;
+        let start = (packed >> 32) as usize;
+        let end = start + (packed & 0xffff_ffff) as usize;
+        memory
+            .data(&store)
+            .get(start..end)
+            .map(<[u8]>::to_vec)
+            .ok_or_else(|| wasmtime::Error::msg("Output is out of memory bounds"))
+    };
+    let result = execution
+        .supervise(None, async {
+            task.await
+                .map_err(|e| describe(e, &stderr.contents(), execution).into())
+        })
+        .await;
+
+    // WASI 的输出在执行结束后一次性发送
+    for (stream, pipe) in [("stdout", &stdout), ("stderr", &stderr)] {
+        for line in String::from_utf8_lossy(&pipe.contents()).lines() {
+            execution.emit(stream, line);
+        }
+    }
+    String::from_utf8(result?)
+        .map_err(|_| "WebAssembly module returned invalid UTF-8 output".into())
+}
+
+/// 加载并编译模块，相同内容的模块只编译一次
+///
+/// 以 `(` 或 `;;` 开头的内容视为 WAT 文本，否则视为模块文件路径
+async fn load_module(source: &str) -> Result<(Module, String), AppError> {
+    let source = source.trim().to_string();
+    tokio::task::spawn_blocking(move || {
+        let (bytes, origin) = if source.starts_with('(') || source.starts_with(";;") {
+            (source.into_bytes(), "inline".to_string())
+        } else {
+            let bytes = std::fs::read(&source)
+                .map_err(|e| format!("Failed to read WebAssembly module '{}': {}", source, e))?;
+            (bytes, source)
+        };
+        let key: String = Sha256::digest(&bytes)
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect();
+
+        let engine = {
+            let mut cache = WASM_MODULES.lock()?;
+            if let Some(module) = cache.modules.get(&key) {
+                return Ok((module.clone(), origin));
+            }
+            cache.engine()?
+        };
+        // 编译耗时较长，不持有缓存锁
+        let module = Module::new(&engine, &bytes)
+            .map_err(|e| format!("Failed to compile WebAssembly module: {:#}", e))?;
+
+        let mut cache = WASM_MODULES.lock()?;
+        if cache.modules.len() >= MAX_CACHED_MODULES {
+            if let Some(evicted) = cache.modules.keys().next().cloned() {
+                cache.modules.remove(&evicted);
+            }
+        }
+        cache.modules.insert(key, module.clone());
+        Ok((module, origin))
+    })
+    .await
+    .map_err(|e| format!("WebAssembly compilation task failed: {}", e))?
+}
+
+/// 将执行失败的原因转换为错误信息，附带模块写入标准错误的内容
+fn describe(error: wasmtime::Error, stderr: &[u8], execution: &Execution) -> String {
+    let reason = if let Some(exit) = error.downcast_ref::<I32Exit>() {
+        format!("module exited with code {}", exit.0)
+    } else if error.downcast_ref::<Trap>() == Some(&Trap::OutOfFuel) {
+        "fuel limit exceeded".to_string()
+    } else if error.root_cause().to_string().contains("growing memory") {
+        // 超出内存上限时增长内存失败，按设置转换为陷阱
+        "memory limit exceeded".to_string()
+    } else {
+        format!("{:#}", error)
+    };
+    let stderr = String::from_utf8_lossy(stderr);
+    let message = if stderr.trim().is_empty() {
+        format!("WebAssembly execution failed: {}", reason)
+    } else {
+        format!(
+            "WebAssembly execution failed: {}\n{}",
+            reason,
+            stderr.trim_end()
+        )
+    };
+    execution.redact(&message)
+}
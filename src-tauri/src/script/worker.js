@@ -0,0 +1,104 @@
+// TextGO JavaScript 常驻工作进程，兼容 Node.js 和 Deno
+//
+// 用法: node -e <源码> 或 deno eval <源码>
+// 从标准输入逐行读取 JSON 请求 {id, key, code, data}，调用脚本中的 process(data) 函数，
+// 并向标准输出逐行写入 JSON 消息：
+//   {id, type: "output", stream, line}  脚本的 console 输出
+//   {id, type: "result", result, memory, cached}  脚本返回值
+//   {id, type: "error", message, memory, cached}  脚本执行失败
+// 编译后的脚本按 key 缓存，请求中的 code 为空时直接使用缓存，cached 表示脚本是否已缓存
+const isDeno = typeof Deno !== 'undefined';
+const host = globalThis.process;
+const encoder = new TextEncoder();
+const scripts = new Map();
+let current = null;
+
+function send(message) {
+  const line = JSON.stringify({ id: current, ...message }) + '\n';
+  if (isDeno) {
+    const bytes = encoder.encode(line);
+    let written = 0;
+    while (written < bytes.length) {
+      written += Deno.stdout.writeSync(bytes.subarray(written));
+    }
+  } else {
+    host.stdout.write(line);
+  }
+}
+
+function memory() {
+  return (isDeno ? Deno.memoryUsage() : host.memoryUsage()).rss;
+}
+
+// 逐行读取标准输入
+async function* lines() {
+  const decoder = new TextDecoder();
+  let buffer = '';
+  for await (const chunk of isDeno ? Deno.stdin.readable : host.stdin) {
+    buffer += decoder.decode(chunk, { stream: true });
+    let index;
+    while ((index = buffer.indexOf('\n')) >= 0) {
+      yield buffer.slice(0, index);
+      buffer = buffer.slice(index + 1);
+    }
+  }
+}
+
+// 将脚本的 console 输出转为协议消息，避免干扰标准输出上的协议
+async function redirectConsole() {
+  const inspect = isDeno ? Deno.inspect : (await import('node:util')).inspect;
+  const format = (args) => args.map((arg) => (typeof arg === 'string' ? arg : inspect(arg))).join(' ');
+  const output = (stream) => (...args) => {
+    for (const line of format(args).split('\n')) {
+      send({ type: 'output', stream, line });
+    }
+  };
+  console.log = console.info = console.debug = output('stdout');
+  console.warn = console.error = output('stderr');
+}
+
+// 编译脚本并取出其中的 process 函数，每个脚本拥有独立的作用域
+function load(key, code) {
+  if (code != null) {
+    const process = new Function(`${code}\n;return typeof process === 'function' ? process : undefined;`)();
+    if (typeof process !== 'function') {
+      throw new ReferenceError('process(data) function is not defined');
+    }
+    scripts.set(key, process);
+  }
+  const process = scripts.get(key);
+  if (!process) {
+    throw new Error(`Script ${key} is not cached`);
+  }
+  return process;
+}
+
+async function main() {
+  await redirectConsole();
+  for await (const line of lines()) {
+    if (!line.trim()) {
+      continue;
+    }
+    const request = JSON.parse(line);
+    current = request.id;
+    try {
+      const result = await load(request.key, request.code)(request.data);
+      send({
+        type: 'result',
+        result: typeof result === 'string' ? result : JSON.stringify(result),
+        memory: memory(),
+        cached: scripts.has(request.key)
+      });
+    } catch (error) {
+      send({
+        type: 'error',
+        message: String(error?.stack ?? error),
+        memory: memory(),
+        cached: scripts.has(request.key)
+      });
+    }
+    current = null;
+  }
+}
+
+main();
@@ -0,0 +1,97 @@
+# TextGO Python 常驻工作进程
+#
+# 用法: python -c <源码>
+# 从标准输入逐行读取 JSON 请求 {id, key, code, data}，调用脚本中的 process(data) 函数，
+# 并向标准输出逐行写入 JSON 消息：
+#   {id, type: "output", stream, line}  脚本的 print 输出
+#   {id, type: "result", result, memory, cached}  脚本返回值
+#   {id, type: "error", message, memory, cached}  脚本执行失败
+# 编译后的脚本按 key 缓存，请求中的 code 为空时直接使用缓存，cached 表示脚本是否已缓存
+import json
+import sys
+import traceback
+
+_protocol = sys.stdout.buffer
+_scripts = {}
+_current = None
+
+
+def _send(message):
+    line = json.dumps({"id": _current, **message}, ensure_ascii=False) + "\n"
+    _protocol.write(line.encode("utf-8"))
+    _protocol.flush()
+
+
+def _memory():
+    try:
+        import resource
+    except ImportError:
+        return None
+    usage = resource.getrusage(resource.RUSAGE_SELF).ru_maxrss
+    # Linux 下单位为 KB，macOS 下单位为字节
+    return usage if sys.platform == "darwin" else usage * 1024
+
+
+class _Stream:
+    """将脚本的输出转为协议消息，避免干扰标准输出上的协议"""
+
+    def __init__(self, name):
+        self.name = name
+        self.buffer = ""
+
+    def write(self, text):
+        self.buffer += text
+        *lines, self.buffer = self.buffer.split("\n")
+        for line in lines:
+            _send({"type": "output", "stream": self.name, "line": line})
+        return len(text)
+
+    def flush(self):
+        pass
+
+    def drain(self):
+        if self.buffer:
+            self.write("\n")
+
+
+def _load(key, code):
+    """编译脚本并取出其中的 process 函数，每个脚本拥有独立的作用域"""
+    if code is not None:
+        scope = {"__name__": "__textgo__"}
+        exec(compile(code, f"<{key}>", "exec"), scope)
+        process = scope.get("process")
+        if not callable(process):
+            raise NameError("process(data) function is not defined")
+        _scripts[key] = process
+    if key not in _scripts:
+        raise KeyError(f"Script {key} is not cached")
+    return _scripts[key]
+
+
+def main():
+    global _current
+    stdout, stderr = _Stream("stdout"), _Stream("stderr")
+    sys.stdout, sys.stderr = stdout, stderr
+
+    for line in sys.stdin.buffer:
+        if not line.strip():
+            continue
+        request = json.loads(line.decode("utf-8"))
+        _current = request["id"]
+        try:
+            result = _load(request["key"], request.get("code"))(request["data"])
+            message = {
+                "type": "result",
+                "result": result if isinstance(result, str) else json.dumps(result, ensure_ascii=False),
+            }
+        except (Exception, SystemExit):
+            message = {"type": "error", "message": traceback.format_exc()}
+        stdout.drain()
+        stderr.drain()
+        message["memory"] = _memory()
+        message["cached"] = request["key"] in _scripts
+        _send(message)
+        _current = None
+
+
+main()
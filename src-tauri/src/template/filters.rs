@@ -0,0 +1,28 @@
+use minijinja::{Error, ErrorKind, Value};
+
+/// `truncate` 过滤器的默认长度
+const DEFAULT_TRUNCATE_LENGTH: usize = 255;
+
+/// 截断到指定的字符数，超出时以 `end` 结尾，结果不超过指定长度
+///
+/// 用法：`{{ selection | truncate(100) }}`、`{{ selection | truncate(100, "…") }}`
+pub fn truncate(value: &str, length: Option<usize>, end: Option<&str>) -> String {
+    let length = length.unwrap_or(DEFAULT_TRUNCATE_LENGTH);
+    if value.chars().count() <= length {
+        return value.to_string();
+    }
+    let end = end.unwrap_or("...");
+    let mut result: String = value
+        .chars()
+        .take(length.saturating_sub(end.chars().count()))
+        .collect();
+    result.push_str(end);
+    result
+}
+
+/// 序列化为 JSON 字符串
+///
+/// 用法：`{{ selection | json }}`
+pub fn json(value: &Value) -> Result<String, Error> {
+    serde_json::to_string(value).map_err(|e| Error::new(ErrorKind::InvalidOperation, e.to_string()))
+}
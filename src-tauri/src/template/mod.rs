@@ -0,0 +1,4 @@
+mod filters;
+mod render;
+
+pub use render::*;
@@ -0,0 +1,113 @@
+use super::filters;
+use crate::engine::{is_language, MODEL_MARK, REGEXP_MARK};
+use crate::error::AppError;
+use crate::script::Data;
+use minijinja::Environment;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::sync::LazyLock;
+
+/// 模板引擎，在内置过滤器之外注册了 `truncate` 和 `json`
+static ENVIRONMENT: LazyLock<Environment<'static>> = LazyLock::new(|| {
+    let mut env = Environment::new();
+    // 提示词和参数原样保留末尾的换行
+    env.set_keep_trailing_newline(true);
+    env.add_filter("truncate", filters::truncate);
+    env.add_filter("json", filters::json);
+    env
+});
+
+/// 模板的语法问题
+#[derive(Debug, Clone, Serialize)]
+pub struct TemplateIssue {
+    /// 出错的行号，从 1 开始
+    pub line: usize,
+    /// 出错的列号，从 1 开始
+    pub column: usize,
+    /// 错误信息
+    pub message: String,
+}
+
+/// 使用触发数据渲染模板
+///
+/// 可使用的变量有 `selection`、`clipboard`、`datetime`、`app_name`、
+/// `detected_language`、`matched_case`、`key` 和 `action`，
+/// `extra` 中的变量会追加或覆盖同名的变量
+pub fn render(source: &str, data: &Data, extra: &[(&str, &str)]) -> Result<String, AppError> {
+    let mut vars = variables(data);
+    vars.extend(extra.iter().copied());
+    ENVIRONMENT.render_str(source, vars).map_err(|e| {
+        let issue = describe(source, &e);
+        format!(
+            "Failed to render template at line {}, column {}: {}",
+            issue.line, issue.column, issue.message
+        )
+        .into()
+    })
+}
+
+/// 检查模板的语法，并以空数据试渲染以发现未知的过滤器等问题
+pub fn validate(source: &str) -> Option<TemplateIssue> {
+    let data = Data::default();
+    ENVIRONMENT
+        .template_from_str(source)
+        .and_then(|template| template.render(variables(&data)))
+        .err()
+        .map(|e| describe(source, &e))
+}
+
+/// 判断模板中是否使用了指定的变量，模板无法解析时视为未使用
+pub fn uses(source: &str, name: &str) -> bool {
+    ENVIRONMENT
+        .template_from_str(source)
+        .is_ok_and(|template| template.undeclared_variables(false).contains(name))
+}
+
+/// 模板中可使用的变量
+fn variables(data: &Data) -> BTreeMap<&str, &str> {
+    let case = data.context.case.as_str();
+    let matched_case = case
+        .strip_prefix(REGEXP_MARK)
+        .or_else(|| case.strip_prefix(MODEL_MARK))
+        .unwrap_or(case);
+    BTreeMap::from([
+        ("selection", data.selection.as_str()),
+        ("clipboard", data.clipboard.as_str()),
+        ("datetime", data.datetime.as_str()),
+        ("app_name", data.context.app.as_str()),
+        (
+            "detected_language",
+            if is_language(case) { case } else { "" },
+        ),
+        ("matched_case", matched_case),
+        ("key", data.context.key.as_str()),
+        ("case", case),
+        ("action", data.context.action.as_str()),
+    ])
+}
+
+/// 从模板错误中取出行列位置和错误信息
+fn describe(source: &str, error: &minijinja::Error) -> TemplateIssue {
+    let line = error.line().unwrap_or(1);
+    let column = error
+        .range()
+        .and_then(|range| source.get(..range.start))
+        .map_or(1, |before| {
+            before
+                .rsplit('\n')
+                .next()
+                .unwrap_or_default()
+                .chars()
+                .count()
+                + 1
+        });
+    let message = match error.detail() {
+        Some(detail) => format!("{}: {}", error.kind(), detail),
+        None => error.kind().to_string(),
+    };
+    TemplateIssue {
+        line,
+        column,
+        message,
+    }
+}
@@ -1,5 +1,7 @@
 export { default as Button } from './Button.svelte';
 export { default as CodeMirror } from './CodeMirror.svelte';
+export { default as Command } from './Command.svelte';
+export { default as Environment } from './Environment.svelte';
 export { default as Label } from './Label.svelte';
 export { default as List } from './List.svelte';
 export { default as Modal } from './Modal.svelte';
@@ -9,3 +9,6 @@ export const SCRIPT_MARK = 'script-';
 
 /** 提示词前缀标识 */
 export const PROMPT_MARK = 'prompt-';
+
+/** 命令行动作前缀标识 */
+export const COMMAND_MARK = 'command-';
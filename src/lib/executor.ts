@@ -1,8 +1,35 @@
-import { PROMPT_MARK, SCRIPT_MARK } from '$lib/constants';
+import { COMMAND_MARK, PROMPT_MARK, SCRIPT_MARK } from '$lib/constants';
 import { m } from '$lib/paraglide/messages';
-import { entries, historySize, nodePath, prompts, pythonPath, scripts } from '$lib/stores.svelte';
-import type { Entry, Option, Prompt, Rule, Script } from '$lib/types';
-import { invoke } from '@tauri-apps/api/core';
+import {
+  commands,
+  entries,
+  findScript,
+  historySize,
+  nodePath,
+  npmRegistry,
+  pipIndexUrl,
+  prompts,
+  pythonPath,
+  runtimes,
+  warmPool
+} from '$lib/stores.svelte';
+import type {
+  ChatMessage,
+  ChatRequest,
+  Command,
+  Entry,
+  ExecutionError,
+  Option,
+  Prompt,
+  Rule,
+  RuntimeStatus,
+  Script,
+  Session,
+  SessionReply,
+  TemplateIssue,
+  TestResult
+} from '$lib/types';
+import { Channel, invoke } from '@tauri-apps/api/core';
 import { readText } from '@tauri-apps/plugin-clipboard-manager';
 import { openPath, openUrl } from '@tauri-apps/plugin-opener';
 import { memoize } from 'es-toolkit/function';
@@ -40,6 +67,17 @@ type Data = {
   clipboard: string;
   /** 当前日期时间 */
   datetime: string;
+  /** 触发上下文 */
+  context?: {
+    /** 触发键位 */
+    key: string;
+    /** 匹配到的文本类型 */
+    case: string;
+    /** 执行的动作标识 */
+    action: string;
+    /** 触发时前台应用的名称 */
+    app?: string;
+  };
 };
 
 /**
@@ -212,14 +250,20 @@ const findBuiltinAction = memoize((action: string) =>
  * @param rule - 规则对象
  * @param selection - 选中的文本
  */
-export async function execute(rule: Rule, selection: string): Promise<void> {
+export async function execute(rule: Rule, selection: string, app?: string | null): Promise<void> {
   // 动作标识
   const action = rule.action;
   // 组装数据
   const data: Data = {
     selection: selection,
     clipboard: await readText(),
-    datetime: new Date().toISOString()
+    datetime: new Date().toISOString(),
+    context: {
+      key: rule.key,
+      case: rule.case,
+      action: rule.action,
+      app: app ?? ''
+    }
   };
   // 生成记录
   const entry: Entry = {
@@ -234,27 +278,77 @@ export async function execute(rule: Rule, selection: string): Promise<void> {
   // 根据动作标识执行对应的操作
   if (action.startsWith(SCRIPT_MARK)) {
     const scriptId = action.substring(SCRIPT_MARK.length);
-    const script = scripts.current.find((s) => s.id === scriptId);
+    const script = findScript(scriptId);
     if (script) {
       console.debug('开始执行脚本:', scriptId);
-      const result = await executeScript(script, data);
-      console.debug('脚本执行成功:', result);
-      // 保存记录
       entry.actionType = 'script';
       entry.actionLabel = scriptId;
-      entry.result = result;
       entry.scriptLang = script.lang;
       entry.quietMode = script.quietMode;
-      entries.current.unshift(entry);
-      // 删除多余记录
-      if (entries.current.length > historySize.current) {
-        entries.current = entries.current.slice(0, historySize.current);
+      let result: string;
+      try {
+        result = await executeScript(script, data, (id) => {
+          // 非静默模式下，脚本启动时即显示弹窗以展示实时输出
+          if (!script.quietMode) {
+            showPopup({ ...entry, executionId: id });
+          }
+        });
+      } catch (error) {
+        if (!script.quietMode) {
+          await updatePopup({ ...entry, error: typeof error === 'string' ? error : (error as ExecutionError).message });
+        }
+        throw error;
       }
+      console.debug('脚本执行成功:', result);
+      entry.result = result;
+      // 结构化结果由后端分发并保存记录
+      if (await deliverResult(rule, entry)) {
+        return;
+      }
+      // 保存记录
+      saveEntry(entry);
       if (script.quietMode) {
         // 静默模式下不显示窗口
         await invoke('send_paste_key', { text: result });
       } else {
-        await showPopup(entry);
+        await updatePopup(entry);
+      }
+    }
+  } else if (action.startsWith(COMMAND_MARK)) {
+    const commandId = action.substring(COMMAND_MARK.length);
+    const command = commands.current.find((c) => c.id === commandId);
+    if (command) {
+      console.debug('开始执行命令:', commandId);
+      entry.actionType = 'command';
+      entry.actionLabel = commandId;
+      entry.quietMode = command.quietMode;
+      let result: string;
+      try {
+        result = await executeCommand(command, data, (id) => {
+          // 非静默模式下，命令启动时即显示弹窗以展示实时输出
+          if (!command.quietMode) {
+            showPopup({ ...entry, executionId: id });
+          }
+        });
+      } catch (error) {
+        if (!command.quietMode) {
+          await updatePopup({ ...entry, error: typeof error === 'string' ? error : (error as ExecutionError).message });
+        }
+        throw error;
+      }
+      console.debug('命令执行成功:', result);
+      entry.result = result;
+      // 结构化结果由后端分发并保存记录
+      if (await deliverResult(rule, entry)) {
+        return;
+      }
+      // 保存记录
+      saveEntry(entry);
+      if (command.quietMode) {
+        // 静默模式下不显示窗口
+        await invoke('send_paste_key', { text: result });
+      } else {
+        await updatePopup(entry);
       }
     }
   } else if (action.startsWith(PROMPT_MARK)) {
@@ -269,13 +363,11 @@ export async function execute(rule: Rule, selection: string): Promise<void> {
       entry.actionLabel = promptId;
       entry.result = result;
       entry.systemPrompt = prompt.systemPrompt;
+      entry.tools = prompt.tools?.length ? prompt.tools : undefined;
+      entry.temperature = prompt.temperature;
       entry.provider = prompt.provider;
       entry.model = prompt.model;
-      entries.current.unshift(entry);
-      // 删除多余记录
-      if (entries.current.length > historySize.current) {
-        entries.current = entries.current.slice(0, historySize.current);
-      }
+      saveEntry(entry);
       await showPopup(entry);
     }
   } else {
@@ -289,38 +381,236 @@ export async function execute(rule: Rule, selection: string): Promise<void> {
   }
 }
 
+/**
+ * 保存触发记录并删除多余记录
+ *
+ * @param entry - 记录对象
+ */
+export function saveEntry(entry: Entry): void {
+  entries.current.unshift(entry);
+  if (entries.current.length > historySize.current) {
+    entries.current = entries.current.slice(0, historySize.current);
+  }
+}
+
+/**
+ * 组装脚本运行选项
+ */
+function scriptOptions() {
+  return {
+    nodePath: nodePath.current,
+    pythonPath: pythonPath.current,
+    warmPool: warmPool.current,
+    runtimes: runtimes.current,
+    pipIndexUrl: pipIndexUrl.current,
+    npmRegistry: npmRegistry.current
+  };
+}
+
+/**
+ * 分发脚本或命令返回的结构化结果
+ *
+ * @param rule - 规则对象
+ * @param entry - 记录对象
+ * @returns 是否为结构化结果
+ */
+async function deliverResult(rule: Rule, entry: Entry): Promise<boolean> {
+  try {
+    return await invoke<boolean>('deliver_result', { rule, entry });
+  } catch (error) {
+    if (!entry.quietMode) {
+      await updatePopup({ ...entry, error: typeof error === 'string' ? error : (error as ExecutionError).message });
+    }
+    throw error;
+  }
+}
+
+/**
+ * 检测可用的脚本运行环境
+ *
+ * @param refresh - 是否忽略缓存重新检测
+ * @returns 各运行环境的检测结果
+ */
+export async function detectRuntimes(refresh: boolean = false): Promise<RuntimeStatus[]> {
+  return await invoke<RuntimeStatus[]>('detect_runtimes', { options: scriptOptions(), refresh: refresh });
+}
+
 /**
  * 执行输入的脚本并返回结果
  *
  * @param script - 脚本对象
  * @param data - 数据对象
+ * @param onstart - 脚本启动时的回调函数，参数为执行标识
  * @returns 脚本执行结果
  */
-export async function executeScript(script: Script, data: Data): Promise<string> {
+export async function executeScript(script: Script, data: Data, onstart?: (id: string) => void): Promise<string> {
+  // 接收后端返回的执行标识
+  const onStart = new Channel<string>();
+  onStart.onmessage = (id) => onstart?.(id);
   try {
-    if (script.lang === 'javascript') {
-      const result = await invoke<string>('execute_javascript', {
-        code: script.script,
-        data: JSON.stringify(data),
-        nodePath: nodePath.current
-      });
-      return result;
-    } else if (script.lang === 'python') {
-      const result = await invoke<string>('execute_python', {
-        code: script.script,
-        data: JSON.stringify(data),
-        pythonPath: pythonPath.current
-      });
-      return result;
-    } else {
-      throw new Error(`不支持的脚本语言: ${script.lang}`);
-    }
+    const result = await invoke<string>('execute_script', {
+      script: script,
+      data: data,
+      options: scriptOptions(),
+      onStart: onStart
+    });
+    return result;
   } catch (error) {
     console.error('脚本执行失败:', error);
     throw error;
   }
 }
 
+/**
+ * 安装脚本声明的依赖
+ *
+ * @param script - 脚本对象
+ * @param onstart - 安装开始时的回调函数，参数为执行标识
+ */
+export async function installDependencies(script: Script, onstart?: (id: string) => void): Promise<void> {
+  // 接收后端返回的执行标识
+  const onStart = new Channel<string>();
+  onStart.onmessage = (id) => onstart?.(id);
+  try {
+    await invoke('install_dependencies', { script, options: scriptOptions(), onStart });
+  } catch (error) {
+    console.error('依赖安装失败:', error);
+    throw error;
+  }
+}
+
+/**
+ * 运行脚本的测试用例
+ *
+ * @param script - 脚本对象 (可以是尚未保存的表单内容)
+ * @param onstart - 测试开始时的回调函数，参数为执行标识
+ * @returns 各用例的执行结果
+ */
+export async function runScriptTests(script: Script, onstart?: (id: string) => void): Promise<TestResult[]> {
+  // 接收后端返回的执行标识
+  const onStart = new Channel<string>();
+  onStart.onmessage = (id) => onstart?.(id);
+  try {
+    return await invoke<TestResult[]>('run_script_tests', { script, options: scriptOptions(), onStart });
+  } catch (error) {
+    console.error('脚本测试失败:', error);
+    throw error;
+  }
+}
+
+/**
+ * 执行命令行动作并返回结果
+ *
+ * @param command - 命令对象
+ * @param data - 数据对象
+ * @param onstart - 命令启动时的回调函数，参数为执行标识
+ * @returns 命令的标准输出
+ */
+export async function executeCommand(
+  command: Command,
+  data: Data,
+  onstart?: (id: string) => void
+): Promise<string> {
+  // 接收后端返回的执行标识
+  const onStart = new Channel<string>();
+  onStart.onmessage = (id) => onstart?.(id);
+  try {
+    return await invoke<string>('execute_command', { command, data, onStart });
+  } catch (error) {
+    console.error('命令执行失败:', error);
+    throw error;
+  }
+}
+
+/**
+ * 流式调用大模型并返回完整的回复
+ *
+ * @param request - 对话请求
+ * @param ontoken - 收到新生成的文本时的回调函数
+ * @param onstart - 请求开始时的回调函数，参数为执行标识，可用于取消
+ * @returns 完整的回复
+ */
+export async function streamChat(
+  request: ChatRequest,
+  ontoken: (token: string) => void,
+  onstart?: (id: string) => void
+): Promise<string> {
+  // 接收后端返回的执行标识和生成的文本
+  const onStart = new Channel<string>();
+  onStart.onmessage = (id) => onstart?.(id);
+  const onToken = new Channel<string>();
+  onToken.onmessage = ontoken;
+  return await invoke<string>('stream_chat', { request, onStart, onToken });
+}
+
+/**
+ * 在多轮对话中发送消息，回复逐段返回
+ *
+ * @param session - 对话不存在时用于创建的对话
+ * @param message - 用户消息
+ * @param ontoken - 接收生成文本的回调
+ * @param onstart - 请求开始时接收执行标识的回调，可用于取消
+ * @param onstep - 接收工具调用和结果消息的回调
+ * @returns 完整的回复，使用缓存时不会逐段返回
+ */
+export async function chatSession(
+  session: Session,
+  message: string,
+  ontoken: (token: string) => void,
+  onstart?: (id: string) => void,
+  onstep?: (message: ChatMessage) => void
+): Promise<SessionReply> {
+  const onStart = new Channel<string>();
+  onStart.onmessage = (id) => onstart?.(id);
+  const onToken = new Channel<string>();
+  onToken.onmessage = ontoken;
+  const onStep = new Channel<ChatMessage>();
+  onStep.onmessage = (message) => onstep?.(message);
+  return await invoke<SessionReply>('chat_session', { session, message, onStart, onToken, onStep });
+}
+
+/**
+ * 跳过缓存重新执行记录对应的脚本或提示词动作
+ *
+ * @param entry - 命中缓存的记录
+ */
+export async function rerunAction(entry: Entry): Promise<void> {
+  await invoke('rerun_action', { entry });
+}
+
+/**
+ * 获取保存的多轮对话
+ *
+ * @param id - 对话标识
+ * @returns 对话不存在时返回 null
+ */
+export async function getSession(id: string): Promise<Session | null> {
+  return await invoke<Session | null>('get_session', { id });
+}
+
+/**
+ * 删除保存的多轮对话
+ *
+ * @param id - 对话标识
+ */
+export async function deleteSession(id: string): Promise<void> {
+  try {
+    await invoke('delete_session', { id });
+  } catch (error) {
+    console.error('删除对话失败:', error);
+  }
+}
+
+/**
+ * 取消正在执行的脚本
+ *
+ * @param id - 执行标识
+ * @returns 是否找到对应的执行
+ */
+export async function cancelScript(id: string): Promise<boolean> {
+  return await invoke<boolean>('cancel_execution', { id });
+}
+
 /**
  * 渲染输入的提示词并返回结果
  *
@@ -329,14 +619,17 @@ export async function executeScript(script: Script, data: Data): Promise<string>
  * @returns 渲染结果
  */
 export async function renderPrompt(prompt: Prompt, data: Data): Promise<string> {
-  let result = prompt.prompt || '';
-
-  // 使用正则表达式替换模板参数
-  result = result.replace(/\{\{clipboard\}\}/g, data.clipboard);
-  result = result.replace(/\{\{selection\}\}/g, data.selection);
-  result = result.replace(/\{\{datetime\}\}/g, data.datetime);
+  return await invoke<string>('render_template', { template: prompt.prompt || '', data });
+}
 
-  return result;
+/**
+ * 检查模板的语法
+ *
+ * @param template - 模板内容
+ * @returns 第一个问题及其位置，没有问题时返回 null
+ */
+export async function validateTemplate(template: string): Promise<TemplateIssue | null> {
+  return await invoke<TemplateIssue | null>('validate_template', { template });
 }
 
 /**
@@ -344,10 +637,23 @@ export async function renderPrompt(prompt: Prompt, data: Data): Promise<string>
  *
  * @param entry - 记录对象
  */
-async function showPopup(entry: Entry): Promise<void> {
+export async function showPopup(entry: Entry): Promise<void> {
   try {
     await invoke('show_popup', { payload: JSON.stringify(entry) });
   } catch (error) {
     console.error('显示弹出窗口失败:', error);
   }
 }
+
+/**
+ * 更新弹出窗口内容，不改变窗口位置
+ *
+ * @param entry - 记录对象
+ */
+async function updatePopup(entry: Entry): Promise<void> {
+  try {
+    await invoke('update_popup', { payload: JSON.stringify(entry) });
+  } catch (error) {
+    console.error('更新弹出窗口失败:', error);
+  }
+}
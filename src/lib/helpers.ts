@@ -2,6 +2,7 @@ import { messages } from '$lib/components/Confirm.svelte';
 import { modals } from '$lib/components/Modal.svelte';
 import { getLocale } from '$lib/paraglide/runtime';
 import { getCurrentWindow } from '@tauri-apps/api/window';
+import { marked } from 'marked';
 import type { ActionReturn } from 'svelte/action';
 import type { Instance, Props } from 'tippy.js';
 import tippy from 'tippy.js';
@@ -84,3 +85,154 @@ export function unfreeze(): Promise<void> {
   }
   return Promise.reject();
 }
+
+// 渲染脚本和模型输出时保留的标签
+const ALLOWED_TAGS = new Set([
+  'a',
+  'abbr',
+  'b',
+  'blockquote',
+  'br',
+  'code',
+  'dd',
+  'del',
+  'details',
+  'div',
+  'dl',
+  'dt',
+  'em',
+  'h1',
+  'h2',
+  'h3',
+  'h4',
+  'h5',
+  'h6',
+  'hr',
+  'i',
+  'img',
+  'ins',
+  'kbd',
+  'li',
+  'mark',
+  'ol',
+  'p',
+  'pre',
+  's',
+  'small',
+  'span',
+  'strong',
+  'sub',
+  'summary',
+  'sup',
+  'table',
+  'tbody',
+  'td',
+  'tfoot',
+  'th',
+  'thead',
+  'tr',
+  'u',
+  'ul'
+]);
+
+// 连同内容一起移除的标签，其余未允许的标签只保留内容
+const DROPPED_TAGS = new Set([
+  'base',
+  'button',
+  'embed',
+  'form',
+  'frame',
+  'frameset',
+  'iframe',
+  'input',
+  'link',
+  'math',
+  'meta',
+  'noscript',
+  'object',
+  'script',
+  'select',
+  'style',
+  'svg',
+  'template',
+  'textarea',
+  'title'
+]);
+
+// 保留的属性
+const ALLOWED_ATTRS = new Set(['href', 'src', 'alt', 'title', 'colspan', 'rowspan', 'align', 'start', 'open']);
+
+/**
+ * 判断链接地址是否安全，只允许网页、邮件、页内锚点和内嵌图片
+ *
+ * @param name - 属性名称
+ * @param value - 链接地址
+ */
+function isSafeUrl(name: string, value: string): boolean {
+  const url = value.trim().toLowerCase();
+  if (name === 'src' && /^data:image\/(png|jpeg|gif|webp);/.test(url)) {
+    return true;
+  }
+  return /^(https?:|mailto:|#)/.test(url);
+}
+
+/**
+ * 清理元素及其子节点，只保留允许的标签和属性
+ *
+ * @param parent - 父节点
+ */
+function sanitizeNode(parent: Node): void {
+  for (const node of Array.from(parent.childNodes)) {
+    if (node.nodeType === Node.COMMENT_NODE) {
+      node.remove();
+      continue;
+    }
+    if (!(node instanceof Element)) {
+      continue;
+    }
+    const tag = node.localName;
+    if (DROPPED_TAGS.has(tag) || node.namespaceURI !== 'http://www.w3.org/1999/xhtml') {
+      node.remove();
+      continue;
+    }
+    sanitizeNode(node);
+    if (!ALLOWED_TAGS.has(tag)) {
+      node.replaceWith(...Array.from(node.childNodes));
+      continue;
+    }
+    for (const { name, value } of Array.from(node.attributes)) {
+      if (!ALLOWED_ATTRS.has(name) || ((name === 'href' || name === 'src') && !isSafeUrl(name, value))) {
+        node.removeAttribute(name);
+      }
+    }
+    if (tag === 'a') {
+      node.setAttribute('target', '_blank');
+      node.setAttribute('rel', 'noopener noreferrer');
+    }
+  }
+}
+
+/**
+ * 清理 HTML，移除脚本、事件属性和不安全的链接
+ *
+ * 脚本和模型的输出不可信，插入页面前必须清理，否则可以调用应用的全部命令
+ *
+ * @param html - HTML 文本
+ * @returns 清理后的 HTML 文本
+ */
+export function sanitizeHtml(html: string): string {
+  // DOMParser 解析的文档不会执行脚本或加载资源
+  const doc = new DOMParser().parseFromString(html, 'text/html');
+  sanitizeNode(doc.body);
+  return doc.body.innerHTML;
+}
+
+/**
+ * 将 Markdown 渲染为清理后的 HTML
+ *
+ * @param markdown - Markdown 文本
+ * @returns 清理后的 HTML 文本
+ */
+export function renderMarkdown(markdown: string): string {
+  return sanitizeHtml(marked.parse(markdown, { async: false }));
+}
@@ -1,7 +1,7 @@
-import { execute } from '$lib/executor';
-import { match } from '$lib/matcher';
+import { execute, saveEntry } from '$lib/executor';
+import { getCaseLabel, match } from '$lib/matcher';
 import { shortcuts } from '$lib/stores.svelte';
-import type { Rule } from '$lib/types';
+import type { Entry, Rule } from '$lib/types';
 import { invoke } from '@tauri-apps/api/core';
 import { listen } from '@tauri-apps/api/event';
 import { getCurrentWindow } from '@tauri-apps/api/window';
@@ -20,10 +20,22 @@ export class Manager {
   private async initialize(): Promise<void> {
     if (getCurrentWindow().label === 'main') {
       try {
-        // 监听来自 Rust 后端的快捷键触发事件
+        // 监听后端无法处理、需要由前端匹配或执行的快捷键触发事件
         await listen('shortcut-triggered', async (event) => {
-          const payload = event.payload as { key: string; selection: string };
-          await this.handleShortcutEvent(payload.key, payload.selection);
+          const payload = event.payload as {
+            key: string;
+            selection: string;
+            rule?: string | null;
+            app?: string | null;
+          };
+          await this.handleShortcutEvent(payload.key, payload.selection, payload.rule, payload.app);
+        });
+        // 监听后端已执行完毕的动作，保存触发记录
+        await listen('shortcut-executed', (event) => {
+          const payload = event.payload as { case: string; entry: Entry };
+          const entry = payload.entry;
+          entry.caseLabel = entry.caseLabel ?? getCaseLabel(payload.case);
+          saveEntry(entry);
         });
       } catch (error) {
         console.error('初始化快捷键事件监听器失败:', error);
@@ -36,16 +48,28 @@ export class Manager {
    *
    * @param key - 触发的快捷键
    * @param selection - 选中的文本
+   * @param ruleId - 后端已匹配的规则标识
+   * @param app - 触发时前台应用的名称
    */
-  private async handleShortcutEvent(key: string, selection: string): Promise<void> {
+  private async handleShortcutEvent(
+    key: string,
+    selection: string,
+    ruleId?: string | null,
+    app?: string | null
+  ): Promise<void> {
     try {
       // 获取所有绑定到该键位的规则
       const rules = shortcuts.current[key];
       if (!rules || rules.length === 0) {
         return;
       }
-      // 匹配要执行的动作
-      const rule = await match(selection, rules);
+      // 优先使用后端已匹配的规则，否则匹配要执行的动作
+      let rule = ruleId ? (rules.find((r) => r.id === ruleId) ?? null) : null;
+      if (rule) {
+        rule.caseLabel = getCaseLabel(rule.case);
+      } else {
+        rule = await match(selection, rules);
+      }
       if (rule === null) {
         console.warn('没有匹配的规则');
         return;
@@ -56,7 +80,7 @@ export class Manager {
         return;
       }
       // 执行动作
-      await execute(rule, selection);
+      await execute(rule, selection, app);
     } catch (error) {
       console.error('处理快捷键事件失败:', error);
     }
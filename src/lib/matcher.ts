@@ -2,7 +2,8 @@ import { predict } from '$lib/classifier';
 import { MODEL_MARK, REGEXP_MARK } from '$lib/constants';
 import { m } from '$lib/paraglide/messages';
 import { models, regexps } from '$lib/stores.svelte';
-import type { Model, Option, Rule } from '$lib/types';
+import type { Classification, Model, Option, Rule } from '$lib/types';
+import { invoke } from '@tauri-apps/api/core';
 import { ModelOperations, type ModelResult } from '@vscode/vscode-languagedetection';
 import { memoize } from 'es-toolkit/function';
 import { franc } from 'franc-min';
@@ -279,6 +280,32 @@ const findBuiltinCase = memoize((_case: string) => [...GENERAL_CASES, ...TEXT_CA
 const findNaturalCase = memoize((_case: string) => NATURAL_CASES.find((c) => c.value === _case));
 const findProgrammingCase = memoize((_case: string) => PROGRAMMING_CASES.find((c) => c.value === _case));
 
+/**
+ * 获取文本类型的显示标签
+ *
+ * @param _case - 文本类型
+ * @returns 文本类型标签，未找到则返回`undefined`
+ */
+export function getCaseLabel(_case: string): string | undefined {
+  if (_case.startsWith(REGEXP_MARK)) {
+    return _case.substring(REGEXP_MARK.length);
+  }
+  if (_case.startsWith(MODEL_MARK)) {
+    return _case.substring(MODEL_MARK.length);
+  }
+  return (findBuiltinCase(_case) ?? findNaturalCase(_case) ?? findProgrammingCase(_case))?.label;
+}
+
+/**
+ * 由后端识别文本包含的全部内置文本类型
+ *
+ * @param text - 待识别的文本
+ * @returns 各类型匹配的片段和置信度，按置信度从高到低排列
+ */
+export async function classifyText(text: string): Promise<Classification[]> {
+  return await invoke<Classification[]>('classify_text', { text });
+}
+
 /**
  * 根据文本类型匹配要执行的快捷键动作
  *
@@ -1,5 +1,7 @@
 import { manager } from '$lib/manager';
-import type { Entry, Rule, Model, Prompt, Regexp, Script } from '$lib/types';
+import type { Command, Entry, Rule, Model, Prompt, Regexp, Runtime, Script, ScriptLibrary } from '$lib/types';
+import { invoke } from '@tauri-apps/api/core';
+import { listen } from '@tauri-apps/api/event';
 import { getCurrentWindow } from '@tauri-apps/api/window';
 import { LazyStore } from '@tauri-apps/plugin-store';
 import { untrack } from 'svelte';
@@ -117,12 +119,73 @@ export const nodePath = persisted<string>('nodePath', '');
 // Python 路径
 export const pythonPath = persisted<string>('pythonPath', '');
 
+// 是否启用常驻进程池
+export const warmPool = persisted<boolean>('warmPool', false);
+
+// Python 包索引地址
+export const pipIndexUrl = persisted<string>('pipIndexUrl', '');
+
+// npm 源地址
+export const npmRegistry = persisted<string>('npmRegistry', '');
+
+// 自定义脚本运行环境
+export const runtimes = persisted<Runtime[]>('runtimes', []);
+
+// 脚本目录，为空时使用应用数据目录下的 scripts 目录
+export const scriptsDir = persisted<string>('scriptsDir', '');
+
+// 是否在执行记录中保存输入文本
+export const auditInput = persisted<boolean>('auditInput', false);
+
+// 密钥名称，密钥值只保存在系统钥匙串中
+export const secrets = persisted<string[]>('secrets', []);
+
+// 脚本目录中加载的脚本，目录变化时由后端推送
+export const library: ScriptLibrary = $state({ dir: null, scripts: [], issues: [] });
+invoke<ScriptLibrary>('get_script_library')
+  .then((value) => Object.assign(library, value))
+  .catch((error) => console.error('加载脚本目录失败:', error));
+listen<ScriptLibrary>('script-library', (event) => Object.assign(library, event.payload));
+
+/**
+ * 根据标识查找脚本，名称相同时存储中的脚本优先
+ *
+ * @param id - 脚本标识
+ */
+export function findScript(id: string): Script | undefined {
+  return scripts.current.find((s) => s.id === id) ?? library.scripts.find((s) => s.id === id);
+}
+
 // Ollama 服务地址
 export const ollamaHost = persisted<string>('ollamaHost', '');
 
+// LM Studio 服务地址
+export const lmStudioHost = persisted<string>('lmStudioHost', '');
+
+// 兼容 OpenAI 接口的服务地址
+export const openaiBaseUrl = persisted<string>('openaiBaseUrl', '');
+
+// 兼容 OpenAI 接口的服务使用的 API 密钥名称 (密钥值保存在系统钥匙串中)
+export const openaiApiKey = persisted<string>('openaiApiKey', '');
+
+// 多轮对话的上下文长度 (token)
+export const contextTokens = persisted<number>('contextTokens', 8192);
+
+// 是否缓存纯脚本和温度为 0 的提示词的结果
+export const responseCache = persisted<boolean>('responseCache', false);
+
+// 缓存有效期 (小时)
+export const cacheTtl = persisted<number>('cacheTtl', 168);
+
+// 缓存大小上限 (MiB)
+export const cacheSize = persisted<number>('cacheSize', 50);
+
 // 历史记录保留条数
 export const historySize = persisted<number>('historySize', 5);
 
+// 同时运行的动作数量上限
+export const maxConcurrency = persisted<number>('maxConcurrency', 2);
+
 // 快捷键组
 export const shortcuts = persisted<Record<string, Rule[]>>(
   'shortcuts',
@@ -151,5 +214,8 @@ export const scripts = persisted<Script[]>('scripts', []);
 // 提示词
 export const prompts = persisted<Prompt[]>('prompts', []);
 
+// 命令行动作
+export const commands = persisted<Command[]>('commands', []);
+
 // 触发记录
 export const entries = persisted<Entry[]>('entries', []);
@@ -53,21 +53,45 @@ export type Entry = {
   /** 触发动作 */
   actionLabel?: string;
   /** 动作类型 */
-  actionType?: 'script' | 'prompt';
+  actionType?: 'script' | 'prompt' | 'command';
   /** 执行结果 (脚本返回值/提示词) */
   result?: string;
+  /** 执行结果的渲染格式 (脚本返回结构化结果时设置) */
+  resultFormat?: 'text' | 'markdown' | 'html';
   /** 脚本语言 */
-  scriptLang?: 'javascript' | 'python';
+  scriptLang?: string;
   /** 静默执行 */
   quietMode?: boolean;
   /** 模型供应 */
-  provider?: 'ollama' | 'lmstudio';
+  provider?: Provider;
   /** 模型名称 */
   model?: string;
   /** 系统提示词 */
   systemPrompt?: string;
-  /** 响应内容 */
-  response?: string;
+  /** 作为工具提供给模型的脚本标识 */
+  tools?: string[];
+  /** 采样温度 */
+  temperature?: number;
+  /** 执行结果是否来自缓存 */
+  cached?: boolean;
+  /** 脚本执行标识 (执行期间用于接收实时输出) */
+  executionId?: string;
+  /** 执行失败时的错误信息 */
+  error?: string;
+};
+
+/**
+ * 文本类型的识别结果
+ */
+export type Classification = {
+  /** 文本类型标识 */
+  case: string;
+  /** 是否整段文本都是该类型 */
+  exact: boolean;
+  /** 置信度 (0 ~ 1) */
+  confidence: number;
+  /** 匹配的片段 (按 Unicode 字符计算偏移) */
+  spans: { start: number; end: number }[];
 };
 
 /**
@@ -93,11 +117,335 @@ export type Script = {
   /** 脚本标识 */
   id: string;
   /** 脚本语言 */
-  lang: 'javascript' | 'python';
+  lang: string;
   /** 脚本内容 */
   script: string;
   /** 静默执行 */
   quietMode?: boolean;
+  /** 返回值只取决于选中的文本 (启用缓存时可以复用之前的结果) */
+  pure?: boolean;
+  /** 超时时间 (秒) */
+  timeout?: number;
+  /** 沙箱级别 */
+  sandbox?: 'none' | 'restricted' | 'offline';
+  /** 正在运行时再次触发的处理方式 (忽略、排队或重新开始) */
+  concurrency?: 'drop' | 'queue' | 'restart';
+  /** 依赖的包 (安装在脚本独立的环境中) */
+  dependencies?: string[];
+  /** 额外设置的环境变量 */
+  env?: Record<string, string>;
+  /** 使用的密钥名称 (运行时以同名环境变量传递) */
+  secrets?: string[];
+  /** 测试用例 */
+  tests?: TestCase[];
+  /** WebAssembly 模块的运行选项 */
+  wasm?: WasmOptions;
+  /** 授予脚本的宿主 API 权限 */
+  permissions?: HostPermission[];
+  /** 从脚本目录加载时的文件路径 */
+  source?: string;
+};
+
+/**
+ * 脚本可以使用的宿主 API 权限
+ */
+export type HostPermission = 'clipboard' | 'notify' | 'prompt' | 'run_action' | 'ask_user';
+
+/**
+ * 脚本测试用例
+ */
+export type TestCase = {
+  /** 用例名称 */
+  name?: string;
+  /** 作为选中文本传入的输入 */
+  input: string;
+  /** 剪贴板文本 */
+  clipboard?: string;
+  /** 期望的返回值 */
+  output?: string | null;
+  /** 期望的错误信息 (实际错误信息包含该文本即通过) */
+  error?: string | null;
+};
+
+/**
+ * 测试用例的执行结果
+ */
+export type TestResult = {
+  /** 用例序号 */
+  index: number;
+  /** 用例名称 */
+  name: string;
+  /** 是否通过 */
+  passed: boolean;
+  /** 实际返回值 */
+  output?: string | null;
+  /** 实际的错误信息 */
+  error?: string | null;
+  /** 返回值与期望不同时的逐行差异 */
+  diff: { tag: 'equal' | 'delete' | 'insert'; text: string }[];
+  /** 执行耗时 (毫秒) */
+  durationMs: number;
+};
+
+/**
+ * WebAssembly 模块的运行选项
+ */
+export type WasmOptions = {
+  /** 燃料上限 (大致等于可执行的指令数) */
+  fuel?: number;
+  /** 线性内存上限 (MiB) */
+  memoryMb?: number;
+  /** 允许只读访问的目录 */
+  readDirs?: string[];
+  /** 允许读写的目录 */
+  writeDirs?: string[];
+};
+
+/**
+ * 脚本目录的解析问题
+ */
+export type LibraryIssue = {
+  /** 文件路径 */
+  path: string;
+  /** 出错的行号 */
+  line?: number | null;
+  /** 错误信息 */
+  message: string;
+};
+
+/**
+ * 模板的语法问题
+ */
+export type TemplateIssue = {
+  /** 出错的行号 */
+  line: number;
+  /** 出错的列号 */
+  column: number;
+  /** 错误信息 */
+  message: string;
+};
+
+/**
+ * 脚本目录的加载结果
+ */
+export type ScriptLibrary = {
+  /** 脚本目录 */
+  dir?: string | null;
+  /** 成功加载的脚本 */
+  scripts: Script[];
+  /** 解析失败的文件 */
+  issues: LibraryIssue[];
+};
+
+/**
+ * 脚本运行环境
+ */
+export type Runtime = {
+  /** 运行环境标识，与内置运行环境相同时覆盖内置的 */
+  id: string;
+  /** 显示名称 */
+  name: string;
+  /** 可执行的脚本语言 */
+  lang: string;
+  /** 可执行文件名称 */
+  executables: string[];
+  /** 追加到 PATH 中的查找路径 */
+  searchPaths?: string[];
+  /** 查询版本的参数 */
+  versionArgs?: string[];
+  /** 调用参数模板 */
+  args: string[];
+  /** 运行器源码 */
+  runner?: string;
+  /** 脚本文件扩展名 */
+  extension?: string;
+  /** 数据传递方式 */
+  input?: 'json' | 'text';
+  /** 额外的环境变量 */
+  env?: Record<string, string>;
+};
+
+/**
+ * 运行环境的检测结果
+ */
+export type RuntimeStatus = {
+  /** 运行环境标识 */
+  id: string;
+  /** 显示名称 */
+  name: string;
+  /** 可执行的脚本语言 */
+  lang: string;
+  /** 可执行文件路径 */
+  path?: string;
+  /** 版本信息 */
+  version?: string;
+  /** 可执行文件的 CPU 架构 */
+  arch?: string;
+  /** 是否使用自定义路径 */
+  custom: boolean;
+  /** 是否可用于执行脚本 */
+  available: boolean;
+  /** 发现的问题 */
+  problems: string[];
+};
+
+/**
+ * 命令行动作
+ */
+export type Command = {
+  /** 命令标识 */
+  id: string;
+  /** 程序名称或路径 */
+  program: string;
+  /** 参数模板 (每项一个参数) */
+  args?: string[];
+  /** 选中文本的传递方式 */
+  input?: 'stdin' | 'argument' | 'file';
+  /** 视为成功的退出码 */
+  successCodes?: number[];
+  /** 标准错误输出的处理方式 */
+  stderr?: 'ignore' | 'append' | 'fail';
+  /** 静默执行 */
+  quietMode?: boolean;
+  /** 超时时间 (秒) */
+  timeout?: number;
+  /** 沙箱级别 */
+  sandbox?: 'none' | 'restricted' | 'offline';
+  /** 正在运行时再次触发的处理方式 (忽略、排队或重新开始) */
+  concurrency?: 'drop' | 'queue' | 'restart';
+  /** 额外设置的环境变量 */
+  env?: Record<string, string>;
+  /** 使用的密钥名称 (运行时以同名环境变量传递) */
+  secrets?: string[];
+};
+
+/**
+ * 一次执行的审计记录
+ */
+export type AuditRecord = {
+  /** 记录标识 */
+  id: string;
+  /** 动作类型 */
+  actionType: 'script' | 'command';
+  /** 动作标识 */
+  action: string;
+  /** 开始时间 */
+  startedAt: string;
+  /** 执行耗时 (毫秒) */
+  durationMs: number;
+  /** 执行结果的状态 */
+  status: 'success' | 'failure' | 'timeout' | 'cancelled';
+  /** 子进程的退出码 */
+  exitCode?: number | null;
+  /** 运行环境名称 */
+  runtime?: string | null;
+  /** 可执行文件路径 */
+  program?: string | null;
+  /** 运行环境版本 */
+  version?: string | null;
+  /** 标准输出 (超出上限的部分被截断) */
+  stdout: string;
+  /** 标准错误 (超出上限的部分被截断) */
+  stderr: string;
+  /** 输出是否被截断 */
+  truncated: boolean;
+  /** 输入文本的字符数 */
+  inputLength: number;
+  /** 输入文本 (仅在开启记录输入时保存) */
+  input?: string;
+  /** 执行失败时的错误信息 */
+  error?: string;
+};
+
+/**
+ * 脚本执行错误
+ */
+export type ExecutionError = {
+  /** 错误类型 */
+  kind: 'timeout' | 'cancelled';
+  /** 错误信息 */
+  message: string;
+};
+
+/**
+ * 大模型服务的提供方 (openai 为兼容 OpenAI 接口的服务)
+ */
+export type Provider = 'ollama' | 'lmstudio' | 'openai';
+
+/**
+ * 对话消息
+ */
+export type ChatMessage = {
+  /** 角色 */
+  role: 'system' | 'user' | 'assistant' | 'tool';
+  /** 消息内容 */
+  content: string;
+  /** 模型请求的工具调用 */
+  toolCalls?: ToolCall[];
+  /** 工具结果对应的调用标识 */
+  toolCallId?: string;
+  /** 返回结果的工具名称 */
+  name?: string;
+};
+
+/**
+ * 模型请求的工具调用
+ */
+export type ToolCall = {
+  /** 调用标识 */
+  id: string;
+  /** 工具名称 */
+  name: string;
+  /** 调用参数 */
+  arguments: unknown;
+};
+
+/**
+ * 对话请求
+ */
+export type ChatRequest = {
+  /** 提供方 */
+  provider: Provider;
+  /** 模型名称 */
+  model: string;
+  /** 对话消息 */
+  messages: ChatMessage[];
+  /** 采样温度 */
+  temperature?: number;
+};
+
+/**
+ * 多轮对话 (标识与触发记录相同)
+ */
+export type Session = {
+  /** 对话标识 */
+  id: string;
+  /** 提供方 */
+  provider: Provider;
+  /** 模型名称 */
+  model: string;
+  /** 全部消息 (包括系统提示词和工具调用) */
+  messages: ChatMessage[];
+  /** 作为工具提供给模型的脚本标识 */
+  tools?: string[];
+  /** 创建对话的提示词标识 (用于缓存第一条回复) */
+  action?: string;
+  /** 采样温度 */
+  temperature?: number;
+  /** 第一条回复是否来自缓存 */
+  cached?: boolean;
+  /** 最后更新时间 */
+  updatedAt?: string;
+};
+
+/**
+ * 多轮对话中模型的回复
+ */
+export type SessionReply = {
+  /** 回复内容 */
+  content: string;
+  /** 是否来自缓存 */
+  cached: boolean;
 };
 
 /**
@@ -107,13 +455,17 @@ export type Prompt = {
   /** 提示词标识 */
   id: string;
   /** 模型供应 */
-  provider: 'ollama' | 'lmstudio';
+  provider: Provider;
   /** 模型名称 */
   model: string;
   /** 提示词内容 */
   prompt: string;
   /** 系统提示词 */
   systemPrompt?: string;
+  /** 作为工具提供给模型的脚本标识 */
+  tools?: string[];
+  /** 采样温度 (为 0 时启用缓存后可以复用之前的回复) */
+  temperature?: number;
 };
 
 /**